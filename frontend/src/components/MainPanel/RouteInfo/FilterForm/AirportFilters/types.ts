@@ -1,18 +1,33 @@
+import type { ParsedElevationRange, ParsedRange, RunwaySurface } from '../../types';
+import type { DistanceUnitKind } from '../../../../../settings/units';
+
 export interface ParsedAirportFilters {
   icao?: string;
-  airportType?: AirportType;
+  airportType?: AirportType | AirportType[];
+  excludedAirportTypes?: AirportType[];
   runwayLength?: ParsedRunwayLength;
+  runways?: ParsedRunwayFilters;
+  elevation?: ParsedElevationRange;
+  services?: ParsedServiceFilters;
+  radius?: ParsedRadiusFilter;
+  area?: ParsedAreaFilter;
   countries?: string[];
 }
 
 export interface ParsedSpeed {
   value: number,
   type: SpeedType,
+  altitudeFT?: number,
+  isaDeviationC?: number,
 }
 
 export const enum SpeedType {
   Mach = "mach",
+  // True airspeed
   Knots = "knots",
+  Kias = "kias",
+  Kmh = "kmh",
+  Mph = "mph",
 }
 
 export interface AirportTypes {
@@ -32,6 +47,35 @@ export interface ParsedRunwayLength {
   selector: LengthSelector
 }
 
+export interface ParsedRunwayFilters {
+  length?: ParsedRange<number>,
+  width?: ParsedRange<number>,
+  surfaces?: RunwaySurface[],
+  lightedOnly?: boolean,
+  excludeClosed?: boolean,
+  minCount?: number,
+  match?: "any" | "all",
+}
+
+export interface ParsedRadiusFilter extends ParsedRange<number> {
+  center: string | { lat: number, lon: number },
+  unit?: DistanceUnitKind,
+}
+
+// Bounding boxes are in the form of [west, south, east, north]
+export type ParsedAreaFilter =
+  | { bbox: [number, number, number, number] }
+  | { geojson: object }
+  | { region: string };
+
+export type FrequencyType = "atis" | "arrival" | "departure" | "arrivalDeparture" | "ground" | "tower" | "unicom";
+
+export interface ParsedServiceFilters {
+  towered?: boolean,
+  hasAll?: FrequencyType[],
+  hasAny?: FrequencyType[],
+}
+
 export const enum LengthSelector {
   LessThan = "lt",
   Equal = "eq",
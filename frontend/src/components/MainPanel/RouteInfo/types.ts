@@ -1,4 +1,5 @@
 import type { ParsedSpeed, ParsedAirportFilters } from './FilterForm/AirportFilters/types';
+import type { LengthUnitKind } from '../../../settings/units';
 
 export type Ok<T> = {
   kind: "ok",
@@ -11,18 +12,82 @@ export type Err<T> = {
 }
 
 export type Result<T, E> = Ok<T> | Err<E>;
+
+export interface ApiError {
+  field: string,
+  code: "malformed_request" | "invalid_value" | "invalid_range" | "invalid_expression" | "invalid_area" | "unknown_airport",
+  message: string,
+  position?: number,
+}
 export type InputResult = Result<string, string>;
 
 export interface FindRoutesQuery {
-  speed: ParsedSpeed;
+  // Required unless an aircraft is selected
+  speed?: ParsedSpeed;
+  aircraft?: string;
   departure?: ParsedAirportFilters;
   arrival?: ParsedAirportFilters;
   timeDist?: ParsedTimeDist;
+  constraints?: RouteConstraint[];
+  geometry?: GeometryOptions;
+  distance?: DistanceOptions;
+  profile?: FlightProfile;
+  wind?: WindSource;
+}
+
+export type WindSource =
+  | { type: "uniform", value: Wind }
+  | { type: "aloft", value: WindLevel[] }
+  // The forecast file in the data directory, which is listed by /api/winds
+  | { type: "forecast" };
+
+export interface Wind {
+  // The true direction the wind is blowing from
+  direction: number,
+  speedKT: number,
+}
+
+export interface WindLevel extends Wind {
+  altitudeFT: number,
 }
 
+// Unset fields default to a typical airliner
+export interface FlightProfile {
+  taxiOutMin?: number,
+  climbRateFPM?: number,
+  climbSpeedKT?: number,
+  cruiseAltitudeFT?: number,
+  descentRateFPM?: number,
+  descentSpeedKT?: number,
+  approachMin?: number,
+  taxiInMin?: number,
+}
+
+export interface DistanceOptions {
+  model?: "spherical" | "wgs84",
+  path?: "greatCircle" | "rhumb",
+}
+
+export interface GeometryOptions {
+  spacing?: number,
+  unit?: "nm" | "mi" | "km",
+}
+
+export type RouteConstraint =
+  | ParsedTimeDist
+  | { type: "course", value: CourseSector | { min: number, max: number } }
+  | { type: "elevationChange", value: ParsedElevationChange };
+
+export type CourseSector = "north" | "northeast" | "east" | "southeast" | "south" | "southwest" | "west" | "northwest";
+
 export interface Airport {
   icao: string,
+  name: string,
+  iataCode?: string,
+  gpsCode?: string,
+  municipality?: string,
   position: CoordPos,
+  elevationFT?: number,
   runways: Runway[],
   frequencies: Frequencies,
   countryName: string,
@@ -36,10 +101,15 @@ export interface CoordPos {
 export interface Runway {
   lengthFT?: number,
   widthFT?: number,
+  surface: RunwaySurface,
+  lighted: boolean,
+  closed: boolean,
   heMarker?: RunwayMarker,
   leMarker?: RunwayMarker,
 }
 
+export type RunwaySurface = "paved" | "unpaved" | "water" | "unknown";
+
 export interface RunwayMarker {
   name: string,
   position: CoordPos,
@@ -61,6 +131,31 @@ export interface Route {
   to: Airport,
   distance: number,
   time: Time,
+  initialCourse: number,
+  finalCourse: number,
+  geometry?: RouteGeometry,
+  flightTime?: FlightTime,
+  wind?: RouteWind,
+}
+
+export interface RouteWind {
+  groundSpeedKT: number,
+  // Negative for a tailwind
+  headwindKT: number,
+  crosswindKT: number,
+  time: Time,
+}
+
+export interface FlightTime {
+  block: Time,
+  air: Time,
+  cruiseAltitudeFT: number,
+}
+
+export interface RouteGeometry {
+  path: CoordPos[][],
+  midpoint: CoordPos,
+  vertex: CoordPos,
 }
 
 export interface Time {
@@ -73,6 +168,14 @@ export interface ParsedRange<T> {
   max?: T,
 }
 
+export interface ParsedElevationRange extends ParsedRange<number> {
+  unit?: LengthUnitKind,
+}
+
+export interface ParsedElevationChange extends ParsedElevationRange {
+  absolute?: boolean,
+}
+
 export type TimeDistVariant = "time" | "dist";
 
 export interface ParsedTimeDist {
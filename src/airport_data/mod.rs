@@ -1,23 +1,221 @@
 pub mod our_airports;
+pub mod spatial_index;
+pub mod suggest_index;
 
+#[cfg(test)]
+pub mod test_data;
+
+use crate::geo;
+use crate::geo::geodesic::{self, DistanceOptions};
 use anyhow::{anyhow, Result};
 use chrono::{Duration, NaiveDate, Utc};
 use serde::de::{Deserialize, Deserializer, Visitor};
 use serde::ser::{Serialize, Serializer};
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
+use spatial_index::SpatialIndex;
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, BufWriter};
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::result;
+use suggest_index::{Country, SuggestIndex};
 
 pub trait AirportData {
     fn is_up_to_date(&self) -> bool;
     fn update(&mut self) -> Result<()>;
 
     fn load(&self) -> Result<Vec<Airport>>;
+    fn load_navaids(&self) -> Result<Vec<Navaid>>;
+}
+
+/// All loaded airports, sorted by their ICAO code, along with lookup indices over them.
+pub struct Airports {
+    airports: Vec<Airport>,
+    spatial: SpatialIndex,
+    /// Every navaid, whether or not it's associated with an airport.
+    navaids: Vec<Navaid>,
+    navaid_spatial: SpatialIndex,
+    suggest: SuggestIndex,
+    /// Indices of airports by their IATA code.
+    iata_codes: HashMap<String, usize>,
+    /// Indices of airports by their GPS code.
+    gps_codes: HashMap<String, usize>,
+}
+
+impl Airports {
+    pub fn new(airports: Vec<Airport>, navaids: Vec<Navaid>) -> Self {
+        let spatial = SpatialIndex::new(airports.iter().map(|arpt| arpt.position));
+        let navaid_spatial = SpatialIndex::new(navaids.iter().map(|navaid| navaid.position));
+        let suggest = SuggestIndex::new(&airports);
+
+        let index_by = |code: fn(&Airport) -> Option<&String>| {
+            airports
+                .iter()
+                .enumerate()
+                .filter_map(|(i, arpt)| code(arpt).map(|code| (code.to_ascii_uppercase(), i)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let iata_codes = index_by(|arpt| arpt.iata_code.as_ref());
+        let gps_codes = index_by(|arpt| arpt.gps_code.as_ref());
+
+        Self {
+            airports,
+            spatial,
+            navaids,
+            navaid_spatial,
+            suggest,
+            iata_codes,
+            gps_codes,
+        }
+    }
+
+    /// Find an airport by its ICAO code.
+    pub fn find_by_icao(&self, icao: &str) -> Option<&Airport> {
+        self.airports
+            .binary_search_by(|arpt| arpt.icao.as_str().cmp(icao))
+            .ok()
+            .map(|index| &self.airports[index])
+    }
+
+    /// Find an airport by its ICAO, IATA or GPS code, in that order of precedence.
+    pub fn find_by_ident(&self, ident: &str) -> Option<&Airport> {
+        let ident = ident.trim().to_ascii_uppercase();
+
+        self.find_by_icao(&ident)
+            .or_else(|| self.iata_codes.get(&ident).map(|&i| &self.airports[i]))
+            .or_else(|| self.gps_codes.get(&ident).map(|&i| &self.airports[i]))
+    }
+
+    /// Get the airports that best match `query` by their codes, name or municipality, from best to worst.
+    pub fn suggest_airports(&self, query: &str, limit: usize) -> Vec<&Airport> {
+        self.suggest
+            .airports(query, &self.airports, limit)
+            .into_iter()
+            .map(|i| &self.airports[i])
+            .collect()
+    }
+
+    /// Get the countries that best match `query` by their name or code, from best to worst.
+    #[inline(always)]
+    pub fn suggest_countries(&self, query: &str, limit: usize) -> Vec<&Country> {
+        self.suggest.countries(query, limit)
+    }
+
+    /// Get all airports within `radius_nm` of `center` when measured with `options`, in ICAO order.
+    pub fn within_radius(
+        &self,
+        center: Position,
+        radius_nm: f32,
+        options: DistanceOptions,
+    ) -> Vec<&Airport> {
+        self.spatial
+            .candidates_within(center, radius_nm)
+            .into_iter()
+            .map(|i| &self.airports[i])
+            .filter(|arpt| geodesic::distance_nm(center, arpt.position, options) <= radius_nm)
+            .collect()
+    }
+
+    /// Get all navaids within `radius_nm` of `center` along with their distance in nautical miles,
+    /// sorted from closest to furthest.
+    pub fn navaids_within(&self, center: Position, radius_nm: f32) -> Vec<(&Navaid, f32)> {
+        let mut results = self
+            .navaid_spatial
+            .candidates_within(center, radius_nm)
+            .into_iter()
+            .map(|i| {
+                let navaid = &self.navaids[i];
+                let distance =
+                    geodesic::distance_nm(center, navaid.position, DistanceOptions::default());
+
+                (navaid, distance)
+            })
+            .filter(|&(_, distance)| distance <= radius_nm)
+            .collect::<Vec<_>>();
+
+        results.sort_by(|(_, x), (_, y)| x.partial_cmp(y).unwrap_or(Ordering::Equal));
+        results
+    }
+
+    /// Get up to `count` airports accepted by `matches` that are closest to `center`, along with
+    /// their distance in nautical miles when measured with `options`. Results are sorted from
+    /// closest to furthest.
+    ///
+    /// The search starts near `center` and widens until enough airports have been found,
+    /// so `matches` is only called on airports that could be among the results.
+    pub fn nearest<F>(
+        &self,
+        center: Position,
+        count: usize,
+        options: DistanceOptions,
+        matches: F,
+    ) -> Vec<(&Airport, f32)>
+    where
+        F: Fn(&Airport) -> bool,
+    {
+        const INITIAL_RADIUS_NM: f32 = 50.0;
+        // Half of the Earth's circumference reaches every point along a great circle
+        const MAX_RADIUS_NM: f32 = geo::EARTH_RADIUS_NM * std::f32::consts::PI;
+
+        let mut results = Vec::new();
+
+        if count == 0 {
+            return results;
+        }
+
+        let mut searched_nm = -1.0;
+        let mut radius_nm = INITIAL_RADIUS_NM;
+
+        loop {
+            // Airports within the previous radius have already been checked
+            for i in self.spatial.candidates_within(center, radius_nm) {
+                let airport = &self.airports[i];
+                let distance = geodesic::distance_nm(center, airport.position, options);
+
+                if distance > searched_nm && distance <= radius_nm && matches(airport) {
+                    results.push((airport, distance));
+                }
+            }
+
+            // Every airport found within the radius is closer than any airport outside of it
+            if results.len() >= count || radius_nm.is_infinite() {
+                break;
+            }
+
+            searched_nm = radius_nm;
+
+            // Rhumb lines can be longer than half of the circumference, so the last search
+            // takes in every remaining airport
+            radius_nm = if radius_nm * 2.0 < MAX_RADIUS_NM {
+                radius_nm * 2.0
+            } else {
+                f32::INFINITY
+            };
+        }
+
+        results.sort_unstable_by(|(x_arpt, x_dist), (y_arpt, y_dist)| {
+            x_dist
+                .partial_cmp(y_dist)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| x_arpt.icao.cmp(&y_arpt.icao))
+        });
+
+        results.truncate(count);
+        results
+    }
+}
+
+impl Deref for Airports {
+    type Target = [Airport];
+
+    fn deref(&self) -> &Self::Target {
+        &self.airports
+    }
 }
 
 fn download_file<S, P>(url: &str, name: S, dir: P) -> Result<()>
@@ -171,13 +369,45 @@ impl Backup {
 #[derive(Debug, Serialize)]
 pub struct Airport {
     pub icao: String,
+    pub name: String,
+    #[serde(rename = "iataCode")]
+    pub iata_code: Option<String>,
+    #[serde(rename = "gpsCode")]
+    pub gps_code: Option<String>,
+    pub municipality: Option<String>,
     #[serde(skip_serializing)]
     pub class: AirportType,
     pub position: Position,
+    #[serde(rename = "elevationFT")]
+    pub elevation_ft: Option<i32>,
     pub runways: Vec<Runway>,
     pub frequencies: HashMap<FrequencyType, String>,
     #[serde(skip_serializing)]
     pub country_name: String,
+    #[serde(skip_serializing)]
+    pub region: String,
+}
+
+impl Airport {
+    /// Get the ISO code of the airport's country, which prefixes its region code.
+    #[inline(always)]
+    pub fn country_code(&self) -> &str {
+        self.region.split('-').next().unwrap_or_default()
+    }
+
+    /// Get the longest open runway, preferring paved and then lighted runways when lengths are equal.
+    pub fn best_runway(&self) -> Option<&Runway> {
+        self.runways
+            .iter()
+            .filter(|runway| !runway.closed)
+            .max_by_key(|runway| {
+                (
+                    runway.length_ft.unwrap_or(0),
+                    runway.surface == RunwaySurface::Paved,
+                    runway.lighted,
+                )
+            })
+    }
 }
 
 impl PartialEq for Airport {
@@ -198,7 +428,7 @@ pub enum AirportType {
 }
 
 impl AirportType {
-    fn from_str(value: &str) -> Self {
+    pub fn from_str(value: &str) -> Self {
         match value {
             "large_airport" => Self::Large,
             "medium_airport" => Self::Medium,
@@ -262,7 +492,7 @@ impl Serialize for AirportType {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
 pub struct Position {
     #[serde(rename = "latitudeDeg")]
     pub latitude_deg: f32,
@@ -286,12 +516,46 @@ pub struct Runway {
     pub length_ft: Option<u32>,
     #[serde(rename = "widthFT")]
     pub width_ft: Option<u32>,
+    pub surface: RunwaySurface,
+    pub lighted: bool,
+    pub closed: bool,
     #[serde(rename = "heMarker")]
     pub he_marker: Option<RunwayMarker>,
     #[serde(rename = "leMarker")]
     pub le_marker: Option<RunwayMarker>,
 }
 
+impl Runway {
+    /// Get the true course in degrees from one end of the runway to the other, if the
+    /// positions of both ends are known.
+    pub fn heading_deg(&self) -> Option<f32> {
+        let he = self.he_marker.as_ref()?.position;
+        let le = self.le_marker.as_ref()?.position;
+        let leg = geodesic::measure(he, le, DistanceOptions::default());
+
+        if leg.distance_nm > 0.0 {
+            Some(leg.initial_course_deg as f32)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunwaySurface {
+    Paved,
+    Unpaved,
+    Water,
+    Unknown,
+}
+
+impl Default for RunwaySurface {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct RunwayMarker {
     pub name: String,
@@ -308,7 +572,19 @@ impl RunwayMarker {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+#[derive(Debug, Serialize)]
+pub struct Navaid {
+    pub ident: String,
+    pub name: String,
+    /// The type of navaid, such as VOR-DME or NDB.
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(rename = "frequencyKHz")]
+    pub frequency_khz: Option<u32>,
+    pub position: Position,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FrequencyType {
     Atis,
@@ -320,3 +596,16 @@ pub enum FrequencyType {
     Tower,
     Unicom,
 }
+
+impl FrequencyType {
+    /// Returns true if a frequency of this type provides the service of the `other` type.
+    ///
+    /// This is mainly to account for combined arrival / departure frequencies.
+    pub fn provides(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::ArrivalDeparture, Self::Arrival) => true,
+            (Self::ArrivalDeparture, Self::Departure) => true,
+            (kind, other) => kind == other,
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use super::{AirportData, AirportType, LastUpdate, Position, RunwayMarker};
+use super::{
+    AirportData, AirportType, LastUpdate, Navaid as NavaidInfo, Position, RunwayMarker,
+    RunwaySurface,
+};
 use crate::path::FilePath;
 use anyhow::Result;
 use serde::de::{Deserialize, Deserializer, Visitor};
@@ -36,7 +39,12 @@ impl OurAirports {
 
 impl AirportData for OurAirports {
     fn is_up_to_date(&self) -> bool {
-        !self.last_update.needs_update()
+        // Files that were added in newer versions won't exist until the next update
+        let has_all_files = FileType::ALL
+            .iter()
+            .all(|kind| self.data_dir.join(kind.filename()).exists());
+
+        has_all_files && !self.last_update.needs_update()
     }
 
     fn update(&mut self) -> Result<()> {
@@ -84,11 +92,17 @@ impl AirportData for OurAirports {
 
             let result = super::Airport {
                 icao: airport.icao,
+                name: airport.name,
+                iata_code: airport.iata_code,
+                gps_code: airport.gps_code,
+                municipality: airport.municipality,
                 class: airport.class,
                 position: Position::new(airport.lat_deg, airport.lon_deg),
+                elevation_ft: airport.elevation_ft,
                 runways: runways.into_iter().map(Into::into).collect(),
                 frequencies,
                 country_name: country.name.clone(),
+                region: airport.region,
             };
 
             results.push(result);
@@ -99,6 +113,11 @@ impl AirportData for OurAirports {
 
         Ok(results)
     }
+
+    fn load_navaids(&self) -> Result<Vec<NavaidInfo>> {
+        let navaids = Navaid::from_dir(&self.data_dir)?;
+        Ok(navaids.into_iter().map(Into::into).collect())
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -107,14 +126,16 @@ enum FileType {
     Runways,
     Frequencies,
     Countries,
+    Navaids,
 }
 
 impl FileType {
-    const ALL: [Self; 4] = [
+    const ALL: [Self; 5] = [
         Self::Airports,
         Self::Runways,
         Self::Frequencies,
         Self::Countries,
+        Self::Navaids,
     ];
 
     fn filename(self) -> &'static str {
@@ -123,6 +144,7 @@ impl FileType {
             Self::Runways => "runways.csv",
             Self::Frequencies => "airport-frequencies.csv",
             Self::Countries => "countries.csv",
+            Self::Navaids => "navaids.csv",
         }
     }
 }
@@ -149,12 +171,19 @@ struct Airport {
     icao: String,
     #[serde(rename = "type")]
     class: AirportType,
+    name: String,
     #[serde(rename = "latitude_deg")]
     lat_deg: f32,
     #[serde(rename = "longitude_deg")]
     lon_deg: f32,
+    elevation_ft: Option<i32>,
     #[serde(rename = "iso_country")]
     country_code: String,
+    #[serde(rename = "iso_region")]
+    region: String,
+    municipality: Option<String>,
+    gps_code: Option<String>,
+    iata_code: Option<String>,
 }
 
 impl Airport {
@@ -188,6 +217,9 @@ struct Runway {
     airport_ref: i32,
     length_ft: Option<u32>,
     width_ft: Option<u32>,
+    surface: Option<String>,
+    lighted: Option<u8>,
+    closed: Option<u8>,
     le_ident: Option<String>,
     #[serde(rename = "le_latitude_deg")]
     le_lat_deg: Option<f32>,
@@ -220,6 +252,31 @@ impl Runway {
 
         Ok(results)
     }
+
+    /// Classify the free-form surface description used by OurAirports.
+    fn surface_class(surface: &str) -> RunwaySurface {
+        const PAVED: [&str; 10] = [
+            "ASP", "CON", "BIT", "PEM", "MAC", "TAR", "PAV", "CEM", "BRI", "COP",
+        ];
+
+        const UNPAVED: [&str; 14] = [
+            "TURF", "GRS", "GRA", "GRE", "GRV", "DIRT", "SAND", "CLA", "SOIL", "EARTH", "COR",
+            "LAT", "SNOW", "ICE",
+        ];
+
+        let surface = surface.trim().to_ascii_uppercase();
+
+        // Checked first, as surfaces like "UNPAVED" would otherwise be seen as paved
+        if surface.starts_with("UNP") || UNPAVED.iter().any(|kind| surface.starts_with(kind)) {
+            RunwaySurface::Unpaved
+        } else if surface.starts_with("WAT") {
+            RunwaySurface::Water
+        } else if PAVED.iter().any(|kind| surface.starts_with(kind)) {
+            RunwaySurface::Paved
+        } else {
+            RunwaySurface::Unknown
+        }
+    }
 }
 
 impl Into<super::Runway> for Runway {
@@ -234,9 +291,18 @@ impl Into<super::Runway> for Runway {
             _ => None,
         };
 
+        let surface = self
+            .surface
+            .as_deref()
+            .map(Self::surface_class)
+            .unwrap_or_default();
+
         super::Runway {
             length_ft: self.length_ft,
             width_ft: self.width_ft,
+            surface,
+            lighted: self.lighted == Some(1),
+            closed: self.closed == Some(1),
             he_marker,
             le_marker,
         }
@@ -399,3 +465,52 @@ impl FileSource for Country {
         FileType::Countries
     }
 }
+
+#[derive(Debug, Deserialize)]
+struct Navaid {
+    ident: String,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    frequency_khz: Option<u32>,
+    #[serde(rename = "latitude_deg")]
+    lat_deg: f32,
+    #[serde(rename = "longitude_deg")]
+    lon_deg: f32,
+}
+
+impl Navaid {
+    /// Load every navaid, including the ones that aren't associated with an airport.
+    fn from_dir<P>(dir: P) -> Result<Vec<Self>>
+    where
+        P: Into<PathBuf>,
+    {
+        let reader = Self::open_reader(dir)?;
+        let mut results = Vec::new();
+
+        for result in reader.into_deserialize() {
+            let record: Self = result?;
+            results.push(record);
+        }
+
+        Ok(results)
+    }
+}
+
+impl Into<NavaidInfo> for Navaid {
+    fn into(self) -> NavaidInfo {
+        NavaidInfo {
+            ident: self.ident,
+            name: self.name,
+            kind: self.kind,
+            frequency_khz: self.frequency_khz,
+            position: Position::new(self.lat_deg, self.lon_deg),
+        }
+    }
+}
+
+impl FileSource for Navaid {
+    fn file_type() -> FileType {
+        FileType::Navaids
+    }
+}
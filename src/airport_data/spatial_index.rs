@@ -0,0 +1,200 @@
+use super::Position;
+use crate::geo;
+use std::collections::HashMap;
+
+/// A grid over latitude / longitude cells that allows points such as airports near a position
+/// to be found without scanning every point.
+pub struct SpatialIndex {
+    cells: HashMap<(u16, u16), Vec<usize>>,
+}
+
+impl SpatialIndex {
+    const CELL_SIZE_DEG: f32 = 1.0;
+    const LAT_CELLS: u16 = (180.0 / Self::CELL_SIZE_DEG) as u16;
+    const LON_CELLS: u16 = (360.0 / Self::CELL_SIZE_DEG) as u16;
+
+    /// Nautical miles per degree of latitude.
+    const NM_PER_DEG: f32 = geo::EARTH_RADIUS_NM * std::f32::consts::PI / 180.0;
+
+    /// How much the search radius is widened by, since distances on the WGS-84 ellipsoid
+    /// can be up to about 0.6% shorter than on the sphere the cells are sized for.
+    const RADIUS_PADDING: f32 = 1.01;
+
+    /// Index `positions` by their order, which is what candidates are reported as.
+    pub fn new<I>(positions: I) -> Self
+    where
+        I: IntoIterator<Item = Position>,
+    {
+        let mut cells = HashMap::new();
+
+        for (i, position) in positions.into_iter().enumerate() {
+            let cell = (
+                Self::lat_cell(position.latitude_deg),
+                Self::lon_cell(position.longitude_deg),
+            );
+
+            cells.entry(cell).or_insert_with(Vec::new).push(i);
+        }
+
+        Self { cells }
+    }
+
+    /// Get the indices of all points that may be within `radius_nm` of `center`.
+    ///
+    /// The returned candidates are a superset of the points actually within the radius,
+    /// so their exact distance still needs to be checked. Indices are returned in ascending order.
+    pub fn candidates_within(&self, center: Position, radius_nm: f32) -> Vec<usize> {
+        let radius_deg = radius_nm.max(0.0) * Self::RADIUS_PADDING / Self::NM_PER_DEG;
+
+        let min_lat = center.latitude_deg - radius_deg;
+        let max_lat = center.latitude_deg + radius_deg;
+
+        let lat_cells = Self::lat_cell(min_lat)..=Self::lat_cell(max_lat);
+        let widest_lat = min_lat.abs().max(max_lat.abs());
+
+        // Degrees of longitude shrink towards the poles, so the search has to be widened
+        // at the latitude furthest from the equator. If that includes a pole, every
+        // longitude can be reached.
+        let lon_cells = if widest_lat >= 90.0 {
+            None
+        } else {
+            let radius_lon_deg = radius_deg / widest_lat.to_radians().cos();
+
+            if radius_lon_deg >= 180.0 {
+                None
+            } else {
+                let first = ((center.longitude_deg - radius_lon_deg + 180.0) / Self::CELL_SIZE_DEG)
+                    .floor() as i32;
+                let last = ((center.longitude_deg + radius_lon_deg + 180.0) / Self::CELL_SIZE_DEG)
+                    .floor() as i32;
+
+                Some((first, last))
+            }
+        };
+
+        let mut results = Vec::new();
+
+        for lat_cell in lat_cells {
+            let mut push_cell = |lon_cell| {
+                if let Some(indices) = self.cells.get(&(lat_cell, lon_cell)) {
+                    results.extend_from_slice(indices);
+                }
+            };
+
+            match lon_cells {
+                Some((first, last)) => {
+                    for lon_cell in first..=last {
+                        // Wrap around the antimeridian
+                        let lon_cell = lon_cell.rem_euclid(i32::from(Self::LON_CELLS)) as u16;
+                        push_cell(lon_cell);
+                    }
+                }
+                None => (0..Self::LON_CELLS).for_each(push_cell),
+            }
+        }
+
+        results.sort_unstable();
+        results.dedup();
+        results
+    }
+
+    fn lat_cell(lat_deg: f32) -> u16 {
+        let cell = ((lat_deg + 90.0) / Self::CELL_SIZE_DEG).floor();
+        (cell.max(0.0) as u16).min(Self::LAT_CELLS - 1)
+    }
+
+    fn lon_cell(lon_deg: f32) -> u16 {
+        let cell = ((lon_deg + 180.0) / Self::CELL_SIZE_DEG).floor() as i32;
+        cell.rem_euclid(i32::from(Self::LON_CELLS)) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::Airport;
+    use crate::geo::geodesic::{self, DistanceOptions};
+    use std::collections::HashMap;
+
+    fn airport_at(icao: &str, lat: f32, lon: f32) -> Airport {
+        Airport {
+            icao: icao.into(),
+            name: String::new(),
+            iata_code: None,
+            gps_code: None,
+            municipality: None,
+            class: Default::default(),
+            position: Position::new(lat, lon),
+            elevation_ft: None,
+            runways: Vec::new(),
+            frequencies: HashMap::new(),
+            country_name: String::new(),
+            region: String::new(),
+        }
+    }
+
+    fn find_within(airports: &[Airport], center: Position, radius_nm: f32) -> Vec<&str> {
+        let index = SpatialIndex::new(airports.iter().map(|arpt| arpt.position));
+
+        let mut results = index
+            .candidates_within(center, radius_nm)
+            .into_iter()
+            .map(|i| &airports[i])
+            .filter(|arpt| {
+                geodesic::distance_nm(center, arpt.position, DistanceOptions::default())
+                    <= radius_nm
+            })
+            .map(|arpt| arpt.icao.as_str())
+            .collect::<Vec<_>>();
+
+        results.sort_unstable();
+        results
+    }
+
+    #[test]
+    fn candidates_near_point() {
+        let airports = vec![
+            airport_at("KSFO", 37.618, -122.375),
+            airport_at("KSAC", 38.512, -121.492),
+            airport_at("KLAX", 33.942, -118.408),
+            airport_at("RJAA", 35.764, 140.386),
+        ];
+
+        let center = Position::new(37.618, -122.375);
+
+        assert_eq!(find_within(&airports, center, 100.0), vec!["KSAC", "KSFO"]);
+        assert_eq!(
+            find_within(&airports, center, 400.0),
+            vec!["KLAX", "KSAC", "KSFO"]
+        );
+    }
+
+    #[test]
+    fn candidates_across_antimeridian() {
+        let airports = vec![
+            // Fiji
+            airport_at("NFFN", -17.755, 177.443),
+            // Samoa
+            airport_at("NSFA", -13.830, -172.008),
+            airport_at("NZAA", -37.008, 174.792),
+        ];
+
+        let center = Position::new(-15.0, 179.9);
+
+        assert_eq!(find_within(&airports, center, 500.0), vec!["NFFN", "NSFA"]);
+    }
+
+    #[test]
+    fn candidates_near_pole() {
+        let airports = vec![
+            airport_at("ENSB", 78.246, 15.466),
+            // Alert, Canada
+            airport_at("CYLT", 82.518, -62.281),
+        ];
+
+        let center = Position::new(89.0, 0.0);
+
+        assert_eq!(find_within(&airports, center, 600.0), vec!["CYLT"]);
+        assert_eq!(find_within(&airports, center, 800.0), vec!["CYLT", "ENSB"]);
+    }
+}
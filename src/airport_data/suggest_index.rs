@@ -0,0 +1,569 @@
+use super::{Airport, AirportType};
+use serde_derive::Serialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::iter;
+
+/// Prebuilt search terms for suggesting airports and countries from partial or misspelled text.
+///
+/// Airport terms are indexed so that only the terms that could match a query need to be scored.
+pub struct SuggestIndex {
+    airport_terms: Vec<AirportTerm>,
+    /// The start of every word of the airport terms as a term index and byte offset, sorted by
+    /// the text from there on so that words starting with a query can be found with a binary search.
+    word_starts: Vec<(u32, u32)>,
+    /// The name and municipality terms that contain each sequence of three characters.
+    trigrams: HashMap<[char; 3], Vec<u32>>,
+    /// The name and municipality terms that contain each pair of characters, where a space
+    /// marks the start of a word.
+    bigrams: HashMap<[char; 2], Vec<u32>>,
+    countries: Vec<Country>,
+}
+
+impl SuggestIndex {
+    pub fn new(airports: &[Airport]) -> Self {
+        let mut airport_terms = Vec::with_capacity(airports.len() * 3);
+        let mut countries = Vec::new();
+        let mut country_codes = HashSet::new();
+
+        for (i, airport) in airports.iter().enumerate() {
+            let terms = [
+                (TermKind::Code, Some(&airport.icao)),
+                (TermKind::Code, airport.iata_code.as_ref()),
+                (TermKind::Code, airport.gps_code.as_ref()),
+                (TermKind::Name, Some(&airport.name)),
+                (TermKind::Municipality, airport.municipality.as_ref()),
+            ];
+
+            for (kind, text) in terms.iter() {
+                let text = match text {
+                    Some(text) if !text.is_empty() => normalize(text),
+                    _ => continue,
+                };
+
+                // GPS codes are often the same as the ICAO code
+                let is_duplicate = airport_terms
+                    .last()
+                    .map(|term: &AirportTerm| term.airport == i && term.text == text)
+                    .unwrap_or(false);
+
+                if is_duplicate {
+                    continue;
+                }
+
+                airport_terms.push(AirportTerm {
+                    airport: i,
+                    kind: *kind,
+                    text,
+                });
+            }
+
+            let code = airport.country_code();
+
+            if !code.is_empty() && country_codes.insert(code) {
+                countries.push(Country::new(code.to_string(), airport.country_name.clone()));
+            }
+        }
+
+        countries.sort_unstable_by(|x, y| x.name.cmp(&y.name));
+
+        let mut word_starts = Vec::new();
+        let mut trigrams = HashMap::new();
+        let mut bigrams = HashMap::new();
+
+        for (i, term) in airport_terms.iter().enumerate() {
+            let i = i as u32;
+
+            word_starts.push((i, 0));
+            word_starts.extend(
+                term.text
+                    .match_indices(' ')
+                    .map(|(offset, _)| (i, offset as u32 + 1)),
+            );
+
+            // Codes are only matched by their prefix
+            if term.kind == TermKind::Code {
+                continue;
+            }
+
+            let chars = term.text.chars().collect::<Vec<_>>();
+
+            for window in chars.windows(3) {
+                add_posting(&mut trigrams, [window[0], window[1], window[2]], i);
+            }
+
+            let padded = iter::once(' ').chain(chars).collect::<Vec<_>>();
+
+            for window in padded.windows(2) {
+                add_posting(&mut bigrams, [window[0], window[1]], i);
+            }
+        }
+
+        word_starts.sort_unstable_by(|&x, &y| {
+            word_start_text(&airport_terms, x).cmp(word_start_text(&airport_terms, y))
+        });
+
+        Self {
+            airport_terms,
+            word_starts,
+            trigrams,
+            bigrams,
+            countries,
+        }
+    }
+
+    /// Get the indices of the airports that best match `query`, from best to worst.
+    pub fn airports(&self, query: &str, airports: &[Airport], limit: usize) -> Vec<usize> {
+        let query = normalize(query);
+
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: HashMap<usize, u32> = HashMap::new();
+
+        for i in self.candidates(&query) {
+            let term = &self.airport_terms[i as usize];
+
+            let score = match term.kind.score(&query, &term.text) {
+                Some(score) => score,
+                None => continue,
+            };
+
+            let best = scores.entry(term.airport).or_insert(0);
+            *best = (*best).max(score);
+        }
+
+        let mut results = scores.into_iter().collect::<Vec<_>>();
+
+        // Larger airports are more likely to be what's being searched for
+        results.sort_unstable_by_key(|&(i, score)| {
+            let airport = &airports[i];
+            (Reverse(score), type_rank(airport.class), &airport.icao)
+        });
+
+        results.into_iter().take(limit).map(|(i, _)| i).collect()
+    }
+
+    /// Get the indices of every airport term that could match `query`, in ascending order.
+    ///
+    /// `query` should already be normalized.
+    fn candidates(&self, query: &str) -> Vec<u32> {
+        let chars = query.chars().collect::<Vec<_>>();
+        let mut candidates = self.word_prefix_candidates(query);
+
+        if chars.len() >= TermKind::MIN_SUBSTRING_LEN {
+            candidates.extend(self.substring_candidates(&chars));
+        }
+
+        if let Some(max_typos) = TermKind::max_typos(chars.len()) {
+            candidates.extend(self.typo_candidates(&chars, max_typos));
+        }
+
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Get the terms that have a word starting with `query`, which includes terms that start with it.
+    fn word_prefix_candidates(&self, query: &str) -> Vec<u32> {
+        let text = |start| word_start_text(&self.airport_terms, start);
+
+        // Never comparing as equal finds where the first word starting with the query would be
+        let first = self
+            .word_starts
+            .binary_search_by(|&start| {
+                if text(start) < query {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            })
+            .unwrap_or_else(|index| index);
+
+        self.word_starts[first..]
+            .iter()
+            .take_while(|&&start| text(start).starts_with(query))
+            .map(|&(term, _)| term)
+            .collect()
+    }
+
+    /// Get the terms that contain every sequence of three characters in `query`.
+    fn substring_candidates(&self, query: &[char]) -> Vec<u32> {
+        let mut postings = Vec::new();
+
+        for window in query.windows(3) {
+            match self.trigrams.get(&[window[0], window[1], window[2]]) {
+                Some(terms) => postings.push(terms),
+                None => return Vec::new(),
+            }
+        }
+
+        postings.sort_unstable_by_key(|terms| terms.len());
+
+        let (shortest, rest) = match postings.split_first() {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+
+        shortest
+            .iter()
+            .copied()
+            .filter(|term| rest.iter().all(|terms| terms.binary_search(term).is_ok()))
+            .collect()
+    }
+
+    /// Get the terms with words that share enough pairs of characters with `query` to be
+    /// within `max_typos` of it.
+    ///
+    /// A typo changes at most three pairs, which is what swapping two characters does, so a
+    /// word within `max_typos` shares every other pair. That leaves nothing to require of a
+    /// six character query with two swaps that change every pair, so those aren't found.
+    fn typo_candidates(&self, query: &[char], max_typos: usize) -> Vec<u32> {
+        let padded = iter::once(' ')
+            .chain(query.iter().copied())
+            .collect::<Vec<_>>();
+
+        let pairs = padded
+            .windows(2)
+            .map(|window| [window[0], window[1]])
+            .collect::<HashSet<_>>();
+
+        let min_shared = pairs.len().saturating_sub(3 * max_typos).max(1);
+        let mut shared: HashMap<u32, usize> = HashMap::new();
+
+        for terms in pairs.iter().filter_map(|pair| self.bigrams.get(pair)) {
+            for &term in terms {
+                *shared.entry(term).or_insert(0) += 1;
+            }
+        }
+
+        shared
+            .into_iter()
+            .filter(|&(_, count)| count >= min_shared)
+            .map(|(term, _)| term)
+            .collect()
+    }
+
+    /// Get the countries that best match `query` by their name or code, from best to worst.
+    pub fn countries(&self, query: &str, limit: usize) -> Vec<&Country> {
+        let query = normalize(query);
+
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut results = self
+            .countries
+            .iter()
+            .filter_map(|country| {
+                let code_score = TermKind::Code.score(&query, &country.normalized_code);
+                let name_score = TermKind::Name.score(&query, &country.normalized_name);
+
+                code_score.max(name_score).map(|score| (country, score))
+            })
+            .collect::<Vec<_>>();
+
+        results.sort_by_key(|&(country, score)| (Reverse(score), &country.name));
+        results.into_iter().take(limit).map(|(c, _)| c).collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Country {
+    pub code: String,
+    pub name: String,
+    #[serde(skip)]
+    normalized_code: String,
+    #[serde(skip)]
+    normalized_name: String,
+}
+
+impl Country {
+    fn new(code: String, name: String) -> Self {
+        Self {
+            normalized_code: normalize(&code),
+            normalized_name: normalize(&name),
+            code,
+            name,
+        }
+    }
+}
+
+struct AirportTerm {
+    airport: usize,
+    kind: TermKind,
+    text: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum TermKind {
+    /// An ICAO, IATA or GPS code.
+    Code,
+    Name,
+    Municipality,
+}
+
+impl TermKind {
+    /// The shortest query that can match the middle of a word.
+    const MIN_SUBSTRING_LEN: usize = 3;
+
+    /// Get the most typos that a query of `query_len` characters can have and still match,
+    /// or `None` if it's too short to match with any.
+    ///
+    /// Too many unrelated words are a single typo away from very short queries.
+    fn max_typos(query_len: usize) -> Option<usize> {
+        match query_len {
+            0..=3 => None,
+            4..=5 => Some(1),
+            _ => Some(2),
+        }
+    }
+
+    /// Score how well `query` matches `text`, with higher scores being better matches.
+    ///
+    /// Both should already be normalized.
+    fn score(self, query: &str, text: &str) -> Option<u32> {
+        if text == query {
+            return Some(match self {
+                Self::Code => 110,
+                Self::Name => 100,
+                Self::Municipality => 90,
+            });
+        }
+
+        if text.starts_with(query) {
+            return Some(match self {
+                Self::Code => 85,
+                Self::Name => 80,
+                Self::Municipality => 70,
+            });
+        }
+
+        // Codes are too short for anything but prefix matches to be meaningful
+        if self == Self::Code {
+            return None;
+        }
+
+        let penalty = if self == Self::Municipality { 5 } else { 0 };
+
+        if text.split(' ').any(|word| word.starts_with(query)) {
+            return Some(60 - penalty);
+        }
+
+        let query_len = query.chars().count();
+
+        if query_len < Self::MIN_SUBSTRING_LEN {
+            return None;
+        }
+
+        if text.contains(query) {
+            return Some(40 - penalty);
+        }
+
+        let max_typos = Self::max_typos(query_len)?;
+
+        let typos = text
+            .split(' ')
+            .map(|word| {
+                // Only compare against the start of the word so partially typed words can match
+                let word = word.chars().take(query_len).collect::<String>();
+                edit_distance(query, &word)
+            })
+            .min()?;
+
+        if typos <= max_typos {
+            Some(30 - 10 * typos as u32 - penalty)
+        } else {
+            None
+        }
+    }
+}
+
+/// Get the text of a term from the start of one of its words to its end.
+#[inline(always)]
+fn word_start_text(terms: &[AirportTerm], (term, offset): (u32, u32)) -> &str {
+    &terms[term as usize].text[offset as usize..]
+}
+
+/// Add `term` to the terms that contain `key`, which are kept in ascending order
+/// when terms are added in that order.
+fn add_posting<K>(postings: &mut HashMap<K, Vec<u32>>, key: K, term: u32)
+where
+    K: Eq + Hash,
+{
+    let terms = postings.entry(key).or_default();
+
+    if terms.last() != Some(&term) {
+        terms.push(term);
+    }
+}
+
+#[inline(always)]
+fn type_rank(kind: AirportType) -> u8 {
+    match kind {
+        AirportType::Large => 0,
+        AirportType::Medium => 1,
+        AirportType::Small => 2,
+        _ => 3,
+    }
+}
+
+/// Lowercase text and collapse anything that isn't alphanumeric into single spaces.
+fn normalize(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            result.extend(c.to_lowercase());
+        } else if !result.is_empty() && !result.ends_with(' ') {
+            result.push(' ');
+        }
+    }
+
+    if result.ends_with(' ') {
+        result.pop();
+    }
+
+    result
+}
+
+/// Get the number of insertions, deletions, substitutions and transpositions needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    let mut prev_prev = vec![0; b.len() + 1];
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            current[j] = (prev[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(prev_prev[j - 2] + 1);
+            }
+        }
+
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut current);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::get_airports;
+
+    fn suggest(query: &str) -> Vec<String> {
+        get_airports()
+            .suggest_airports(query, 10)
+            .into_iter()
+            .map(|arpt| arpt.icao.clone())
+            .collect()
+    }
+
+    #[test]
+    fn normalize_text() {
+        assert_eq!(normalize("  San Francisco Int'l. "), "san francisco int l");
+        assert_eq!(normalize("Zürich"), "zürich");
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("francisco", "francisco"), 0);
+        assert_eq!(edit_distance("fransisco", "francisco"), 1);
+        assert_eq!(edit_distance("nartia", "narita"), 1);
+        assert_eq!(edit_distance("abc", ""), 3);
+    }
+
+    #[test]
+    fn suggest_airports_by_code() {
+        assert_eq!(suggest("KSFO"), vec!["KSFO"]);
+        assert_eq!(suggest("nrt"), vec!["RJAA"]);
+        assert_eq!(suggest("sac"), vec!["KSAC"]);
+        assert_eq!(suggest("KS"), vec!["KSFO", "KSAC"]);
+    }
+
+    #[test]
+    fn suggest_airports_by_name() {
+        assert_eq!(suggest("narita"), vec!["RJAA"]);
+        assert_eq!(suggest("nartia"), vec!["RJAA"]);
+        assert_eq!(suggest("tokyo"), vec!["RJAA"]);
+        assert_eq!(suggest("San Fran"), vec!["KSFO", "3CA1"]);
+        assert_eq!(suggest("fransisco"), vec!["KSFO", "3CA1"]);
+        assert!(suggest("zzzzzz").is_empty());
+        assert!(suggest(" ").is_empty());
+    }
+
+    #[test]
+    fn candidates_include_every_match() {
+        let airports = get_airports();
+        let index = SuggestIndex::new(&airports.airports);
+
+        let queries = [
+            "k",
+            "ksfo",
+            "nrt",
+            "san",
+            "san fran",
+            "fran",
+            "ncisco",
+            "int",
+            "nartia",
+            "fransisco",
+            "tokoy",
+            "sacramento",
+            "xecutive",
+            "zzzzzz",
+        ];
+
+        for query in queries.iter() {
+            let query = normalize(query);
+            let candidates = index.candidates(&query);
+
+            // Scoring every term is what the index avoids
+            for (i, term) in index.airport_terms.iter().enumerate() {
+                if term.kind.score(&query, &term.text).is_some() {
+                    assert!(
+                        candidates.contains(&(i as u32)),
+                        "\"{}\" is missing \"{}\"",
+                        query,
+                        term.text
+                    );
+                }
+            }
+        }
+
+        assert!(index.candidates("zzzzzz").is_empty());
+        assert!(index.candidates("ksfo").len() < index.airport_terms.len());
+    }
+
+    #[test]
+    fn suggest_countries() {
+        let airports = get_airports();
+
+        let suggest = |query| {
+            airports
+                .suggest_countries(query, 10)
+                .into_iter()
+                .map(|country| country.code.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(suggest("jp"), vec!["JP"]);
+        assert_eq!(suggest("united"), vec!["US"]);
+        assert_eq!(suggest("japna"), vec!["JP"]);
+        assert!(suggest("xx").is_empty());
+    }
+}
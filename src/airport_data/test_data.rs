@@ -0,0 +1,152 @@
+use super::{
+    Airport, AirportType, Airports, FrequencyType, Navaid, Position, Runway, RunwayMarker,
+    RunwaySurface,
+};
+use std::collections::HashMap;
+
+fn frequencies(kinds: &[FrequencyType]) -> HashMap<FrequencyType, String> {
+    kinds
+        .iter()
+        .map(|&kind| (kind, "123.450".to_string()))
+        .collect()
+}
+
+/// Get a small set of airports, sorted by ICAO like loaded airports are.
+pub fn get_airports() -> Airports {
+    use FrequencyType::*;
+
+    // San Francisco
+    let ksfo = Airport {
+        icao: "KSFO".into(),
+        name: "San Francisco International Airport".into(),
+        iata_code: Some("SFO".into()),
+        gps_code: Some("KSFO".into()),
+        municipality: Some("San Francisco".into()),
+        class: AirportType::Large,
+        position: Position::new(37.618, -122.375),
+        elevation_ft: Some(13),
+        runways: vec![
+            Runway {
+                length_ft: Some(7500),
+                width_ft: Some(200),
+                surface: RunwaySurface::Paved,
+                lighted: true,
+                closed: false,
+                he_marker: Some(RunwayMarker::new("01L".into(), 37.608, -122.381)),
+                le_marker: Some(RunwayMarker::new("19R".into(), 37.627, -122.367)),
+            },
+            Runway {
+                length_ft: Some(11870),
+                width_ft: Some(200),
+                surface: RunwaySurface::Paved,
+                lighted: true,
+                closed: true,
+                he_marker: Some(RunwayMarker::new("10L".into(), 37.629, -122.393)),
+                le_marker: Some(RunwayMarker::new("28R".into(), 37.614, -122.357)),
+            },
+        ],
+        frequencies: frequencies(&[Atis, Arrival, Departure, Ground, Tower]),
+        country_name: "United States".into(),
+        region: "US-CA".into(),
+    };
+
+    // Sacramento Executive
+    let ksac = Airport {
+        icao: "KSAC".into(),
+        name: "Sacramento Executive Airport".into(),
+        iata_code: Some("SAC".into()),
+        gps_code: Some("KSAC".into()),
+        municipality: Some("Sacramento".into()),
+        class: AirportType::Medium,
+        position: Position::new(38.512, -121.492),
+        elevation_ft: Some(24),
+        runways: vec![Runway {
+            length_ft: Some(3836),
+            width_ft: Some(100),
+            surface: RunwaySurface::Paved,
+            lighted: true,
+            closed: false,
+            he_marker: Some(RunwayMarker::new("12".into(), 38.516, -121.497)),
+            le_marker: Some(RunwayMarker::new("30".into(), 38.508, -121.487)),
+        }],
+        frequencies: frequencies(&[Atis, Ground, Tower]),
+        country_name: "United States".into(),
+        region: "US-CA".into(),
+    };
+
+    // Narita
+    let rjaa = Airport {
+        icao: "RJAA".into(),
+        name: "Narita International Airport".into(),
+        iata_code: Some("NRT".into()),
+        gps_code: Some("RJAA".into()),
+        municipality: Some("Tokyo".into()),
+        class: AirportType::Large,
+        position: Position::new(35.764, 140.386),
+        elevation_ft: Some(141),
+        runways: vec![Runway {
+            length_ft: Some(8202),
+            width_ft: Some(196),
+            surface: RunwaySurface::Paved,
+            lighted: true,
+            closed: false,
+            he_marker: Some(RunwayMarker::new("16L".into(), 35.778, 140.375)),
+            le_marker: Some(RunwayMarker::new("34R".into(), 35.749, 140.391)),
+        }],
+        frequencies: frequencies(&[ArrivalDeparture, Tower]),
+        country_name: "Japan".into(),
+        region: "JP-12".into(),
+    };
+
+    // Heliport near San Francisco
+    let heliport = Airport {
+        icao: "3CA1".into(),
+        name: "Downtown San Francisco Heliport".into(),
+        iata_code: None,
+        gps_code: Some("3CA1".into()),
+        municipality: None,
+        class: AirportType::Heliport,
+        position: Position::new(37.790, -122.400),
+        elevation_ft: Some(200),
+        runways: vec![Runway {
+            length_ft: Some(50),
+            width_ft: Some(50),
+            surface: RunwaySurface::Unknown,
+            lighted: false,
+            closed: false,
+            he_marker: Some(RunwayMarker::new("H1".into(), 37.790, -122.400)),
+            le_marker: None,
+        }],
+        frequencies: frequencies(&[Unicom]),
+        country_name: "United States".into(),
+        region: "US-CA".into(),
+    };
+
+    let mut airports = vec![ksfo, ksac, rjaa, heliport];
+    airports.sort_unstable_by(|x, y| x.icao.cmp(&y.icao));
+    Airports::new(airports, get_navaids())
+}
+
+/// Get one of the airports from `get_airports` by its ICAO code.
+pub fn get_airport<'a>(airports: &'a Airports, icao: &str) -> &'a Airport {
+    airports
+        .find_by_icao(icao)
+        .unwrap_or_else(|| panic!("no test airport with ICAO {}", icao))
+}
+
+/// Get navaids around the bay area and Sacramento.
+fn get_navaids() -> Vec<Navaid> {
+    let navaid = |ident: &str, name: &str, kind: &str, frequency_khz, lat, lon| Navaid {
+        ident: ident.into(),
+        name: name.into(),
+        kind: kind.into(),
+        frequency_khz: Some(frequency_khz),
+        position: Position::new(lat, lon),
+    };
+
+    vec![
+        navaid("SFO", "San Francisco", "VOR-DME", 115800, 37.619, -122.374),
+        navaid("OAK", "Oakland", "VORTAC", 116800, 37.726, -122.224),
+        navaid("SAC", "Sacramento", "VORTAC", 115200, 38.444, -121.552),
+    ]
+}
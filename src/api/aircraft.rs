@@ -0,0 +1,243 @@
+use super::error::{ErrorCode, ValidationErrors};
+use super::flight_profile::FlightProfile;
+use super::speed::Speed;
+use crate::airport_data::RunwaySurface;
+use crate::path::FilePath;
+use anyhow::{anyhow, Context, Result};
+use rocket::State;
+use rocket_contrib::json::JsonValue;
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// List every aircraft in the catalog.
+#[get("/aircraft")]
+pub fn list_aircraft<'a>(catalog: State<'a, AircraftCatalog>) -> JsonValue {
+    json!({ "aircraft": catalog.aircraft })
+}
+
+/// The performance of an aircraft, which can be selected in a search instead of entering
+/// its speed and runway requirements by hand.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Aircraft {
+    /// A short name to select the aircraft with, which is usually its ICAO type designator.
+    pub id: String,
+    pub name: String,
+    /// The cruise speed, which is flown at the profile's cruise altitude unless it specifies its own.
+    pub speed: Speed,
+    #[serde(default)]
+    pub profile: FlightProfile,
+    #[serde(rename = "maxRangeNM")]
+    pub max_range_nm: f32,
+    #[serde(rename = "minRunwayFT")]
+    pub min_runway_ft: u32,
+    /// The runway surfaces that the aircraft can use, or any surface when this is empty.
+    #[serde(rename = "runwaySurfaces", default)]
+    pub runway_surfaces: Vec<RunwaySurface>,
+    /// The strongest crosswind that the aircraft can take off and land in, which is checked
+    /// against the runways at both ends of a route when a wind is given.
+    #[serde(rename = "maxCrosswindKT")]
+    pub max_crosswind_kt: f32,
+    /// Fuel burned per hour of flight in kilograms.
+    #[serde(rename = "fuelBurnKGH")]
+    pub fuel_burn_kgh: f32,
+}
+
+impl Aircraft {
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let Err(errs) = self.speed.validate() {
+            errors.extend_within("speed", errs);
+        }
+
+        if let Err(errs) = self.profile.validate() {
+            errors.extend_within("profile", errs);
+        }
+
+        let values = [
+            ("maxRangeNM", self.max_range_nm),
+            ("maxCrosswindKT", self.max_crosswind_kt),
+            ("fuelBurnKGH", self.fuel_burn_kgh),
+        ];
+
+        for &(field, value) in &values {
+            if !value.is_finite() || value < 0.0 {
+                errors.add(
+                    field,
+                    ErrorCode::InvalidValue,
+                    format!("must be at least 0, got {}", value),
+                );
+            }
+        }
+
+        errors.into_result()
+    }
+}
+
+/// The built-in aircraft, along with any that the user added.
+#[derive(Debug, Default)]
+pub struct AircraftCatalog {
+    aircraft: Vec<Aircraft>,
+}
+
+impl AircraftCatalog {
+    const BUILTIN: &'static str = include_str!("builtin_aircraft.toml");
+    const FILENAME: &'static str = "aircraft.toml";
+
+    /// Load the built-in aircraft and the aircraft from the user's catalog file, if it exists.
+    pub fn load() -> Result<Self> {
+        let mut catalog = Self::builtin()?;
+        let path = FilePath::LocalData.validated_file(Self::FILENAME)?;
+
+        if path.exists() {
+            catalog
+                .load_file(&path)
+                .with_context(|| format!("failed to load aircraft from {}", path.display()))?;
+        }
+
+        Ok(catalog)
+    }
+
+    pub fn builtin() -> Result<Self> {
+        let mut catalog = Self::default();
+        catalog
+            .add_from_str(Self::BUILTIN)
+            .context("failed to load built-in aircraft")?;
+
+        Ok(catalog)
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<()> {
+        let contents = fs::read_to_string(path)?;
+        self.add_from_str(&contents)
+    }
+
+    /// Add every aircraft in a TOML catalog, replacing existing aircraft with the same ID.
+    fn add_from_str(&mut self, toml: &str) -> Result<()> {
+        #[derive(Deserialize)]
+        struct CatalogFile {
+            #[serde(default)]
+            aircraft: Vec<Aircraft>,
+        }
+
+        let file: CatalogFile = toml::from_str(toml)?;
+
+        for aircraft in file.aircraft {
+            if let Err(errors) = aircraft.validate() {
+                let messages = errors
+                    .errors()
+                    .iter()
+                    .map(|err| format!("{}: {}", err.field, err.message))
+                    .collect::<Vec<_>>();
+                return Err(anyhow!("{}: {}", aircraft.id, messages.join("; ")));
+            }
+
+            match self.find_index(&aircraft.id) {
+                Some(index) => self.aircraft[index] = aircraft,
+                None => self.aircraft.push(aircraft),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find an aircraft by its case-insensitive ID.
+    #[inline(always)]
+    pub fn find(&self, id: &str) -> Option<&Aircraft> {
+        self.find_index(id).map(|index| &self.aircraft[index])
+    }
+
+    #[inline(always)]
+    fn find_index(&self, id: &str) -> Option<usize> {
+        self.aircraft
+            .iter()
+            .position(|aircraft| aircraft.id.eq_ignore_ascii_case(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::speed::SpeedKind;
+
+    #[test]
+    fn builtin_catalog() {
+        let catalog = AircraftCatalog::builtin().unwrap();
+
+        let c172 = catalog.find("c172").unwrap();
+        assert_eq!(c172.speed.kind, SpeedKind::Knots(122.0));
+        assert_eq!(c172.profile.cruise_altitude_ft, 8000.0);
+        assert_eq!(c172.min_runway_ft, 1700);
+
+        // Unspecified profile fields fall back to the defaults
+        let a320 = catalog.find("A320").unwrap();
+        assert_eq!(a320.profile.cruise_altitude_ft, 36_000.0);
+        assert_eq!(
+            a320.profile.climb_rate_fpm,
+            FlightProfile::default().climb_rate_fpm
+        );
+        assert_eq!(a320.runway_surfaces, vec![RunwaySurface::Paved]);
+
+        assert!(catalog.find("ZZZZ").is_none());
+    }
+
+    #[test]
+    fn user_catalog() {
+        let mut catalog = AircraftCatalog::builtin().unwrap();
+        let count = catalog.aircraft.len();
+
+        catalog
+            .add_from_str(
+                r#"
+                [[aircraft]]
+                id = "c172"
+                name = "Cessna 172 with a tailwind"
+                speed = { type = "knots", value = 140 }
+                maxRangeNM = 700
+                minRunwayFT = 1500
+                maxCrosswindKT = 15
+                fuelBurnKGH = 24
+
+                [[aircraft]]
+                id = "DA40"
+                name = "Diamond DA40"
+                speed = { type = "ktas", value = 140, altitudeFT = 8000 }
+                maxRangeNM = 720
+                minRunwayFT = 1600
+                maxCrosswindKT = 20
+                fuelBurnKGH = 25
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(catalog.aircraft.len(), count + 1);
+        assert_eq!(catalog.find("C172").unwrap().max_range_nm, 700.0);
+        assert!(catalog.find("C172").unwrap().runway_surfaces.is_empty());
+        assert_eq!(
+            catalog.find("da40").unwrap().speed.altitude_ft,
+            Some(8000.0)
+        );
+
+        let err = catalog
+            .add_from_str(
+                r#"
+                [[aircraft]]
+                id = "BAD"
+                name = "Bad"
+                speed = { type = "knots", value = 0 }
+                maxRangeNM = 100
+                minRunwayFT = 1000
+                maxCrosswindKT = 10
+                fuelBurnKGH = -1
+                "#,
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "BAD: speed.value: speed must be greater than 0, got 0; \
+             fuelBurnKGH: must be at least 0, got -1"
+        );
+    }
+}
@@ -0,0 +1,614 @@
+use super::error::{self, ErrorCode, FieldError, ValidationErrors};
+use super::filters::airport::AirportFilters;
+use crate::airport_data::{Airport, AirportType, Airports, Navaid, Position};
+use crate::geo::geodesic::{self, DistanceOptions};
+use rocket::http::Status;
+use rocket::response::status;
+use rocket::State;
+use rocket_contrib::json::{Json, JsonError, JsonValue};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_derive::{Deserialize, Serialize};
+
+/// Get the full record of an airport by its ICAO, IATA or GPS code.
+#[get("/airports/<ident>")]
+pub fn airport<'a>(
+    ident: String,
+    airports: State<'a, Airports>,
+) -> Result<JsonValue, status::Custom<JsonValue>> {
+    match airports.find_by_ident(&ident) {
+        Some(airport) => Ok(json!({ "airport": AirportDetails::new(airport, &airports) })),
+        None => {
+            let mut errors = ValidationErrors::new();
+            errors.push(FieldError::unknown_ident("ident", &ident));
+
+            Err(errors.into_status(Status::NotFound))
+        }
+    }
+}
+
+/// List the airports that match the JSON encoded airport filters in `filters`.
+///
+/// `fields` is a comma separated list of the airport fields to return.
+#[get("/airports?<filters>&<page>&<limit>&<fields>")]
+pub fn list_airports<'a>(
+    filters: Option<String>,
+    page: Option<usize>,
+    limit: Option<usize>,
+    fields: Option<String>,
+    airports: State<'a, Airports>,
+) -> Result<JsonValue, status::Custom<JsonValue>> {
+    let filters = parse_filters(filters)?;
+
+    let fields = fields.map(|fields| {
+        fields
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty())
+            .collect()
+    });
+
+    let query = AirportQuery {
+        filters,
+        page: page.unwrap_or(1),
+        limit,
+        fields,
+    };
+
+    query.run(&airports)
+}
+
+/// Like `list_airports`, but with the query in the request body.
+#[post("/airports", format = "json", data = "<query>")]
+pub fn search_airports<'a>(
+    query: Result<Json<AirportQuery>, JsonError<'_>>,
+    airports: State<'a, Airports>,
+) -> Result<JsonValue, status::Custom<JsonValue>> {
+    query
+        .map_err(error::malformed_json)?
+        .into_inner()
+        .run(&airports)
+}
+
+/// Find the `n` airports closest to a position that match the JSON encoded airport filters in `filters`.
+#[get("/airports/nearest?<lat>&<lon>&<n>&<filters>")]
+pub fn nearest_airports<'a>(
+    lat: Option<f32>,
+    lon: Option<f32>,
+    n: Option<usize>,
+    filters: Option<String>,
+    airports: State<'a, Airports>,
+) -> Result<JsonValue, status::Custom<JsonValue>> {
+    let query = NearestQuery {
+        lat,
+        lon,
+        n,
+        filters: parse_filters(filters)?,
+    };
+
+    let results = query
+        .into_results(&airports)
+        .map_err(ValidationErrors::into_response)?;
+
+    Ok(json!({ "airports": results }))
+}
+
+fn parse_filters(filters: Option<String>) -> Result<AirportFilters, status::Custom<JsonValue>> {
+    match filters {
+        Some(filters) => serde_json::from_str(&filters).map_err(|err| {
+            let mut errors = ValidationErrors::new();
+            errors.add("filters", ErrorCode::MalformedRequest, err.to_string());
+            errors.into_status(Status::BadRequest)
+        }),
+        None => Ok(AirportFilters::default()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AirportQuery {
+    #[serde(default)]
+    filters: AirportFilters,
+    /// The page of results to return, starting from 1.
+    #[serde(default = "AirportQuery::first_page")]
+    page: usize,
+    /// The number of airports per page.
+    limit: Option<usize>,
+    /// The airport fields to return. All fields are returned when this isn't specified.
+    fields: Option<Vec<String>>,
+}
+
+impl AirportQuery {
+    const DEFAULT_LIMIT: usize = 50;
+    const MAX_LIMIT: usize = 500;
+
+    #[inline(always)]
+    fn first_page() -> usize {
+        1
+    }
+
+    fn run(mut self, airports: &Airports) -> Result<JsonValue, status::Custom<JsonValue>> {
+        let page = self
+            .fetch_page(airports)
+            .map_err(ValidationErrors::into_response)?;
+
+        Ok(json!(page))
+    }
+
+    fn fetch_page<'a>(
+        &'a mut self,
+        airports: &'a Airports,
+    ) -> Result<AirportPage<'a>, ValidationErrors> {
+        let limit = self.limit.unwrap_or(Self::DEFAULT_LIMIT);
+        let mut errors = ValidationErrors::new();
+
+        if let Err(errs) = self.filters.prepare(airports) {
+            errors.extend_within("filters", errs);
+        }
+
+        if self.page == 0 {
+            errors.add(
+                "page",
+                ErrorCode::InvalidValue,
+                "page must be at least 1".into(),
+            );
+        }
+
+        if limit == 0 || limit > Self::MAX_LIMIT {
+            errors.add(
+                "limit",
+                ErrorCode::InvalidValue,
+                format!("limit must be between 1 and {}", Self::MAX_LIMIT),
+            );
+        }
+
+        for (i, field) in self.fields.iter().flatten().enumerate() {
+            if !AirportDetails::FIELDS.contains(&field.as_str()) {
+                errors.add(
+                    format!("fields[{}]", i),
+                    ErrorCode::InvalidValue,
+                    format!("unknown airport field \"{}\"", field),
+                );
+            }
+        }
+
+        errors.into_result()?;
+
+        // Every match is needed to know the total
+        let matches = self
+            .filters
+            .matching_airports_up_to(airports, usize::MAX)
+            .airports;
+
+        let start = (self.page - 1).saturating_mul(limit);
+        let fields = self.fields.as_deref();
+
+        let results = matches
+            .iter()
+            .skip(start)
+            .take(limit)
+            .map(|arpt| AirportDetails::new(arpt, airports).select(fields))
+            .collect();
+
+        Ok(AirportPage {
+            airports: results,
+            page: self.page,
+            limit,
+            total: matches.len(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AirportPage<'a> {
+    airports: Vec<SelectedFields<'a>>,
+    page: usize,
+    limit: usize,
+    total: usize,
+}
+
+struct NearestQuery {
+    lat: Option<f32>,
+    lon: Option<f32>,
+    /// The number of airports to return.
+    n: Option<usize>,
+    filters: AirportFilters,
+}
+
+impl NearestQuery {
+    const DEFAULT_COUNT: usize = 10;
+    const MAX_COUNT: usize = 100;
+
+    fn into_results(
+        mut self,
+        airports: &Airports,
+    ) -> Result<Vec<NearbyAirport<'_>>, ValidationErrors> {
+        let count = self.n.unwrap_or(Self::DEFAULT_COUNT);
+        let mut errors = ValidationErrors::new();
+
+        let mut coordinate = |field: &str, value: Option<f32>, max: f32| match value {
+            Some(value) if value.is_finite() && value.abs() <= max => value,
+            Some(_) => {
+                errors.add(
+                    field,
+                    ErrorCode::InvalidValue,
+                    format!("{} must be between -{} and {}", field, max, max),
+                );
+                0.0
+            }
+            None => {
+                errors.add(
+                    field,
+                    ErrorCode::InvalidValue,
+                    format!("{} is required", field),
+                );
+                0.0
+            }
+        };
+
+        let center = Position::new(
+            coordinate("lat", self.lat, 90.0),
+            coordinate("lon", self.lon, 180.0),
+        );
+
+        if count == 0 || count > Self::MAX_COUNT {
+            errors.add(
+                "n",
+                ErrorCode::InvalidValue,
+                format!("n must be between 1 and {}", Self::MAX_COUNT),
+            );
+        }
+
+        if let Err(errs) = self.filters.prepare(airports) {
+            errors.extend_within("filters", errs);
+        }
+
+        errors.into_result()?;
+
+        let results = self
+            .filters
+            .nearest_airports(airports, center, count)
+            .into_iter()
+            .map(|(airport, distance)| NearbyAirport {
+                airport: AirportDetails::new(airport, airports),
+                distance,
+                bearing: geodesic::measure(center, airport.position, DistanceOptions::default())
+                    .initial_course_deg as f32,
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct NearbyAirport<'a> {
+    airport: AirportDetails<'a>,
+    /// The distance to the airport in nautical miles.
+    distance: f32,
+    /// The initial true course in degrees to fly directly to the airport.
+    bearing: f32,
+}
+
+/// An airport along with the fields that are left out when it's part of a route.
+#[derive(Debug, Serialize)]
+pub struct AirportDetails<'a> {
+    #[serde(flatten)]
+    airport: &'a Airport,
+    #[serde(rename = "type")]
+    class: AirportType,
+    country: &'a str,
+    region: &'a str,
+    /// Navaids within `NAVAID_RADIUS_NM` of the airport, from closest to furthest.
+    navaids: Vec<NearbyNavaid<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct NearbyNavaid<'a> {
+    #[serde(flatten)]
+    navaid: &'a Navaid,
+    /// The distance from the airport in nautical miles.
+    distance: f32,
+}
+
+impl<'a> AirportDetails<'a> {
+    /// The names of every field that an airport is serialized with.
+    const FIELDS: [&'static str; 13] = [
+        "icao",
+        "name",
+        "iataCode",
+        "gpsCode",
+        "municipality",
+        "position",
+        "elevationFT",
+        "runways",
+        "frequencies",
+        "type",
+        "country",
+        "region",
+        "navaids",
+    ];
+
+    /// How far from an airport navaids are reported.
+    const NAVAID_RADIUS_NM: f32 = 30.0;
+
+    pub fn new(airport: &'a Airport, airports: &'a Airports) -> Self {
+        let navaids = airports
+            .navaids_within(airport.position, Self::NAVAID_RADIUS_NM)
+            .into_iter()
+            .map(|(navaid, distance)| NearbyNavaid { navaid, distance })
+            .collect();
+
+        Self {
+            airport,
+            class: airport.class,
+            country: &airport.country_name,
+            region: &airport.region,
+            navaids,
+        }
+    }
+
+    /// Serialize the airport with only the specified fields, or every field if there are none.
+    #[inline(always)]
+    pub fn select(self, fields: Option<&'a [String]>) -> SelectedFields<'a> {
+        SelectedFields {
+            details: self,
+            fields,
+        }
+    }
+}
+
+/// An airport that's serialized with only some of its fields.
+#[derive(Debug)]
+pub struct SelectedFields<'a> {
+    details: AirportDetails<'a>,
+    fields: Option<&'a [String]>,
+}
+
+impl<'a> SelectedFields<'a> {
+    fn entry<M, T>(&self, map: &mut M, field: &'static str, value: &T) -> Result<(), M::Error>
+    where
+        M: SerializeMap,
+        T: Serialize + ?Sized,
+    {
+        let selected = match self.fields {
+            Some(fields) => fields.iter().any(|name| name == field),
+            None => true,
+        };
+
+        if selected {
+            map.serialize_entry(field, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Serialize for SelectedFields<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let details = &self.details;
+        let airport = details.airport;
+        let mut map = serializer.serialize_map(None)?;
+
+        self.entry(&mut map, "icao", &airport.icao)?;
+        self.entry(&mut map, "name", &airport.name)?;
+        self.entry(&mut map, "iataCode", &airport.iata_code)?;
+        self.entry(&mut map, "gpsCode", &airport.gps_code)?;
+        self.entry(&mut map, "municipality", &airport.municipality)?;
+        self.entry(&mut map, "position", &airport.position)?;
+        self.entry(&mut map, "elevationFT", &airport.elevation_ft)?;
+        self.entry(&mut map, "runways", &airport.runways)?;
+        self.entry(&mut map, "frequencies", &airport.frequencies)?;
+        self.entry(&mut map, "type", &details.class)?;
+        self.entry(&mut map, "country", details.country)?;
+        self.entry(&mut map, "region", details.region)?;
+        self.entry(&mut map, "navaids", &details.navaids)?;
+
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::get_airports;
+
+    #[test]
+    fn find_by_ident() {
+        let airports = get_airports();
+        let find = |ident| airports.find_by_ident(ident).map(|arpt| arpt.icao.as_str());
+
+        assert_eq!(find("KSFO"), Some("KSFO"));
+        assert_eq!(find("nrt"), Some("RJAA"));
+        assert_eq!(find(" SAC "), Some("KSAC"));
+        assert_eq!(find("3ca1"), Some("3CA1"));
+        assert_eq!(find("ZZZZ"), None);
+    }
+
+    /// Run a query and get its page as JSON.
+    fn query(json: &str) -> Result<serde_json::Value, ValidationErrors> {
+        let mut query: AirportQuery = serde_json::from_str(json).unwrap();
+        let airports = get_airports();
+        let page = query.fetch_page(&airports)?;
+
+        Ok(serde_json::to_value(page).unwrap())
+    }
+
+    #[test]
+    fn list_airports() {
+        let page = query(r#"{ "filters": { "countries": ["United States"] } }"#).unwrap();
+
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["airports"][0]["icao"], "3CA1");
+        assert_eq!(page["airports"][1]["icao"], "KSAC");
+        assert_eq!(page["airports"][2]["icao"], "KSFO");
+
+        let page = query(
+            r#"{
+                "filters": { "airportType": ["large_airport", "heliport"] },
+                "page": 2,
+                "limit": 2,
+                "fields": ["icao", "name"]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["airports"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            page["airports"][0],
+            serde_json::json!({ "icao": "RJAA", "name": "Narita International Airport" })
+        );
+
+        let page = query(r#"{ "page": 10 }"#).unwrap();
+        assert!(page["airports"].as_array().unwrap().is_empty());
+
+        // Airports requested by their codes still have to match the other filters
+        let page =
+            query(r#"{ "filters": { "icao": "KSFO|KSAC|RJAA", "airportType": "large_airport" } }"#)
+                .unwrap();
+
+        assert_eq!(page["total"], 2);
+        assert_eq!(page["airports"][0]["icao"], "KSFO");
+        assert_eq!(page["airports"][1]["icao"], "RJAA");
+    }
+
+    #[test]
+    fn list_airports_validate() {
+        let errors = query(
+            r#"{
+                "filters": { "icao": "ZZZZ" },
+                "page": 0,
+                "limit": 1000,
+                "fields": ["icao", "altitude"]
+            }"#,
+        )
+        .unwrap_err();
+
+        let fields = errors
+            .errors()
+            .iter()
+            .map(|err| err.field.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(fields, vec!["filters.icao", "page", "limit", "fields[1]"]);
+    }
+
+    fn nearest(lat: f32, lon: f32, n: usize, filters: &str) -> Vec<(String, f32, f32)> {
+        let query = NearestQuery {
+            lat: Some(lat),
+            lon: Some(lon),
+            n: Some(n),
+            filters: serde_json::from_str(filters).unwrap(),
+        };
+
+        query
+            .into_results(&get_airports())
+            .unwrap()
+            .into_iter()
+            .map(|result| {
+                let icao = result.airport.airport.icao.clone();
+                (icao, result.distance.round(), result.bearing.round())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nearest_airports() {
+        // Over Oakland
+        let results = nearest(
+            37.8,
+            -122.25,
+            2,
+            r#"{ "excludedAirportTypes": "heliport" }"#,
+        );
+
+        assert_eq!(
+            results,
+            vec![("KSFO".into(), 12.0, 209.0), ("KSAC".into(), 56.0, 40.0)]
+        );
+
+        let results = nearest(37.8, -122.25, 10, r#"{ "airportType": "heliport" }"#);
+        assert_eq!(results, vec![("3CA1".into(), 7.0, 265.0)]);
+
+        // Every airport is searched when there aren't enough nearby
+        let results = nearest(37.8, -122.25, 10, "{}");
+        let icaos = results
+            .iter()
+            .map(|(icao, ..)| icao.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(icaos, vec!["3CA1", "KSFO", "KSAC", "RJAA"]);
+    }
+
+    #[test]
+    fn nearest_airports_validate() {
+        let query = NearestQuery {
+            lat: Some(91.0),
+            lon: None,
+            n: Some(0),
+            filters: serde_json::from_str(r#"{ "icao": "ZZZZ" }"#).unwrap(),
+        };
+
+        let errors = query.into_results(&get_airports()).unwrap_err();
+
+        let fields = errors
+            .errors()
+            .iter()
+            .map(|err| err.field.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(fields, vec!["lat", "lon", "n", "filters.icao"]);
+    }
+
+    #[test]
+    fn airport_details_fields() {
+        let airports = get_airports();
+        let ksfo = airports.find_by_icao("KSFO").unwrap();
+
+        let details = AirportDetails::new(ksfo, &airports);
+        let full = serde_json::to_value(&details).unwrap();
+        let value = serde_json::to_value(details.select(None)).unwrap();
+        let keys = value.as_object().unwrap().keys().collect::<Vec<_>>();
+
+        assert_eq!(value, full);
+        assert_eq!(keys.len(), AirportDetails::FIELDS.len());
+        assert!(keys
+            .iter()
+            .all(|key| AirportDetails::FIELDS.contains(&key.as_str())));
+
+        let fields = vec!["icao".to_string(), "navaids".to_string()];
+        let details = AirportDetails::new(ksfo, &airports);
+        let value = serde_json::to_value(details.select(Some(&fields))).unwrap();
+        let keys = value.as_object().unwrap().keys().collect::<Vec<_>>();
+
+        assert_eq!(keys, vec!["icao", "navaids"]);
+        assert_eq!(value["navaids"], full["navaids"]);
+    }
+
+    #[test]
+    fn airport_details() {
+        let airports = get_airports();
+        let ksfo = airports.find_by_ident("SFO").unwrap();
+
+        let details = serde_json::to_value(AirportDetails::new(ksfo, &airports)).unwrap();
+
+        assert_eq!(details["icao"], "KSFO");
+        assert_eq!(details["iataCode"], "SFO");
+        assert_eq!(details["type"], "large");
+        assert_eq!(details["country"], "United States");
+        assert_eq!(details["region"], "US-CA");
+        assert_eq!(details["runways"][0]["heMarker"]["name"], "01L");
+
+        // Navaids are found by distance, closest first, and not by their associated airport
+        let navaids = details["navaids"].as_array().unwrap();
+        let idents = navaids
+            .iter()
+            .map(|navaid| navaid["ident"].as_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(idents, vec!["SFO", "OAK"]);
+        assert_eq!(navaids[0]["type"], "VOR-DME");
+        assert!(navaids[1]["distance"].as_f64().unwrap() > 5.0);
+    }
+}
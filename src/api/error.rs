@@ -0,0 +1,255 @@
+use super::filter_expr::ParseError;
+use rocket::http::Status;
+use rocket::response::status;
+use rocket_contrib::json::{JsonError, JsonValue};
+use serde_derive::Serialize;
+use serde_json::error::Category;
+
+#[derive(Copy, Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request body is not valid JSON or doesn't match the expected format.
+    MalformedRequest,
+    InvalidValue,
+    /// A range has a minimum that is greater than its maximum.
+    InvalidRange,
+    InvalidExpression,
+    InvalidArea,
+    /// An airport code that doesn't refer to any known airport.
+    UnknownAirport,
+    /// An aircraft ID that isn't in the aircraft catalog.
+    UnknownAircraft,
+}
+
+impl ErrorCode {
+    /// Whether the error means that part of the request couldn't be parsed at all, rather
+    /// than it having a value that isn't allowed.
+    #[inline(always)]
+    pub fn is_syntax_error(self) -> bool {
+        match self {
+            Self::MalformedRequest | Self::InvalidExpression => true,
+            Self::InvalidValue
+            | Self::InvalidRange
+            | Self::InvalidArea
+            | Self::UnknownAirport
+            | Self::UnknownAircraft => false,
+        }
+    }
+}
+
+/// A problem with a single field of a request.
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    /// The path of the invalid field, such as `departure.expr`.
+    pub field: String,
+    pub code: ErrorCode,
+    pub message: String,
+    /// The position in the field's value where the error occurred, if it applies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+}
+
+impl FieldError {
+    pub fn new<S>(field: S, code: ErrorCode, message: String) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            field: field.into(),
+            code,
+            message,
+            position: None,
+        }
+    }
+
+    pub fn unknown_airport<S>(field: S, icao: &str) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(
+            field,
+            ErrorCode::UnknownAirport,
+            format!("no airport with ICAO {}", icao.to_ascii_uppercase()),
+        )
+    }
+
+    /// Like `unknown_airport`, but for fields that accept ICAO, IATA or GPS codes.
+    pub fn unknown_ident<S>(field: S, ident: &str) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(
+            field,
+            ErrorCode::UnknownAirport,
+            format!("no airport with code {}", ident.to_ascii_uppercase()),
+        )
+    }
+
+    pub fn expression<S>(field: S, err: ParseError) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            position: Some(err.position),
+            ..Self::new(field, ErrorCode::InvalidExpression, err.message)
+        }
+    }
+}
+
+/// Every problem that was found while validating a request.
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, error: FieldError) {
+        self.0.push(error);
+    }
+
+    #[inline(always)]
+    pub fn add<S>(&mut self, field: S, code: ErrorCode, message: String)
+    where
+        S: Into<String>,
+    {
+        self.push(FieldError::new(field, code, message));
+    }
+
+    /// Add the errors of a nested object, with their field paths prefixed with `parent`.
+    pub fn extend_within(&mut self, parent: &str, errors: Self) {
+        self.0.extend(errors.0.into_iter().map(|mut err| {
+            err.field = format!("{}.{}", parent, err.field);
+            err
+        }));
+    }
+
+    #[inline(always)]
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Get the response for the errors, which is a 400 when part of the request couldn't be
+    /// parsed, such as a filter expression, and a 422 otherwise.
+    pub fn into_response(self) -> status::Custom<JsonValue> {
+        let status = if self.0.iter().any(|err| err.code.is_syntax_error()) {
+            Status::BadRequest
+        } else {
+            Status::UnprocessableEntity
+        };
+
+        self.into_status(status)
+    }
+
+    pub fn into_status(self, status: Status) -> status::Custom<JsonValue> {
+        status::Custom(status, json!({ "errors": self.0 }))
+    }
+}
+
+/// Get the response for a JSON request body that couldn't be read or parsed, which keeps
+/// serde's description of what went wrong and where.
+///
+/// Syntax errors get a 400 response and bodies that don't match the expected format get a 422,
+/// like the catchers that handle bodies that aren't JSON at all.
+pub fn malformed_json(err: JsonError) -> status::Custom<JsonValue> {
+    let (status, message) = match err {
+        JsonError::Parse(_, err) => {
+            let status = match err.classify() {
+                Category::Data => Status::UnprocessableEntity,
+                Category::Io | Category::Syntax | Category::Eof => Status::BadRequest,
+            };
+
+            (status, err.to_string())
+        }
+        JsonError::Io(err) => (
+            Status::BadRequest,
+            format!("failed to read the request body: {}", err),
+        ),
+    };
+
+    let mut errors = ValidationErrors::new();
+    errors.add("", ErrorCode::MalformedRequest, message);
+    errors.into_status(status)
+}
+
+/// A fallback for requests that fail before their JSON body is parsed, such as when it isn't JSON.
+#[catch(400)]
+pub fn bad_request() -> JsonValue {
+    malformed_request("the request could not be parsed")
+}
+
+/// A fallback for requests that fail before their JSON body is parsed.
+#[catch(422)]
+pub fn unprocessable_entity() -> JsonValue {
+    malformed_request("the request body does not match the expected format")
+}
+
+fn malformed_request(message: &str) -> JsonValue {
+    json!({
+        "errors": [FieldError::new("", ErrorCode::MalformedRequest, message.into())]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::filter_expr::Expr;
+    use serde_derive::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Query {
+        #[allow(dead_code)]
+        limit: u32,
+    }
+
+    fn response(body: &str) -> (u16, serde_json::Value) {
+        let err = serde_json::from_str::<Query>(body).unwrap_err();
+        let status::Custom(status, body) = malformed_json(JsonError::Parse(body, err));
+        (status.code, body["errors"][0].clone())
+    }
+
+    #[test]
+    fn malformed_json_errors() {
+        let (status, error) = response(r#"{ "limit": "ten" }"#);
+        assert_eq!(status, 422);
+        assert_eq!(error["code"], "malformed_request");
+        assert_eq!(
+            error["message"],
+            "invalid type: string \"ten\", expected u32 at line 1 column 16"
+        );
+
+        let (status, error) = response(r#"{ "limit": "#);
+        assert_eq!(status, 400);
+        assert!(error["message"].as_str().unwrap().contains("line 1"));
+    }
+
+    #[test]
+    fn validation_error_status() {
+        let status = |errors: ValidationErrors| errors.into_response().0.code;
+
+        let mut errors = ValidationErrors::new();
+        errors.add("limit", ErrorCode::InvalidValue, "too high".into());
+        errors.add("radius", ErrorCode::InvalidRange, "backwards".into());
+        assert_eq!(status(errors), 422);
+
+        // A malformed expression is reported as a bad request, even alongside other errors
+        let mut errors = ValidationErrors::new();
+        errors.add("radius", ErrorCode::InvalidRange, "backwards".into());
+        errors.push(FieldError::expression(
+            "expr",
+            Expr::parse("type = ").unwrap_err(),
+        ));
+        assert_eq!(status(errors), 400);
+    }
+}
@@ -0,0 +1,221 @@
+use super::{CompareOp, ParseError};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenKind {
+    /// A bare word, such as a field name, keyword, or unquoted value.
+    Word(String),
+    /// A quoted string.
+    Str(String),
+    /// A bare word that is also a valid number.
+    ///
+    /// The original text is kept so it can also be used as a text value.
+    Number(f64, String),
+    Op(CompareOp),
+    LeftParen,
+    RightParen,
+    Comma,
+    End,
+}
+
+impl TokenKind {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Word(word) => format!("\"{}\"", word),
+            Self::Str(value) => format!("string \"{}\"", value),
+            Self::Number(_, text) => format!("number {}", text),
+            Self::Op(op) => format!("operator \"{}\"", op),
+            Self::LeftParen => "\"(\"".into(),
+            Self::RightParen => "\")\"".into(),
+            Self::Comma => "\",\"".into(),
+            Self::End => "end of expression".into(),
+        }
+    }
+
+    /// Returns true if this token is the specified keyword, ignoring case.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        match self {
+            Self::Word(word) => word.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Character offset of the start of the token in the source expression.
+    pub position: usize,
+}
+
+impl Token {
+    #[inline(always)]
+    fn new(kind: TokenKind, position: usize) -> Self {
+        Self { kind, position }
+    }
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let start = pos;
+        let cur = chars[pos];
+        let next = chars.get(pos + 1).copied();
+
+        let kind = match cur {
+            c if c.is_whitespace() => {
+                pos += 1;
+                continue;
+            }
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            ',' => TokenKind::Comma,
+            '=' if next == Some('=') => {
+                pos += 1;
+                TokenKind::Op(CompareOp::Equal)
+            }
+            '=' => TokenKind::Op(CompareOp::Equal),
+            '!' if next == Some('=') => {
+                pos += 1;
+                TokenKind::Op(CompareOp::NotEqual)
+            }
+            '!' if next == Some('~') => {
+                pos += 1;
+                TokenKind::Op(CompareOp::NotMatches)
+            }
+            '<' if next == Some('=') => {
+                pos += 1;
+                TokenKind::Op(CompareOp::LessOrEqual)
+            }
+            '<' => TokenKind::Op(CompareOp::LessThan),
+            '>' if next == Some('=') => {
+                pos += 1;
+                TokenKind::Op(CompareOp::GreaterOrEqual)
+            }
+            '>' => TokenKind::Op(CompareOp::GreaterThan),
+            '~' => TokenKind::Op(CompareOp::Matches),
+            '"' | '\'' => {
+                let (value, end) = read_string(&chars, pos)?;
+                pos = end;
+                TokenKind::Str(value)
+            }
+            c if is_word_char(c) => {
+                let end = chars[pos..]
+                    .iter()
+                    .position(|&c| !is_word_char(c))
+                    .map(|len| pos + len)
+                    .unwrap_or_else(|| chars.len());
+
+                let word = chars[pos..end].iter().collect::<String>();
+                pos = end - 1;
+
+                match word.parse() {
+                    Ok(num) => TokenKind::Number(num, word),
+                    Err(_) => TokenKind::Word(word),
+                }
+            }
+            c => {
+                return Err(ParseError::new(
+                    start,
+                    format!("unexpected character \"{}\"", c),
+                ))
+            }
+        };
+
+        tokens.push(Token::new(kind, start));
+        pos += 1;
+    }
+
+    tokens.push(Token::new(TokenKind::End, chars.len()));
+    Ok(tokens)
+}
+
+#[inline(always)]
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+/// Read a quoted string starting at `start`, returning its contents and the position of the closing quote.
+fn read_string(chars: &[char], start: usize) -> Result<(String, usize), ParseError> {
+    let quote = chars[start];
+    let mut value = String::new();
+    let mut pos = start + 1;
+
+    while pos < chars.len() {
+        match chars[pos] {
+            '\\' if pos + 1 < chars.len() => {
+                value.push(chars[pos + 1]);
+                pos += 2;
+            }
+            c if c == quote => return Ok((value, pos)),
+            c => {
+                value.push(c);
+                pos += 1;
+            }
+        }
+    }
+
+    Err(ParseError::new(start, "unterminated string"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_condition() {
+        assert_eq!(
+            kinds(r#"country = "United States" and longest_runway>=8000"#),
+            vec![
+                TokenKind::Word("country".into()),
+                TokenKind::Op(CompareOp::Equal),
+                TokenKind::Str("United States".into()),
+                TokenKind::Word("and".into()),
+                TokenKind::Word("longest_runway".into()),
+                TokenKind::Op(CompareOp::GreaterOrEqual),
+                TokenKind::Number(8000.0, "8000".into()),
+                TokenKind::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_words_and_numbers() {
+        assert_eq!(
+            kinds("region = US-HI, 3CA1 -12.5 'it\\'s'"),
+            vec![
+                TokenKind::Word("region".into()),
+                TokenKind::Op(CompareOp::Equal),
+                TokenKind::Word("US-HI".into()),
+                TokenKind::Comma,
+                TokenKind::Word("3CA1".into()),
+                TokenKind::Number(-12.5, "-12.5".into()),
+                TokenKind::Str("it's".into()),
+                TokenKind::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_errors() {
+        assert_eq!(
+            tokenize(r#"icao = "KSFO"#),
+            Err(ParseError::new(7, "unterminated string"))
+        );
+
+        assert_eq!(
+            tokenize("icao & KSFO"),
+            Err(ParseError::new(5, "unexpected character \"&\""))
+        );
+    }
+}
@@ -0,0 +1,385 @@
+mod lexer;
+mod parser;
+
+use super::icao_pattern::glob_matches;
+use crate::airport_data::{Airport, AirportType};
+use parser::Parser;
+use std::error::Error;
+use std::fmt;
+
+/// A boolean expression that can be evaluated against an airport.
+///
+/// Expressions are made up of conditions like `longest_runway > 8000` or
+/// `type in (large, medium)` that can be combined with `and`, `or`, `not` and parentheses.
+#[derive(Debug, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, CompareOp, Literal),
+    In(Field, Vec<Literal>),
+}
+
+impl Expr {
+    pub fn parse<S>(source: S) -> Result<Self, ParseError>
+    where
+        S: AsRef<str>,
+    {
+        let tokens = lexer::tokenize(source.as_ref())?;
+        Parser::new(tokens).parse()
+    }
+
+    pub fn matches(&self, airport: &Airport) -> bool {
+        match self {
+            Self::And(lhs, rhs) => lhs.matches(airport) && rhs.matches(airport),
+            Self::Or(lhs, rhs) => lhs.matches(airport) || rhs.matches(airport),
+            Self::Not(expr) => !expr.matches(airport),
+            Self::Compare(field, op, value) => match field.value(airport) {
+                Some(field_value) => op.eval(&field_value, value),
+                None => false,
+            },
+            Self::In(field, values) => match field.value(airport) {
+                Some(field_value) => values
+                    .iter()
+                    .any(|value| CompareOp::Equal.eval(&field_value, value)),
+                None => false,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// Character offset in the expression where the error occurred.
+    pub position: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new<S>(position: usize, message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Field {
+    Type,
+    Icao,
+    Country,
+    Region,
+    LongestRunway,
+    RunwayCount,
+    Latitude,
+    Longitude,
+    Elevation,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        let field = match name.to_ascii_lowercase().as_str() {
+            "type" => Self::Type,
+            "icao" => Self::Icao,
+            "country" => Self::Country,
+            "region" => Self::Region,
+            "longest_runway" => Self::LongestRunway,
+            "runways" | "runway_count" => Self::RunwayCount,
+            "lat" | "latitude" => Self::Latitude,
+            "lon" | "longitude" => Self::Longitude,
+            "elevation" => Self::Elevation,
+            _ => return None,
+        };
+
+        Some(field)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Type => "type",
+            Self::Icao => "icao",
+            Self::Country => "country",
+            Self::Region => "region",
+            Self::LongestRunway => "longest_runway",
+            Self::RunwayCount => "runways",
+            Self::Latitude => "latitude",
+            Self::Longitude => "longitude",
+            Self::Elevation => "elevation",
+        }
+    }
+
+    fn kind(self) -> FieldKind {
+        match self {
+            Self::Type => FieldKind::Type,
+            Self::Icao | Self::Country | Self::Region => FieldKind::Text,
+            Self::LongestRunway
+            | Self::RunwayCount
+            | Self::Latitude
+            | Self::Longitude
+            | Self::Elevation => FieldKind::Number,
+        }
+    }
+
+    fn value(self, airport: &Airport) -> Option<FieldValue<'_>> {
+        let value = match self {
+            Self::Type => FieldValue::Type(airport.class),
+            Self::Icao => FieldValue::Text(&airport.icao),
+            Self::Country => FieldValue::Text(&airport.country_name),
+            Self::Region => FieldValue::Text(&airport.region),
+            Self::LongestRunway => {
+                let longest = airport.runways.iter().filter_map(|rw| rw.length_ft).max()?;
+                FieldValue::Number(f64::from(longest))
+            }
+            Self::RunwayCount => FieldValue::Number(airport.runways.len() as f64),
+            Self::Latitude => FieldValue::Number(f64::from(airport.position.latitude_deg)),
+            Self::Longitude => FieldValue::Number(f64::from(airport.position.longitude_deg)),
+            Self::Elevation => FieldValue::Number(f64::from(airport.elevation_ft?)),
+        };
+
+        Some(value)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum FieldKind {
+    Text,
+    Number,
+    Type,
+}
+
+impl FieldKind {
+    fn supports(self, op: CompareOp) -> bool {
+        use CompareOp::*;
+
+        match self {
+            Self::Text => matches!(op, Equal | NotEqual | Matches | NotMatches),
+            Self::Number => !matches!(op, Matches | NotMatches),
+            Self::Type => matches!(op, Equal | NotEqual),
+        }
+    }
+
+    fn expected_value(self) -> &'static str {
+        match self {
+            Self::Text => "a string",
+            Self::Number => "a number",
+            Self::Type => "an airport type",
+        }
+    }
+}
+
+enum FieldValue<'a> {
+    Text(&'a str),
+    Number(f64),
+    Type(AirportType),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Text(String),
+    Number(f64),
+    Type(AirportType),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CompareOp {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessOrEqual,
+    GreaterThan,
+    GreaterOrEqual,
+    /// Matches a glob pattern, where `*` matches any number of characters and `?` matches one.
+    Matches,
+    NotMatches,
+}
+
+impl CompareOp {
+    fn eval(self, field: &FieldValue, value: &Literal) -> bool {
+        match (field, value) {
+            (FieldValue::Text(field), Literal::Text(value)) => match self {
+                Self::Equal => field.eq_ignore_ascii_case(value),
+                Self::NotEqual => !field.eq_ignore_ascii_case(value),
+                Self::Matches => glob_matches(value.as_bytes(), field.as_bytes()),
+                Self::NotMatches => !glob_matches(value.as_bytes(), field.as_bytes()),
+                _ => false,
+            },
+            (FieldValue::Number(field), Literal::Number(value)) => match self {
+                Self::Equal => (field - value).abs() < f64::EPSILON,
+                Self::NotEqual => (field - value).abs() >= f64::EPSILON,
+                Self::LessThan => field < value,
+                Self::LessOrEqual => field <= value,
+                Self::GreaterThan => field > value,
+                Self::GreaterOrEqual => field >= value,
+                Self::Matches | Self::NotMatches => false,
+            },
+            (FieldValue::Type(field), Literal::Type(value)) => match self {
+                Self::Equal => field == value,
+                Self::NotEqual => field != value,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::LessThan => "<",
+            Self::LessOrEqual => "<=",
+            Self::GreaterThan => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Matches => "~",
+            Self::NotMatches => "!~",
+        };
+
+        f.write_str(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::{Position, Runway, RunwaySurface};
+    use std::collections::HashMap;
+
+    fn airport(
+        icao: &str,
+        class: AirportType,
+        country: &str,
+        region: &str,
+        rwy_len: u32,
+    ) -> Airport {
+        Airport {
+            icao: icao.into(),
+            name: String::new(),
+            iata_code: None,
+            gps_code: None,
+            municipality: None,
+            class,
+            position: Position::new(0.0, 0.0),
+            elevation_ft: None,
+            runways: vec![Runway {
+                length_ft: Some(rwy_len),
+                width_ft: None,
+                surface: RunwaySurface::Paved,
+                lighted: true,
+                closed: false,
+                he_marker: None,
+                le_marker: None,
+            }],
+            frequencies: HashMap::new(),
+            country_name: country.into(),
+            region: region.into(),
+        }
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let expr = Expr::parse("icao = A or icao = B and not icao = C").unwrap();
+
+        let cmp = |value: &str| {
+            Box::new(Expr::Compare(
+                Field::Icao,
+                CompareOp::Equal,
+                Literal::Text(value.into()),
+            ))
+        };
+
+        assert_eq!(
+            expr,
+            Expr::Or(
+                cmp("A"),
+                Box::new(Expr::And(cmp("B"), Box::new(Expr::Not(cmp("C")))))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        fn err(source: &str) -> ParseError {
+            Expr::parse(source).unwrap_err()
+        }
+
+        assert_eq!(err("").position, 0);
+        assert_eq!(
+            err("altitude > 10"),
+            ParseError::new(0, "unknown field \"altitude\"")
+        );
+
+        assert_eq!(
+            err("longest_runway > big"),
+            ParseError::new(17, "expected a number, found \"big\"")
+        );
+
+        assert_eq!(
+            err("country < Japan"),
+            ParseError::new(8, "operator \"<\" can't be used with field \"country\"")
+        );
+
+        assert_eq!(
+            err("type in (large, huge)"),
+            ParseError::new(16, "unknown airport type \"huge\"")
+        );
+
+        assert_eq!(
+            err("(icao = KSFO"),
+            ParseError::new(12, "expected \")\", found end of expression")
+        );
+
+        assert_eq!(
+            err("icao = KSFO KSAC"),
+            ParseError::new(
+                12,
+                "expected \"and\", \"or\" or end of expression, found \"KSAC\""
+            )
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        let rjtt = airport("RJTT", AirportType::Large, "Japan", "JP-13", 11024);
+        let rjaa = airport("RJAA", AirportType::Large, "Japan", "JP-12", 13123);
+        let phnl = airport("PHNL", AirportType::Large, "United States", "US-HI", 12300);
+        let phny = airport("PHNY", AirportType::Small, "United States", "US-HI", 5001);
+        let ksfo = airport("KSFO", AirportType::Large, "United States", "US-CA", 11870);
+
+        let expr = Expr::parse(
+            r#"type in (large, medium) and (country = "Japan" or region = "US-HI")
+               and not icao ~ "RJT*" and longest_runway > 8000"#,
+        )
+        .unwrap();
+
+        assert!(expr.matches(&rjaa));
+        assert!(expr.matches(&phnl));
+        assert!(!expr.matches(&rjtt));
+        assert!(!expr.matches(&phny));
+        assert!(!expr.matches(&ksfo));
+
+        let expr = Expr::parse("region not in (us-hi, US-CA) and runways >= 1").unwrap();
+
+        assert!(expr.matches(&rjtt));
+        assert!(!expr.matches(&phnl));
+        assert!(!expr.matches(&ksfo));
+    }
+}
@@ -0,0 +1,232 @@
+use super::lexer::{Token, TokenKind};
+use super::{Expr, Field, FieldKind, Literal, ParseError};
+use crate::airport_data::AirportType;
+
+/// A recursive descent parser for filter expressions.
+///
+/// The grammar, in order of increasing precedence:
+///
+/// ```text
+/// or        := and ("or" and)*
+/// and       := unary ("and" unary)*
+/// unary     := "not" unary | primary
+/// primary   := "(" or ")" | condition
+/// condition := field op value | field ["not"] "in" "(" value ("," value)* ")"
+/// ```
+pub struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    pub fn parse(mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_or()?;
+
+        match self.peek() {
+            TokenKind::End => Ok(expr),
+            other => Err(self.unexpected(other, "\"and\", \"or\" or end of expression")),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_and()?;
+
+        while self.peek().is_keyword("or") {
+            self.advance();
+            let rhs = self.parse_and()?;
+            expr = Expr::Or(Box::new(expr), Box::new(rhs));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_unary()?;
+
+        while self.peek().is_keyword("and") {
+            self.advance();
+            let rhs = self.parse_unary()?;
+            expr = Expr::And(Box::new(expr), Box::new(rhs));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.peek().is_keyword("not") {
+            self.advance();
+            let expr = self.parse_unary()?;
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            TokenKind::LeftParen => {
+                self.advance();
+                let expr = self.parse_or()?;
+                self.expect(&TokenKind::RightParen, "\")\"")?;
+                Ok(expr)
+            }
+            TokenKind::Word(_) => self.parse_condition(),
+            other => Err(self.unexpected(other, "a field name, \"not\" or \"(\"")),
+        }
+    }
+
+    fn parse_condition(&mut self) -> Result<Expr, ParseError> {
+        let field = self.parse_field()?;
+
+        let negated = if self.peek().is_keyword("not") {
+            self.advance();
+            true
+        } else {
+            false
+        };
+
+        if self.peek().is_keyword("in") {
+            self.advance();
+            let values = self.parse_list(field)?;
+            let expr = Expr::In(field, values);
+
+            if negated {
+                return Ok(Expr::Not(Box::new(expr)));
+            }
+
+            return Ok(expr);
+        }
+
+        if negated {
+            let token = self.peek().clone();
+            return Err(self.unexpected(&token, "\"in\""));
+        }
+
+        let op = match self.peek() {
+            TokenKind::Op(op) => *op,
+            other => return Err(self.unexpected(other, "a comparison operator or \"in\"")),
+        };
+
+        let op_pos = self.position();
+
+        if !field.kind().supports(op) {
+            return Err(ParseError::new(
+                op_pos,
+                format!("operator \"{}\" can't be used with field \"{}\"", op, field),
+            ));
+        }
+
+        self.advance();
+        let value = self.parse_value(field)?;
+
+        Ok(Expr::Compare(field, op, value))
+    }
+
+    fn parse_field(&mut self) -> Result<Field, ParseError> {
+        let pos = self.position();
+
+        let name = match self.peek() {
+            TokenKind::Word(name) => name,
+            other => return Err(self.unexpected(other, "a field name")),
+        };
+
+        let field = Field::from_name(name)
+            .ok_or_else(|| ParseError::new(pos, format!("unknown field \"{}\"", name)))?;
+
+        self.advance();
+        Ok(field)
+    }
+
+    fn parse_list(&mut self, field: Field) -> Result<Vec<Literal>, ParseError> {
+        self.expect(&TokenKind::LeftParen, "\"(\"")?;
+
+        let mut values = vec![self.parse_value(field)?];
+
+        while let TokenKind::Comma = self.peek() {
+            self.advance();
+            values.push(self.parse_value(field)?);
+        }
+
+        self.expect(&TokenKind::RightParen, "\",\" or \")\"")?;
+        Ok(values)
+    }
+
+    fn parse_value(&mut self, field: Field) -> Result<Literal, ParseError> {
+        let pos = self.position();
+
+        let value = match (field.kind(), self.peek()) {
+            (FieldKind::Text, TokenKind::Word(value))
+            | (FieldKind::Text, TokenKind::Str(value))
+            | (FieldKind::Text, TokenKind::Number(_, value)) => Literal::Text(value.clone()),
+            (FieldKind::Number, TokenKind::Number(value, _)) => Literal::Number(*value),
+            (FieldKind::Type, TokenKind::Word(value))
+            | (FieldKind::Type, TokenKind::Str(value)) => {
+                let kind = parse_airport_type(value).ok_or_else(|| {
+                    ParseError::new(pos, format!("unknown airport type \"{}\"", value))
+                })?;
+
+                Literal::Type(kind)
+            }
+            (kind, other) => return Err(self.unexpected(other, kind.expected_value())),
+        };
+
+        self.advance();
+        Ok(value)
+    }
+
+    fn expect(&mut self, kind: &TokenKind, expected: &str) -> Result<(), ParseError> {
+        if self.peek() != kind {
+            let token = self.peek().clone();
+            return Err(self.unexpected(&token, expected));
+        }
+
+        self.advance();
+        Ok(())
+    }
+
+    fn unexpected(&self, found: &TokenKind, expected: &str) -> ParseError {
+        ParseError::new(
+            self.position(),
+            format!("expected {}, found {}", expected, found.describe()),
+        )
+    }
+
+    #[inline(always)]
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.pos].kind
+    }
+
+    #[inline(always)]
+    fn position(&self) -> usize {
+        self.tokens[self.pos].position
+    }
+
+    #[inline(always)]
+    fn advance(&mut self) {
+        // The last token is always the end token, so we should never move past it
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+    }
+}
+
+fn parse_airport_type(value: &str) -> Option<AirportType> {
+    let value = value.to_ascii_lowercase();
+
+    let kind = match value.as_str() {
+        "large" => AirportType::Large,
+        "medium" => AirportType::Medium,
+        "small" => AirportType::Small,
+        "seaplane" | "seaplanebase" => AirportType::SeaplaneBase,
+        other => AirportType::from_str(other),
+    };
+
+    match kind {
+        AirportType::Unknown => None,
+        kind => Some(kind),
+    }
+}
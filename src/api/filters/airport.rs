@@ -0,0 +1,1366 @@
+use super::Range;
+use crate::airport_data::{
+    Airport, AirportType, Airports, FrequencyType, Position, Runway, RunwaySurface,
+};
+use crate::api::aircraft::Aircraft;
+use crate::api::error::{ErrorCode, FieldError, ValidationErrors};
+use crate::api::filter_expr::Expr;
+use crate::api::icao_pattern::IcaoPattern;
+use crate::api::units::{DistanceUnit, LengthUnit};
+use crate::geo::area::{Area, BoundingBox, GeoJson};
+use crate::geo::geodesic::{self, DistanceOptions};
+use rand::seq::SliceRandom;
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use serde_derive::Deserialize;
+use std::fmt;
+use std::result;
+
+const MAX_AIRPORTS_TO_GET: usize = 2000;
+
+/// Pick airports at random for a departure or arrival set that has no filters.
+pub fn random_airport_set<'a>(
+    airports: &'a [Airport],
+    required_runways: Option<&RunwayFilters>,
+) -> AirportMatches<'a> {
+    let candidates = airports
+        .iter()
+        .filter(|arpt| !AirportFilters::DEFAULT_EXCLUDED_TYPES.contains(&arpt.class))
+        .filter(|arpt| match required_runways {
+            Some(runways) => runways.matches(&arpt.runways),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    let airports = candidates
+        .choose_multiple(&mut rand::thread_rng(), MAX_AIRPORTS_TO_GET)
+        .copied()
+        .collect();
+
+    AirportMatches {
+        airports,
+        truncated: candidates.len() > MAX_AIRPORTS_TO_GET,
+        random: true,
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AirportFilters {
+    icao: Option<IcaoPattern>,
+    #[serde(rename = "airportType", default)]
+    airport_types: AirportTypes,
+    #[serde(rename = "excludedAirportTypes")]
+    excluded_types: Option<AirportTypes>,
+    #[serde(rename = "runwayLength")]
+    runway_length: Option<RunwayLength>,
+    runways: Option<RunwayFilters>,
+    elevation: Option<ElevationRange>,
+    services: Option<ServiceFilters>,
+    radius: Option<RadiusFilter>,
+    area: Option<AreaFilter>,
+    #[serde(skip)]
+    resolved_area: Option<Area>,
+    #[serde(default)]
+    countries: Vec<String>,
+    expr: Option<String>,
+    #[serde(skip)]
+    parsed_expr: Option<Expr>,
+    /// Runways required by the selected aircraft, which apply on top of `runways`.
+    #[serde(skip)]
+    pub required_runways: Option<RunwayFilters>,
+    /// How distances from the center of `radius` are measured, which follows the route distances of a search.
+    #[serde(skip)]
+    pub distance: DistanceOptions,
+}
+
+impl AirportFilters {
+    /// Airport types that are left out of random departure and arrival sets, since
+    /// there are no filters to request them with.
+    const DEFAULT_EXCLUDED_TYPES: [AirportType; 3] = [
+        AirportType::Closed,
+        AirportType::Heliport,
+        AirportType::SeaplaneBase,
+    ];
+
+    pub fn prepare(&mut self, airports: &Airports) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let Some(codes) = self.icao.as_ref().and_then(IcaoPattern::exact_codes) {
+            for code in codes {
+                if airports.find_by_icao(code).is_none() {
+                    errors.push(FieldError::unknown_airport("icao", code));
+                }
+            }
+        }
+
+        if let Some(runways) = &self.runways {
+            let ranges = [
+                ("runways.length", &runways.length),
+                ("runways.width", &runways.width),
+            ];
+
+            for (field, range) in ranges.iter() {
+                if let Err(msg) = range.validate() {
+                    errors.add(*field, ErrorCode::InvalidRange, msg);
+                }
+            }
+
+            if runways.min_count == Some(0) {
+                errors.add(
+                    "runways.minCount",
+                    ErrorCode::InvalidValue,
+                    "must be at least 1, got 0".into(),
+                );
+            }
+        }
+
+        if let Some(elevation) = &self.elevation {
+            if let Err(msg) = elevation.range.validate() {
+                errors.add("elevation", ErrorCode::InvalidRange, msg);
+            }
+        }
+
+        if let Some(radius) = &self.radius {
+            if let Err(msg) = radius.range.validate() {
+                errors.add("radius", ErrorCode::InvalidRange, msg);
+            }
+
+            if let RadiusCenter::Icao(icao) = &radius.center {
+                if radius.center(airports).is_none() {
+                    errors.push(FieldError::unknown_airport("radius.center", icao));
+                }
+            }
+        }
+
+        self.parsed_expr = match &self.expr {
+            Some(expr) => match Expr::parse(expr) {
+                Ok(expr) => Some(expr),
+                Err(err) => {
+                    errors.push(FieldError::expression("expr", err));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        self.resolved_area = match &self.area {
+            Some(area) => match area.resolve() {
+                Ok(area) => Some(area),
+                Err(err) => {
+                    errors.add("area", ErrorCode::InvalidArea, format!("{:#}", err));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        errors.into_result()
+    }
+
+    #[inline(always)]
+    pub fn matching_airports<'a>(&self, airports: &'a Airports) -> AirportMatches<'a> {
+        self.matching_airports_up_to(airports, MAX_AIRPORTS_TO_GET)
+    }
+
+    /// Get the airports that match the filters, stopping once `limit` airports have been found.
+    ///
+    /// Airports are returned in ICAO order, unless they were requested by their exact codes.
+    pub fn matching_airports_up_to<'a>(
+        &self,
+        airports: &'a Airports,
+        limit: usize,
+    ) -> AirportMatches<'a> {
+        // Only the requested airports need to be looked at when they're given by their exact codes
+        let requested = self
+            .icao
+            .as_ref()
+            .and_then(IcaoPattern::exact_codes)
+            .map(|codes| {
+                codes
+                    .into_iter()
+                    .filter_map(|code| airports.find_by_icao(code))
+                    .collect::<Vec<_>>()
+            });
+
+        let bounded_radius = self.radius.as_ref().and_then(|radius| {
+            let center = radius.center(airports)?;
+            let max = radius.range_nm().max?;
+            Some((center, max))
+        });
+
+        self.with_matcher(airports, |matcher| match (requested, bounded_radius) {
+            (Some(requested), _) => Self::airport_matches(matcher, requested, limit),
+            // Only airports near the center need to be looked at when the radius is bounded
+            (None, Some((center, max))) => {
+                let candidates = airports.within_radius(center, max, self.distance);
+                Self::airport_matches(matcher, candidates, limit)
+            }
+            (None, None) => Self::airport_matches(matcher, airports.iter(), limit),
+        })
+        .unwrap_or_default()
+    }
+
+    /// Get the airports closest to `center` that match the filters, along with their distance
+    /// in nautical miles. Results are sorted from closest to furthest.
+    pub fn nearest_airports<'a>(
+        &self,
+        airports: &'a Airports,
+        center: Position,
+        count: usize,
+    ) -> Vec<(&'a Airport, f32)> {
+        self.with_matcher(airports, |matcher| {
+            airports.nearest(center, count, self.distance, matcher)
+        })
+        .unwrap_or_default()
+    }
+
+    /// Build a function that tests if an airport matches every filter and pass it to `func`.
+    ///
+    /// Returns `None` without calling `func` when no airport can match the filters.
+    fn with_matcher<F, R>(&self, airports: &Airports, func: F) -> Option<R>
+    where
+        F: FnOnce(&dyn Fn(&Airport) -> bool) -> R,
+    {
+        let icao_filter = match &self.icao {
+            Some(pattern) => {
+                OptionalFilter::Evaluate(move |arpt: &Airport| pattern.matches(&arpt.icao))
+            }
+            None => OptionalFilter::Passthrough,
+        };
+
+        let radius = match &self.radius {
+            Some(radius) => match radius.center(airports) {
+                Some(center) => Some((center, radius.range_nm())),
+                None => return None,
+            },
+            None => None,
+        };
+
+        let radius_filter = match &radius {
+            Some((center, range)) => OptionalFilter::Evaluate(move |arpt: &Airport| {
+                range.within(&geodesic::distance_nm(
+                    *center,
+                    arpt.position,
+                    self.distance,
+                ))
+            }),
+            None => OptionalFilter::Passthrough,
+        };
+
+        let runway_len_filter = match self.runway_length {
+            Some(len) => OptionalFilter::Evaluate(move |arpt| len.fits_any(&arpt.runways)),
+            None => OptionalFilter::Passthrough,
+        };
+
+        let runway_filter = match &self.runways {
+            Some(filters) => {
+                OptionalFilter::Evaluate(move |arpt: &Airport| filters.matches(&arpt.runways))
+            }
+            None => OptionalFilter::Passthrough,
+        };
+
+        let elevation_filter = match &self.elevation {
+            Some(range) => {
+                OptionalFilter::Evaluate(move |arpt: &Airport| range.within(arpt.elevation_ft))
+            }
+            None => OptionalFilter::Passthrough,
+        };
+
+        let services_filter = match &self.services {
+            Some(services) => {
+                OptionalFilter::Evaluate(move |arpt: &Airport| services.matches(arpt))
+            }
+            None => OptionalFilter::Passthrough,
+        };
+
+        let area_filter = match &self.resolved_area {
+            Some(area) => {
+                OptionalFilter::Evaluate(move |arpt: &Airport| area.contains(arpt.position))
+            }
+            None => OptionalFilter::Passthrough,
+        };
+
+        let country_filter = match self.countries.as_slice() {
+            [] => OptionalFilter::Passthrough,
+            countries => OptionalFilter::Evaluate(move |arpt| {
+                Self::list_has_any(&arpt.country_name, countries)
+            }),
+        };
+
+        let expr_filter = match &self.parsed_expr {
+            Some(expr) => OptionalFilter::Evaluate(move |arpt: &Airport| expr.matches(arpt)),
+            None => OptionalFilter::Passthrough,
+        };
+
+        let required_runway_filter = match &self.required_runways {
+            Some(filters) => {
+                OptionalFilter::Evaluate(move |arpt: &Airport| filters.matches(&arpt.runways))
+            }
+            None => OptionalFilter::Passthrough,
+        };
+
+        let matcher = |arpt: &Airport| {
+            icao_filter.eval(arpt)
+                && self.accepts_type(arpt.class)
+                && runway_len_filter.eval(arpt)
+                && runway_filter.eval(arpt)
+                && elevation_filter.eval(arpt)
+                && services_filter.eval(arpt)
+                && radius_filter.eval(arpt)
+                && area_filter.eval(arpt)
+                && country_filter.eval(arpt)
+                && expr_filter.eval(arpt)
+                && required_runway_filter.eval(arpt)
+        };
+
+        Some(func(&matcher))
+    }
+
+    fn accepts_type(&self, kind: AirportType) -> bool {
+        if !self.airport_types.is_empty() && !self.airport_types.contains(kind) {
+            return false;
+        }
+
+        match &self.excluded_types {
+            Some(excluded) => !excluded.contains(kind),
+            None => true,
+        }
+    }
+
+    fn airport_matches<'a, F, I>(matcher: F, airports: I, limit: usize) -> AirportMatches<'a>
+    where
+        F: Fn(&Airport) -> bool,
+        I: IntoIterator<Item = &'a Airport>,
+    {
+        let mut airports = airports.into_iter();
+        let mut results = Vec::new();
+
+        for airport in &mut airports {
+            if !matcher(airport) {
+                continue;
+            }
+
+            results.push(airport);
+
+            if results.len() >= limit {
+                break;
+            }
+        }
+
+        // Checking for more matches requires looking at every remaining airport,
+        // so only do it if we actually hit the limit
+        let truncated = results.len() >= limit && airports.any(&matcher);
+
+        AirportMatches {
+            airports: results,
+            truncated,
+            random: false,
+        }
+    }
+
+    #[inline(always)]
+    fn list_has_any(name: &str, list: &[String]) -> bool {
+        list.iter().any(|x| x.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Airports that matched a set of filters.
+#[derive(Debug, Default)]
+pub struct AirportMatches<'a> {
+    pub airports: Vec<&'a Airport>,
+    /// Whether more airports matched than the limit, causing some to be left out.
+    pub truncated: bool,
+    /// Whether the airports were picked at random because no filters were given.
+    pub random: bool,
+}
+
+/// A set of airport types that can be deserialized from either a single type or a list of types.
+///
+/// An `unknown` entry is treated as "any type" and is not stored, while any other name
+/// that isn't an airport type is rejected.
+#[derive(Debug, Default)]
+struct AirportTypes(Vec<AirportType>);
+
+impl AirportTypes {
+    const NAMES: &'static [&'static str] = &[
+        "large_airport",
+        "medium_airport",
+        "small_airport",
+        "closed",
+        "heliport",
+        "seaplane_base",
+        "unknown",
+    ];
+
+    fn parse_type<E>(value: &str) -> result::Result<Option<AirportType>, E>
+    where
+        E: serde::de::Error,
+    {
+        match AirportType::from_str(value) {
+            AirportType::Unknown if value == "unknown" => Ok(None),
+            AirportType::Unknown => Err(E::unknown_variant(value, Self::NAMES)),
+            kind => Ok(Some(kind)),
+        }
+    }
+
+    #[inline(always)]
+    fn contains(&self, kind: AirportType) -> bool {
+        self.0.contains(&kind)
+    }
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'de> Deserialize<'de> for AirportTypes {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ArptTypesVisitor;
+
+        impl<'de> Visitor<'de> for ArptTypesVisitor {
+            type Value = AirportTypes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an airport type or a list of airport types")
+            }
+
+            fn visit_str<E>(self, value: &str) -> result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                let types = AirportTypes::parse_type(value)?.into_iter().collect();
+                Ok(AirportTypes(types))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> result::Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut types = Vec::with_capacity(seq.size_hint().unwrap_or(1));
+
+                while let Some(name) = seq.next_element::<String>()? {
+                    match AirportTypes::parse_type(&name)? {
+                        Some(kind) if !types.contains(&kind) => types.push(kind),
+                        _ => (),
+                    }
+                }
+
+                Ok(AirportTypes(types))
+            }
+        }
+
+        deserializer.deserialize_any(ArptTypesVisitor)
+    }
+}
+
+enum OptionalFilter<F>
+where
+    F: Fn(&Airport) -> bool,
+{
+    Evaluate(F),
+    Passthrough,
+}
+
+impl<F> OptionalFilter<F>
+where
+    F: Fn(&Airport) -> bool,
+{
+    fn eval(&self, airport: &Airport) -> bool {
+        match self {
+            Self::Evaluate(func) => func(airport),
+            Self::Passthrough => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(tag = "selector", content = "length")]
+pub enum RunwayLength {
+    #[serde(rename = "eq")]
+    Equal(u32),
+    #[serde(rename = "gt")]
+    GreaterThan(u32),
+    #[serde(rename = "lt")]
+    LessThan(u32),
+}
+
+impl RunwayLength {
+    #[inline(always)]
+    pub fn fits(self, length: u32) -> bool {
+        match self {
+            Self::Equal(len) => len == length,
+            Self::GreaterThan(len) => length > len,
+            Self::LessThan(len) => length < len,
+        }
+    }
+
+    #[inline(always)]
+    pub fn fits_any(self, runways: &[Runway]) -> bool {
+        runways.iter().any(|runway| match runway.length_ft {
+            Some(len) => self.fits(len),
+            None => false,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RunwayFilters {
+    #[serde(default)]
+    length: Range<u32>,
+    #[serde(default)]
+    width: Range<u32>,
+    #[serde(default)]
+    surfaces: Vec<RunwaySurface>,
+    #[serde(rename = "lightedOnly", default)]
+    lighted_only: bool,
+    #[serde(rename = "excludeClosed", default)]
+    exclude_closed: bool,
+    #[serde(rename = "minCount")]
+    min_count: Option<usize>,
+    #[serde(rename = "match", default)]
+    mode: RunwayMatch,
+}
+
+impl RunwayFilters {
+    /// Get filters for the runways that an aircraft can take off from and land on.
+    pub fn for_aircraft(aircraft: &Aircraft) -> Self {
+        Self {
+            length: Range {
+                min: Some(aircraft.min_runway_ft),
+                max: None,
+            },
+            surfaces: aircraft.runway_surfaces.clone(),
+            exclude_closed: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn matches(&self, runways: &[Runway]) -> bool {
+        let mut total = 0;
+        let mut fitting = 0;
+
+        for runway in runways {
+            if self.exclude_closed && runway.closed {
+                continue;
+            }
+
+            total += 1;
+
+            if self.fits(runway) {
+                fitting += 1;
+            }
+        }
+
+        let min_count = self.min_count.unwrap_or(1);
+
+        match self.mode {
+            RunwayMatch::Any => fitting >= min_count,
+            RunwayMatch::All => fitting == total && total >= min_count,
+        }
+    }
+
+    pub fn fits(&self, runway: &Runway) -> bool {
+        if self.lighted_only && !runway.lighted {
+            return false;
+        }
+
+        if !self.surfaces.is_empty() && !self.surfaces.contains(&runway.surface) {
+            return false;
+        }
+
+        self.length.within_opt(runway.length_ft.as_ref())
+            && self.width.within_opt(runway.width_ft.as_ref())
+    }
+}
+
+/// Determines which runways of an airport have to fit the runway filters.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum RunwayMatch {
+    Any,
+    All,
+}
+
+impl Default for RunwayMatch {
+    fn default() -> Self {
+        Self::Any
+    }
+}
+
+/// Filters airports by their distance from either a point or another airport.
+#[derive(Debug, Deserialize)]
+struct RadiusFilter {
+    center: RadiusCenter,
+    #[serde(flatten)]
+    range: Range<f32>,
+    #[serde(default)]
+    unit: DistanceUnit,
+}
+
+impl RadiusFilter {
+    /// Get the position of the center of the radius, if it refers to a known airport or point.
+    fn center(&self, airports: &Airports) -> Option<Position> {
+        match &self.center {
+            RadiusCenter::Icao(icao) => airports.find_by_ident(icao).map(|arpt| arpt.position),
+            RadiusCenter::Point { lat, lon } => Some(Position::new(*lat, *lon)),
+        }
+    }
+
+    fn range_nm(&self) -> Range<f32> {
+        Range {
+            min: self.range.min.map(|min| self.unit.to_nm(min)),
+            max: self.range.max.map(|max| self.unit.to_nm(max)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RadiusCenter {
+    Icao(String),
+    Point { lat: f32, lon: f32 },
+}
+
+/// Filters airports to the ones within a geographic area.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AreaFilter {
+    /// A bounding box in the form of `[west, south, east, north]`.
+    BBox([f32; 4]),
+    /// An inline GeoJSON polygon, multi-polygon, or feature / collection containing them.
+    GeoJson(GeoJson),
+    /// The name of a GeoJSON file stored in the regions folder of the data directory.
+    Region(String),
+}
+
+impl AreaFilter {
+    fn resolve(&self) -> anyhow::Result<Area> {
+        match self {
+            Self::BBox([west, south, east, north]) => {
+                BoundingBox::new(*west, *south, *east, *north).map(Area::BoundingBox)
+            }
+            Self::GeoJson(geojson) => Area::from_geojson(geojson),
+            Self::Region(name) => Area::load_named(name),
+        }
+    }
+}
+
+/// Filters airports by the ATC services they provide, based on their frequencies.
+#[derive(Debug, Default, Deserialize)]
+struct ServiceFilters {
+    /// When set, only airports that either have or don't have a tower will be matched.
+    towered: Option<bool>,
+    /// Frequency types that an airport must all have.
+    #[serde(rename = "hasAll", default)]
+    has_all: Vec<FrequencyType>,
+    /// Frequency types that an airport must have at least one of.
+    #[serde(rename = "hasAny", default)]
+    has_any: Vec<FrequencyType>,
+}
+
+impl ServiceFilters {
+    fn matches(&self, airport: &Airport) -> bool {
+        if let Some(towered) = self.towered {
+            if Self::has_service(airport, FrequencyType::Tower) != towered {
+                return false;
+            }
+        }
+
+        if !self
+            .has_all
+            .iter()
+            .all(|&kind| Self::has_service(airport, kind))
+        {
+            return false;
+        }
+
+        self.has_any.is_empty()
+            || self
+                .has_any
+                .iter()
+                .any(|&kind| Self::has_service(airport, kind))
+    }
+
+    #[inline(always)]
+    fn has_service(airport: &Airport, service: FrequencyType) -> bool {
+        airport
+            .frequencies
+            .keys()
+            .any(|kind| kind.provides(service))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ElevationRange {
+    #[serde(flatten)]
+    pub range: Range<f32>,
+    #[serde(default)]
+    pub unit: LengthUnit,
+}
+
+impl ElevationRange {
+    pub fn within(&self, elevation_ft: Option<i32>) -> bool {
+        let elevation = elevation_ft.map(|elevation| self.unit.feet_to_unit(elevation as f32));
+        self.range.within_opt(elevation.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::{get_airport, get_airports};
+    use crate::api::search_routes::Route;
+    use crate::api::speed::SpeedKind;
+    use crate::geo::geodesic::PathKind;
+
+    #[test]
+    fn runway_length_fits() {
+        assert_eq!(RunwayLength::Equal(12345).fits(12345), true);
+        assert_eq!(RunwayLength::Equal(12345).fits(12346), false);
+
+        assert_eq!(RunwayLength::GreaterThan(1000).fits(1001), true);
+        assert_eq!(RunwayLength::GreaterThan(1000).fits(1000), false);
+
+        assert_eq!(RunwayLength::LessThan(1000).fits(999), true);
+        assert_eq!(RunwayLength::LessThan(1000).fits(1000), false);
+    }
+
+    fn display_airports(airports: Vec<&Airport>) -> Vec<&String> {
+        airports.into_iter().map(|arpt| &arpt.icao).collect()
+    }
+
+    macro_rules! assert_expected_icaos {
+        ($result:expr, $expected:expr) => {
+            let result = $result;
+
+            assert!(
+                !result.is_empty(),
+                "expected to get airports {:?}, got nothing",
+                $expected
+            );
+
+            assert_eq!(
+                $expected.len(),
+                result.len(),
+                "expected to get {} airport(s) ({:?}), got {} ({:?})",
+                $expected.len(),
+                $expected,
+                result.len(),
+                display_airports(result)
+            );
+
+            let has_expected_arpts = result
+                .iter()
+                .all(|found| $expected.contains(&found.icao.as_str()));
+
+            assert!(
+                has_expected_arpts,
+                "expected to get airports {:?}, got {:?}",
+                $expected,
+                display_airports(result)
+            );
+        };
+    }
+
+    #[test]
+    fn filter_icao() {
+        const EXPECTED_ICAO: &'static str = "KSAC";
+
+        let airports = get_airports();
+
+        let filter = AirportFilters {
+            icao: Some(IcaoPattern::parse(EXPECTED_ICAO).unwrap()),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            [EXPECTED_ICAO]
+        );
+    }
+
+    #[test]
+    fn filter_icao_list() {
+        let airports = get_airports();
+
+        let filter = AirportFilters {
+            icao: Some(IcaoPattern::parse("rjaa|KSFO|ZZZZ|3CA1").unwrap()),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "RJAA", "3CA1"]
+        );
+
+        // The other filters still apply to the requested airports
+        let mut filter = AirportFilters {
+            icao: Some(IcaoPattern::parse("rjaa|KSFO|3CA1").unwrap()),
+            countries: vec!["united states".into()],
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "3CA1"]
+        );
+
+        filter.expr = Some("type = heliport".into());
+        filter.prepare(&airports).unwrap();
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["3CA1"]);
+    }
+
+    #[test]
+    fn filter_icao_pattern() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            icao: Some(IcaoPattern::parse("k*").unwrap()),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "KSAC"]
+        );
+
+        filter.icao = Some(IcaoPattern::parse("K??O|RJ*").unwrap());
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "RJAA"]
+        );
+
+        filter.airport_types = AirportTypes(vec![AirportType::Medium]);
+        filter.icao = Some(IcaoPattern::parse("KS*").unwrap());
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSAC"]);
+    }
+
+    #[test]
+    fn filter_airport_type() {
+        const EXPECTED_TYPE: AirportType = AirportType::Large;
+        const EXPECTED_ICAOS: [&'static str; 2] = ["KSFO", "RJAA"];
+
+        let airports = get_airports();
+
+        let filter = AirportFilters {
+            airport_types: AirportTypes(vec![EXPECTED_TYPE]),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, EXPECTED_ICAOS);
+    }
+
+    #[test]
+    fn filter_multiple_airport_types() {
+        let airports = get_airports();
+
+        let filter = AirportFilters {
+            airport_types: AirportTypes(vec![AirportType::Medium, AirportType::Heliport]),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSAC", "3CA1"]
+        );
+    }
+
+    #[test]
+    fn filter_excluded_airport_types() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            excluded_types: Some(AirportTypes(vec![AirportType::Large])),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSAC", "3CA1"]
+        );
+
+        filter.airport_types = AirportTypes(vec![AirportType::Large, AirportType::Medium]);
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSAC"]);
+    }
+
+    #[test]
+    fn filter_default_excluded_airport_types() {
+        let airports = get_airports();
+        let filter = AirportFilters::default();
+
+        // Explicit filters accept every type unless they say otherwise
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "KSAC", "RJAA", "3CA1"]
+        );
+
+        let random = random_airport_set(&airports, None);
+
+        assert!(
+            random
+                .airports
+                .iter()
+                .all(|arpt| arpt.class != AirportType::Heliport),
+            "random airport set should not include heliports"
+        );
+    }
+
+    #[test]
+    fn parse_airport_types() {
+        fn parse(json: &str) -> AirportTypes {
+            serde_json::from_str(json).unwrap()
+        }
+
+        assert_eq!(parse(r#""unknown""#).0, vec![]);
+        assert_eq!(parse(r#""large_airport""#).0, vec![AirportType::Large]);
+
+        assert_eq!(
+            parse(r#"["medium_airport", "small_airport", "medium_airport"]"#).0,
+            vec![AirportType::Medium, AirportType::Small]
+        );
+
+        // A misspelled type would otherwise widen the search to any type
+        assert!(serde_json::from_str::<AirportTypes>(r#""lrage_airport""#).is_err());
+        assert!(serde_json::from_str::<AirportTypes>(r#"["large_airport", "helipad"]"#).is_err());
+    }
+
+    #[test]
+    fn filter_runway_length_gt() {
+        const EXPECTED_ICAOS: [&'static str; 2] = ["KSFO", "RJAA"];
+
+        let airports = get_airports();
+
+        let filter = AirportFilters {
+            runway_length: Some(RunwayLength::GreaterThan(7000)),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, EXPECTED_ICAOS);
+    }
+
+    #[test]
+    fn filter_runway_length_eq() {
+        const EXPECTED_ICAOS: [&'static str; 1] = ["KSAC"];
+
+        let airports = get_airports();
+
+        let filter = AirportFilters {
+            runway_length: Some(RunwayLength::Equal(3836)),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, EXPECTED_ICAOS);
+    }
+
+    #[test]
+    fn filter_runway_length_lt() {
+        const EXPECTED_ICAOS: [&'static str; 3] = ["3CA1", "KSFO", "KSAC"];
+
+        let airports = get_airports();
+
+        let filter = AirportFilters {
+            runway_length: Some(RunwayLength::LessThan(7501)),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, EXPECTED_ICAOS);
+    }
+
+    #[test]
+    fn filter_expression() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            expr: Some("region = US-CA and longest_runway < 5000".into()),
+            ..AirportFilters::default()
+        };
+
+        filter.prepare(&airports).unwrap();
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSAC"]
+        );
+
+        filter.expr = Some("type = heliport or country = japan".into());
+        filter.airport_types = AirportTypes(vec![AirportType::Large, AirportType::Heliport]);
+
+        filter.prepare(&airports).unwrap();
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["RJAA", "3CA1"]
+        );
+
+        filter.expr = Some("type = ".into());
+
+        let errors = filter.prepare(&airports).unwrap_err();
+        assert_eq!(errors.errors()[0].field, "expr");
+        assert_eq!(errors.errors()[0].position, Some(7));
+    }
+
+    #[test]
+    fn filter_runways() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            runways: Some(RunwayFilters {
+                length: Range {
+                    min: Some(8000),
+                    max: None,
+                },
+                ..RunwayFilters::default()
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "RJAA"]
+        );
+
+        filter.runways.as_mut().unwrap().exclude_closed = true;
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["RJAA"]);
+
+        filter.runways = Some(RunwayFilters {
+            width: Range {
+                min: None,
+                max: Some(150),
+            },
+            ..RunwayFilters::default()
+        });
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSAC"]
+        );
+    }
+
+    #[test]
+    fn filter_runways_all() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            runways: Some(RunwayFilters {
+                length: Range {
+                    min: Some(7000),
+                    max: Some(10000),
+                },
+                mode: RunwayMatch::All,
+                ..RunwayFilters::default()
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["RJAA"]);
+
+        filter.runways.as_mut().unwrap().exclude_closed = true;
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "RJAA"]
+        );
+    }
+
+    #[test]
+    fn filter_runway_count() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            runways: Some(RunwayFilters {
+                min_count: Some(2),
+                ..RunwayFilters::default()
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSFO"]);
+
+        filter.runways.as_mut().unwrap().exclude_closed = true;
+        assert!(filter.matching_airports(&airports).airports.is_empty());
+    }
+
+    #[test]
+    fn filter_runway_surface_and_lighting() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            airport_types: AirportTypes(vec![AirportType::Medium, AirportType::Heliport]),
+            runways: Some(RunwayFilters {
+                surfaces: vec![RunwaySurface::Paved, RunwaySurface::Unknown],
+                ..RunwayFilters::default()
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSAC", "3CA1"]
+        );
+
+        filter.runways.as_mut().unwrap().lighted_only = true;
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSAC"]);
+
+        filter.runways.as_mut().unwrap().surfaces = vec![RunwaySurface::Water];
+        assert!(filter.matching_airports(&airports).airports.is_empty());
+    }
+
+    #[test]
+    fn filter_elevation() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            elevation: Some(ElevationRange {
+                range: Range {
+                    min: Some(100.0),
+                    max: None,
+                },
+                unit: LengthUnit::Feet,
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "RJAA"]
+        );
+
+        filter.elevation = Some(ElevationRange {
+            range: Range {
+                min: None,
+                max: Some(5.0),
+            },
+            unit: LengthUnit::Meters,
+        });
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSFO"]);
+    }
+
+    #[test]
+    fn filter_towered() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            airport_types: AirportTypes(vec![
+                AirportType::Large,
+                AirportType::Medium,
+                AirportType::Heliport,
+            ]),
+            services: Some(ServiceFilters {
+                towered: Some(true),
+                ..ServiceFilters::default()
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "KSAC", "RJAA"]
+        );
+
+        filter.services.as_mut().unwrap().towered = Some(false);
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["3CA1"]);
+    }
+
+    #[test]
+    fn filter_services() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            services: Some(ServiceFilters {
+                has_all: vec![FrequencyType::Atis],
+                ..ServiceFilters::default()
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "KSAC"]
+        );
+
+        filter.services = Some(ServiceFilters {
+            has_all: vec![FrequencyType::Departure],
+            ..ServiceFilters::default()
+        });
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSFO", "RJAA"]
+        );
+
+        filter.services = Some(ServiceFilters {
+            has_any: vec![FrequencyType::Arrival, FrequencyType::Unicom],
+            ..ServiceFilters::default()
+        });
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSFO", "RJAA"]
+        );
+
+        filter.services.as_mut().unwrap().has_all = vec![FrequencyType::Atis];
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSFO"]);
+    }
+
+    #[test]
+    fn filter_radius() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            radius: Some(RadiusFilter {
+                center: RadiusCenter::Icao("ksfo".into()),
+                range: Range {
+                    min: None,
+                    max: Some(100.0),
+                },
+                unit: DistanceUnit::NauticalMiles,
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSAC", "KSFO"]
+        );
+
+        filter.radius = Some(RadiusFilter {
+            center: RadiusCenter::Point {
+                lat: 37.618,
+                lon: -122.375,
+            },
+            range: Range {
+                min: Some(100.0),
+                max: Some(5000.0),
+            },
+            unit: DistanceUnit::Kilometers,
+        });
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSAC"]);
+
+        filter.radius.as_mut().unwrap().range.max = None;
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["KSAC", "RJAA"]
+        );
+
+        filter.radius.as_mut().unwrap().center = RadiusCenter::Icao("ZZZZ".into());
+        assert!(filter.matching_airports(&airports).airports.is_empty());
+    }
+
+    #[test]
+    fn filter_radius_follows_route_distances() {
+        let airports = get_airports();
+        let speed = SpeedKind::Knots(450.0).into();
+
+        let rhumb = DistanceOptions {
+            path: PathKind::Rhumb,
+            ..DistanceOptions::default()
+        };
+
+        let great_circle_nm = Route::new(
+            get_airport(&airports, "RJAA"),
+            get_airport(&airports, "KSFO"),
+            speed,
+            DistanceOptions::default(),
+        )
+        .distance;
+        let rhumb_nm = Route::new(
+            get_airport(&airports, "RJAA"),
+            get_airport(&airports, "KSFO"),
+            speed,
+            rhumb,
+        )
+        .distance;
+
+        let mut filter = AirportFilters {
+            icao: Some(IcaoPattern::parse("KSF?").unwrap()),
+            radius: Some(RadiusFilter {
+                center: RadiusCenter::Icao("RJAA".into()),
+                range: Range {
+                    min: None,
+                    max: Some(great_circle_nm + 1.0),
+                },
+                unit: DistanceUnit::NauticalMiles,
+            }),
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSFO"]);
+
+        // The rhumb line is longer than the great circle, so it no longer fits in the same radius
+        filter.distance = rhumb;
+        assert!(filter.matching_airports(&airports).airports.is_empty());
+
+        filter.radius.as_mut().unwrap().range.max = Some(rhumb_nm + 1.0);
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["KSFO"]);
+
+        let center = get_airport(&airports, "RJAA").position;
+        let nearest = filter.nearest_airports(&airports, center, 1);
+        assert_eq!(nearest[0].0.icao, "KSFO");
+        assert!((nearest[0].1 - rhumb_nm).abs() < 0.01);
+    }
+
+    #[test]
+    fn filter_area() {
+        let airports = get_airports();
+
+        // California
+        let mut filter = AirportFilters {
+            area: Some(AreaFilter::BBox([-124.5, 32.5, -114.1, 42.0])),
+            ..AirportFilters::default()
+        };
+
+        filter.prepare(&airports).unwrap();
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSAC", "KSFO"]
+        );
+
+        // Triangle from the Bay Area to Japan across the antimeridian
+        let geojson = serde_json::from_str(
+            r#"{
+                "type": "Polygon",
+                "coordinates": [[[-122.0, 37.0], [140.0, 35.0], [140.0, 36.5], [-122.0, 38.0]]]
+            }"#,
+        )
+        .unwrap();
+
+        filter.area = Some(AreaFilter::GeoJson(geojson));
+        filter.prepare(&airports).unwrap();
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSFO", "RJAA"]
+        );
+
+        filter.area = Some(AreaFilter::BBox([-124.5, 42.0, -114.1, 32.5]));
+
+        let errors = filter.prepare(&airports).unwrap_err();
+        assert_eq!(errors.errors()[0].field, "area");
+    }
+
+    #[test]
+    fn filter_countries() {
+        let airports = get_airports();
+
+        let mut filter = AirportFilters {
+            countries: vec!["United States".into()],
+            ..AirportFilters::default()
+        };
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSAC", "KSFO"]
+        );
+
+        filter.countries = vec!["Japan".into()];
+        assert_expected_icaos!(filter.matching_airports(&airports).airports, ["RJAA"]);
+
+        filter.countries = vec!["United States".into(), "Japan".into()];
+
+        assert_expected_icaos!(
+            filter.matching_airports(&airports).airports,
+            ["3CA1", "KSAC", "KSFO", "RJAA"]
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use super::airport::{ElevationRange, RunwayFilters};
+use super::Range;
+use crate::airport_data::{Airport, Runway};
+use crate::api::search_routes::{Route, Time};
+use crate::geo;
+use crate::wind::Wind;
+use serde_derive::Deserialize;
+
+/// Constrains the elevation of the arrival airport relative to the departure airport.
+#[derive(Debug, Default, Deserialize)]
+pub struct ElevationChange {
+    #[serde(flatten)]
+    range: ElevationRange,
+    /// Whether the range applies to the size of the change, regardless of it being a climb or descent.
+    #[serde(default)]
+    absolute: bool,
+}
+
+impl ElevationChange {
+    fn fits(&self, departure: &Airport, arrival: &Airport) -> bool {
+        let change = match (departure.elevation_ft, arrival.elevation_ft) {
+            (Some(from), Some(to)) if self.absolute => Some((to - from).abs()),
+            (Some(from), Some(to)) => Some(to - from),
+            _ => None,
+        };
+
+        self.range.within(change)
+    }
+}
+
+/// Constrains the initial true course of a route to either a cardinal sector or a range of degrees.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum CourseFilter {
+    Sector(CourseSector),
+    /// A range of courses in degrees. When `min` is greater than `max`, the range wraps through 360.
+    Range {
+        min: f32,
+        max: f32,
+    },
+}
+
+impl CourseFilter {
+    pub fn fits(&self, course: f32) -> bool {
+        let (min, max) = match self {
+            Self::Sector(sector) => return sector.contains(course),
+            Self::Range { min, max } => (geo::normalize_course(*min), geo::normalize_course(*max)),
+        };
+
+        if min <= max {
+            course >= min && course <= max
+        } else {
+            course >= min || course <= max
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CourseSector {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl CourseSector {
+    /// Half of the width of each sector in degrees, so that the eight sectors split
+    /// the compass without overlapping.
+    const HALF_WIDTH: f32 = 22.5;
+
+    /// Whether a course is in the sector, which includes the boundary counterclockwise
+    /// from its center but not the clockwise one.
+    fn contains(self, course: f32) -> bool {
+        let offset = geo::normalize_course(course - self.center() + Self::HALF_WIDTH);
+        offset < Self::HALF_WIDTH * 2.0
+    }
+
+    fn center(self) -> f32 {
+        match self {
+            Self::North => 0.0,
+            Self::NorthEast => 45.0,
+            Self::East => 90.0,
+            Self::SouthEast => 135.0,
+            Self::South => 180.0,
+            Self::SouthWest => 225.0,
+            Self::West => 270.0,
+            Self::NorthWest => 315.0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[serde(tag = "type", content = "value")]
+pub enum TimeOrDistance {
+    #[serde(rename = "time")]
+    Time(Range<Time>),
+    #[serde(rename = "dist")]
+    Distance(Range<f32>),
+}
+
+/// A constraint that is applied to every departure and arrival pair.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum RouteConstraint {
+    #[serde(rename = "time")]
+    Time(Range<Time>),
+    #[serde(rename = "dist")]
+    Distance(Range<f32>),
+    #[serde(rename = "course")]
+    Course(CourseFilter),
+    #[serde(rename = "elevationChange")]
+    ElevationChange(ElevationChange),
+    #[serde(rename = "crosswind")]
+    Crosswind(CrosswindLimit),
+}
+
+impl RouteConstraint {
+    pub fn fits(&self, route: &Route) -> bool {
+        match self {
+            Self::Time(range) => range.within(&route.estimated_time()),
+            Self::Distance(range) => range.within(&route.distance),
+            Self::Course(course) => course.fits(route.initial_course),
+            Self::ElevationChange(change) => change.fits(route.from, route.to),
+            Self::Crosswind(limit) => limit.fits(route),
+        }
+    }
+
+    /// Whether the constraint depends on the wind or flight time of a route, which are costly
+    /// to calculate.
+    #[inline(always)]
+    pub fn needs_estimates(&self) -> bool {
+        match self {
+            Self::Time(_) | Self::Crosswind(_) => true,
+            Self::Distance(_) | Self::Course(_) | Self::ElevationChange(_) => false,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Time(_) => "time",
+            Self::Distance(_) => "dist",
+            Self::Course(_) => "course",
+            Self::ElevationChange(_) => "elevationChange",
+            Self::Crosswind(_) => "crosswind",
+        }
+    }
+
+    /// Get how far a route is from fitting the constraint, for constraints where that can be measured.
+    ///
+    /// The result is in minutes for time constraints and nautical miles for distance constraints.
+    pub fn missed_by(&self, route: &Route) -> Option<f32> {
+        let (min, max, value) = match self {
+            Self::Time(range) => (
+                range.min.map(Time::total_minutes),
+                range.max.map(Time::total_minutes),
+                route.estimated_time().total_minutes(),
+            ),
+            Self::Distance(range) => (range.min, range.max, route.distance),
+            Self::Course(_) | Self::ElevationChange(_) | Self::Crosswind(_) => return None,
+        };
+
+        let missed_by = match (min, max) {
+            (Some(min), _) if value < min => min - value,
+            (_, Some(max)) if value > max => value - max,
+            _ => 0.0,
+        };
+
+        Some(missed_by)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Time(range) => range.validate(),
+            Self::Distance(range) => range.validate(),
+            // Course ranges with a minimum greater than the maximum wrap through 360
+            Self::Course(_) => Ok(()),
+            Self::ElevationChange(change) => change.range.range.validate(),
+            Self::Crosswind(limit) => limit.validate(),
+        }
+    }
+}
+
+/// Limits the crosswind on the runways at both ends of a route, which is only checked
+/// when a wind is given.
+#[derive(Debug, Deserialize)]
+pub struct CrosswindLimit {
+    #[serde(rename = "maxKT")]
+    pub max_kt: f32,
+    /// The runways that can be used, which are all open runways when this isn't set.
+    #[serde(skip)]
+    pub runways: Option<RunwayFilters>,
+}
+
+impl CrosswindLimit {
+    fn fits(&self, route: &Route) -> bool {
+        let wind = match &route.wind {
+            Some(wind) => wind,
+            None => return true,
+        };
+
+        self.fits_airport(route.from, wind.departure_wind)
+            && self.fits_airport(route.to, wind.arrival_wind)
+    }
+
+    /// Whether a usable runway at `airport` has a crosswind within the limit.
+    ///
+    /// Airports where none of the usable runways have a known heading are assumed to fit.
+    fn fits_airport(&self, airport: &Airport, wind: Wind) -> bool {
+        let mut crosswinds = airport
+            .runways
+            .iter()
+            .filter(|runway| !runway.closed)
+            .filter(|runway| match &self.runways {
+                Some(filters) => filters.fits(runway),
+                None => true,
+            })
+            .filter_map(Runway::heading_deg)
+            .map(|heading| wind.relative_to(heading).1.abs())
+            .peekable();
+
+        if crosswinds.peek().is_none() {
+            return true;
+        }
+
+        crosswinds.any(|crosswind| crosswind <= self.max_kt)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_kt.is_finite() && self.max_kt >= 0.0 {
+            Ok(())
+        } else {
+            Err(format!(
+                "maximum crosswind must be at least 0, got {}",
+                self.max_kt
+            ))
+        }
+    }
+}
+
+impl From<TimeOrDistance> for RouteConstraint {
+    fn from(time_or_dist: TimeOrDistance) -> Self {
+        match time_or_dist {
+            TimeOrDistance::Time(range) => Self::Time(range),
+            TimeOrDistance::Distance(range) => Self::Distance(range),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::{get_airport, get_airports};
+    use crate::api::units::LengthUnit;
+
+    #[test]
+    fn course_filter_fits() {
+        let range = CourseFilter::Range {
+            min: 240.0,
+            max: 300.0,
+        };
+
+        assert!(range.fits(270.0));
+        assert!(!range.fits(90.0));
+
+        let wrapping = CourseFilter::Range {
+            min: 330.0,
+            max: 30.0,
+        };
+
+        assert!(wrapping.fits(350.0));
+        assert!(wrapping.fits(10.0));
+        assert!(!wrapping.fits(180.0));
+
+        let north = CourseFilter::Sector(CourseSector::North);
+
+        assert!(north.fits(337.5));
+        assert!(north.fits(0.0));
+        assert!(north.fits(22.0));
+        assert!(!north.fits(22.5));
+        assert!(!north.fits(315.0));
+        assert!(!north.fits(45.0));
+
+        let east = CourseFilter::Sector(CourseSector::East);
+
+        assert!(east.fits(70.0));
+        assert!(!east.fits(60.0));
+        assert!(!east.fits(270.0));
+
+        // Every course is in exactly one sector
+        let sectors = [
+            CourseSector::North,
+            CourseSector::NorthEast,
+            CourseSector::East,
+            CourseSector::SouthEast,
+            CourseSector::South,
+            CourseSector::SouthWest,
+            CourseSector::West,
+            CourseSector::NorthWest,
+        ];
+
+        for course in (0..720).map(|half_deg| half_deg as f32 / 2.0) {
+            let count = sectors
+                .iter()
+                .filter(|sector| sector.contains(course))
+                .count();
+            assert_eq!(count, 1, "course {} is in {} sectors", course, count);
+        }
+    }
+
+    #[test]
+    fn elevation_change_fits() {
+        let airports = get_airports();
+
+        let ksfo = get_airport(&airports, "KSFO");
+        let rjaa = get_airport(&airports, "RJAA");
+
+        let mut change = ElevationChange {
+            range: ElevationRange {
+                range: Range {
+                    min: Some(100.0),
+                    max: None,
+                },
+                unit: LengthUnit::Feet,
+            },
+            absolute: false,
+        };
+
+        assert!(change.fits(ksfo, rjaa));
+        assert!(!change.fits(rjaa, ksfo));
+
+        change.absolute = true;
+        assert!(change.fits(rjaa, ksfo));
+
+        change.range.range.min = Some(129.0);
+        assert!(!change.fits(rjaa, ksfo));
+    }
+}
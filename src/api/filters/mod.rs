@@ -0,0 +1,733 @@
+pub mod airport;
+pub mod constraint;
+
+use super::aircraft::{Aircraft, AircraftCatalog};
+use super::error::{ErrorCode, ValidationErrors};
+use super::flight_profile::FlightProfile;
+use super::search_routes::{GeometryOptions, Route};
+use super::speed::Speed;
+use super::wind::{RouteWind, WindSource};
+use crate::airport_data::{Airport, Airports};
+use crate::geo::geodesic::DistanceOptions;
+use crate::wind::forecast::WindForecast;
+use crate::wind::WindField;
+use airport::{AirportFilters, RunwayFilters};
+use constraint::{CrosswindLimit, RouteConstraint, TimeOrDistance};
+use serde_derive::Deserialize;
+use std::fmt;
+
+/// Why a route between two airports was left out of the search results.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Rejection {
+    /// The route failed the constraint at this index.
+    Constraint(usize),
+    /// The wind is too strong for the aircraft to make progress along part of the route.
+    WindTooStrong,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Filters {
+    /// The cruise speed, which is taken from the aircraft when it isn't set.
+    speed: Option<Speed>,
+    /// The ID of an aircraft from the catalog, whose speed, profile, range and runway
+    /// requirements are applied to the search.
+    aircraft: Option<String>,
+    pub departure: Option<AirportFilters>,
+    pub arrival: Option<AirportFilters>,
+    /// A single time or distance constraint.
+    ///
+    /// This is still accepted for older clients, and is merged into `constraints` when preparing the filters.
+    #[serde(rename = "timeDist", default)]
+    time_or_dist: Option<TimeOrDistance>,
+    /// Constraints that every route must fit.
+    #[serde(default)]
+    pub constraints: Vec<RouteConstraint>,
+    /// Include the geometry of each route when this is set.
+    pub geometry: Option<GeometryOptions>,
+    /// How route distances and courses are measured.
+    #[serde(default)]
+    pub distance: DistanceOptions,
+    /// Estimate block times with this profile, which are used by time constraints when it's set.
+    pub profile: Option<FlightProfile>,
+    /// The wind to correct ground speeds and times for.
+    pub wind: Option<WindSource>,
+    /// The runways that every airport needs to have for the selected aircraft.
+    #[serde(skip)]
+    pub required_runways: Option<RunwayFilters>,
+    /// The fuel that the selected aircraft burns per hour in kilograms.
+    #[serde(skip)]
+    fuel_burn_kgh: Option<f32>,
+}
+
+impl Filters {
+    /// Parse, resolve and validate the parts of the filters that can't be
+    /// handled during deserialization, such as filter expressions and areas.
+    ///
+    /// Returns the cruise speed, which is taken from the aircraft when it isn't set.
+    /// Every problem that is found is returned, rather than only the first one.
+    pub fn prepare(
+        &mut self,
+        airports: &Airports,
+        catalog: &AircraftCatalog,
+        forecast: Option<&WindForecast>,
+    ) -> Result<Speed, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let Some(id) = &self.aircraft {
+            match catalog.find(id) {
+                Some(aircraft) => self.apply_aircraft(aircraft),
+                None => errors.add(
+                    "aircraft",
+                    ErrorCode::UnknownAircraft,
+                    format!("no aircraft with ID {}", id.to_ascii_uppercase()),
+                ),
+            }
+        }
+
+        match self.speed {
+            Some(speed) => {
+                if let Err(errs) = speed.validate() {
+                    errors.extend_within("speed", errs);
+                }
+            }
+            // An unknown aircraft has already been reported
+            None if self.aircraft.is_some() => (),
+            None => errors.add(
+                "speed",
+                ErrorCode::InvalidValue,
+                "a speed is required when no aircraft is selected".into(),
+            ),
+        }
+
+        if let Some(Err(errs)) = self.profile.as_ref().map(FlightProfile::validate) {
+            errors.extend_within("profile", errs);
+        }
+
+        // Speeds without their own altitude are flown at the profile's cruise altitude
+        if let (Some(speed), Some(profile)) = (&mut self.speed, &self.profile) {
+            speed.altitude_ft.get_or_insert(profile.cruise_altitude_ft);
+        }
+
+        if let Some(Err(errs)) = self.wind.as_ref().map(|wind| wind.validate(forecast)) {
+            errors.extend_within("wind", errs);
+        }
+
+        if let (Some(speed), Some(wind)) = (&self.speed, &self.wind) {
+            if wind.varies_with_altitude() && speed.cruise_altitude_ft().is_none() {
+                errors.add(
+                    "speed.altitudeFT",
+                    ErrorCode::InvalidValue,
+                    "an altitude or flight profile is required for winds that vary with altitude"
+                        .into(),
+                );
+            }
+        }
+
+        // Airports within a radius should be measured the same way as the routes between them
+        if let Some(departure) = &mut self.departure {
+            departure.distance = self.distance;
+
+            if let Err(errs) = departure.prepare(airports) {
+                errors.extend_within("departure", errs);
+            }
+        }
+
+        if let Some(arrival) = &mut self.arrival {
+            arrival.distance = self.distance;
+
+            if let Err(errs) = arrival.prepare(airports) {
+                errors.extend_within("arrival", errs);
+            }
+        }
+
+        if let Some(time_or_dist) = self.time_or_dist.take() {
+            let constraint = RouteConstraint::from(time_or_dist);
+
+            match constraint.validate() {
+                Ok(()) => self.constraints.push(constraint),
+                Err(msg) => errors.add("timeDist", ErrorCode::InvalidRange, msg),
+            }
+        }
+
+        for (i, constraint) in self.constraints.iter().enumerate() {
+            if let Err(msg) = constraint.validate() {
+                errors.add(format!("constraints[{}]", i), ErrorCode::InvalidRange, msg);
+            }
+        }
+
+        if let Some(Err(msg)) = self.geometry.as_ref().map(GeometryOptions::validate) {
+            errors.add("geometry.spacing", ErrorCode::InvalidValue, msg);
+        }
+
+        match self.speed {
+            Some(speed) => errors.into_result().map(|()| speed),
+            // A missing speed has already been reported, either on its own or as an unknown aircraft
+            None => Err(errors),
+        }
+    }
+
+    /// Build the route between two airports and check it against every constraint, along with
+    /// the reason it was rejected, if any.
+    ///
+    /// The wind and flight time are only calculated for routes that fit every constraint
+    /// that doesn't depend on them, since they're far more costly than the rest.
+    pub fn evaluate<'a>(
+        &self,
+        from: &'a Airport,
+        to: &'a Airport,
+        speed: Speed,
+        wind: Option<&dyn WindField>,
+    ) -> (Route<'a>, Option<Rejection>) {
+        let mut route = Route::new(from, to, speed, self.distance);
+
+        let first_failed = |route: &Route, estimated: bool| {
+            self.constraints
+                .iter()
+                .position(|constraint| {
+                    constraint.needs_estimates() == estimated && !constraint.fits(route)
+                })
+                .map(Rejection::Constraint)
+        };
+
+        if let Some(rejection) = first_failed(&route, false) {
+            return (route, Some(rejection));
+        }
+
+        if let Some(field) = wind {
+            // Only uniform winds, which are the same at every altitude, are allowed without one
+            let altitude = speed.cruise_altitude_ft().unwrap_or(0.0);
+            let tas = speed.true_airspeed_kt();
+
+            match RouteWind::new(&route, field, tas, altitude, self.distance) {
+                Some(route_wind) => route.wind = Some(route_wind),
+                None => return (route, Some(Rejection::WindTooStrong)),
+            }
+        }
+
+        if let Some(profile) = &self.profile {
+            route.flight_time = Some(profile.estimate(&route, speed));
+        }
+
+        route.fuel_kg = self
+            .fuel_burn_kgh
+            .map(|burn| burn * route.estimated_time().total_minutes() / 60.0);
+
+        let failed = first_failed(&route, true);
+        (route, failed)
+    }
+
+    /// Fill in the speed and profile from an aircraft when they weren't given, and
+    /// add constraints for its range, runway and crosswind requirements.
+    fn apply_aircraft(&mut self, aircraft: &Aircraft) {
+        self.speed.get_or_insert(aircraft.speed);
+        self.profile.get_or_insert(aircraft.profile);
+        self.fuel_burn_kgh = Some(aircraft.fuel_burn_kgh);
+
+        self.constraints.push(RouteConstraint::Distance(Range {
+            min: None,
+            max: Some(aircraft.max_range_nm),
+        }));
+
+        let runways = RunwayFilters::for_aircraft(aircraft);
+
+        self.constraints
+            .push(RouteConstraint::Crosswind(CrosswindLimit {
+                max_kt: aircraft.max_crosswind_kt,
+                runways: Some(runways.clone()),
+            }));
+
+        for filters in self.departure.iter_mut().chain(self.arrival.iter_mut()) {
+            filters.required_runways = Some(runways.clone());
+        }
+
+        self.required_runways = Some(runways);
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Range<T>
+where
+    T: fmt::Debug + Default + PartialOrd,
+{
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T> Range<T>
+where
+    T: fmt::Debug + Default + PartialOrd,
+{
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) if min > max => Err(format!(
+                "minimum of {:?} is greater than the maximum of {:?}",
+                min, max
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn within(&self, value: &T) -> bool {
+        if let Some(true) = self.min.as_ref().map(|min| value < min) {
+            return false;
+        }
+
+        if let Some(true) = self.max.as_ref().map(|max| value > max) {
+            return false;
+        }
+
+        true
+    }
+
+    /// Like `within`, but an unknown value is only considered within the range if it's unbounded.
+    pub fn within_opt(&self, value: Option<&T>) -> bool {
+        match value {
+            Some(value) => self.within(value),
+            None => self.min.is_none() && self.max.is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::airport::random_airport_set;
+    use super::*;
+    use crate::airport_data::test_data::{get_airport, get_airports};
+    use crate::api::speed::SpeedKind;
+
+    #[test]
+    fn range_within() {
+        macro_rules! within {
+            ($min:expr => $max:expr, $value:expr) => {
+                Range::within(
+                    &Range {
+                        min: $min,
+                        max: $max,
+                    },
+                    &$value,
+                )
+            };
+        }
+
+        assert_eq!(within!(Some(1) => Some(3), 2), true);
+        assert_eq!(within!(Some(2) => Some(3), 2), true);
+        assert_eq!(within!(Some(2) => Some(3), 3), true);
+
+        assert_eq!(within!(Some(1) => Some(5), 10), false);
+        assert_eq!(within!(Some(10) => Some(100), 9), false);
+    }
+
+    #[test]
+    fn range_validate() {
+        let valid = Range {
+            min: Some(1.0),
+            max: Some(1.0),
+        };
+
+        let invalid = Range {
+            min: Some(2.0),
+            max: Some(1.0),
+        };
+
+        assert!(valid.validate().is_ok());
+        assert!(invalid.validate().is_err());
+        assert!(Range::<f32>::default().validate().is_ok());
+    }
+
+    #[test]
+    fn route_constraints() {
+        let airports = get_airports();
+
+        let mut filters: Filters = serde_json::from_str(
+            r#"{
+                "speed": { "type": "knots", "value": 450 },
+                "timeDist": { "type": "dist", "value": { "min": 1000 } },
+                "constraints": [
+                    { "type": "time", "value": { "max": { "hour": 20, "minutes": 0 } } },
+                    { "type": "course", "value": "northwest" },
+                    { "type": "elevationChange", "value": { "min": 100 } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let speed = filters
+            .prepare(&airports, &AircraftCatalog::default(), None)
+            .unwrap();
+        assert_eq!(filters.constraints.len(), 4);
+
+        let fits = |from, to| {
+            let route = Route::new(
+                get_airport(&airports, from),
+                get_airport(&airports, to),
+                speed,
+                filters.distance,
+            );
+            filters.constraints.iter().all(|c| c.fits(&route))
+        };
+
+        assert!(fits("KSFO", "RJAA"));
+        // Too short
+        assert!(!fits("KSFO", "KSAC"));
+        // Eastbound and descending
+        assert!(!fits("RJAA", "KSFO"));
+    }
+
+    #[test]
+    fn profile_time_constraints() {
+        let airports = get_airports();
+
+        let mut filters: Filters = serde_json::from_str(
+            r#"{
+                "speed": { "type": "knots", "value": 450 },
+                "profile": {},
+                "constraints": [
+                    { "type": "time", "value": { "min": { "hour": 0, "minutes": 30 } } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let speed = filters
+            .prepare(&airports, &AircraftCatalog::default(), None)
+            .unwrap();
+
+        let mut route = Route::new(
+            get_airport(&airports, "KSFO"),
+            get_airport(&airports, "KSAC"),
+            speed,
+            filters.distance,
+        );
+        let constraint = &filters.constraints[0];
+
+        // Cruising the whole way is too quick, but the block time includes taxiing, climbing and descending
+        assert!(!constraint.fits(&route));
+        assert_eq!(constraint.missed_by(&route), Some(21.0));
+
+        route.flight_time = filters
+            .profile
+            .map(|profile| profile.estimate(&route, speed));
+
+        assert!(constraint.fits(&route));
+        assert_eq!(route.estimated_time(), route.flight_time.unwrap().block);
+    }
+
+    #[test]
+    fn wind_time_constraints() {
+        let airports = get_airports();
+
+        let mut filters: Filters = serde_json::from_str(
+            r#"{
+                "speed": { "type": "knots", "value": 450 },
+                "wind": { "type": "uniform", "value": { "direction": 270, "speedKT": 100 } },
+                "constraints": [
+                    { "type": "time", "value": { "max": { "hour": 11, "minutes": 0 } } },
+                    { "type": "dist", "value": { "min": 100 } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let speed = filters
+            .prepare(&airports, &AircraftCatalog::default(), None)
+            .unwrap();
+
+        let field = filters.wind.as_ref().and_then(|wind| wind.field(None));
+        let evaluate = |from, to| {
+            filters.evaluate(
+                get_airport(&airports, from),
+                get_airport(&airports, to),
+                speed,
+                field,
+            )
+        };
+
+        // Both directions take 9h52m without any wind
+        let (route, failed) = evaluate("RJAA", "KSFO");
+        assert_eq!(failed, None);
+        assert_eq!(route.estimated_time(), route.wind.unwrap().time);
+
+        let (route, failed) = evaluate("KSFO", "RJAA");
+        assert_eq!(failed, Some(Rejection::Constraint(0)));
+        assert!(route.wind.is_some());
+
+        // Routes that are too short are rejected before the wind is calculated
+        let (route, failed) = evaluate("KSFO", "KSAC");
+        assert_eq!(failed, Some(Rejection::Constraint(1)));
+        assert!(route.wind.is_none());
+    }
+
+    #[test]
+    fn wind_too_strong() {
+        let airports = get_airports();
+
+        let mut filters: Filters = serde_json::from_str(
+            r#"{
+                "speed": { "type": "knots", "value": 100 },
+                "wind": { "type": "uniform", "value": { "direction": 40, "speedKT": 120 } },
+                "constraints": [
+                    { "type": "time", "value": { "min": { "hour": 0, "minutes": 10 } } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let speed = filters
+            .prepare(&airports, &AircraftCatalog::default(), None)
+            .unwrap();
+
+        let field = filters.wind.as_ref().and_then(|wind| wind.field(None));
+        let evaluate = |from, to| {
+            filters.evaluate(
+                get_airport(&airports, from),
+                get_airport(&airports, to),
+                speed,
+                field,
+            )
+        };
+
+        // The headwind is stronger than the airspeed, so the route can't be flown at all
+        let (_, failed) = evaluate("KSFO", "KSAC");
+        assert_eq!(failed, Some(Rejection::WindTooStrong));
+
+        let (route, failed) = evaluate("KSAC", "KSFO");
+        assert_eq!(failed, None);
+        assert!(route.wind.unwrap().ground_speed_kt > 200.0);
+    }
+
+    #[test]
+    fn aircraft_filters() {
+        let airports = get_airports();
+        let catalog = AircraftCatalog::builtin().unwrap();
+
+        let mut filters: Filters = serde_json::from_str(
+            r#"{
+                "aircraft": "a320",
+                "departure": { "icao": "K*" }
+            }"#,
+        )
+        .unwrap();
+
+        let speed = filters.prepare(&airports, &catalog, None).unwrap();
+
+        assert_eq!(speed.kind, SpeedKind::Mach(0.78));
+        assert_eq!(filters.profile.unwrap().cruise_altitude_ft, 36_000.0);
+
+        // Sacramento Executive's runway is too short
+        let departures = filters
+            .departure
+            .as_ref()
+            .unwrap()
+            .matching_airports(&airports);
+        assert_eq!(departures.airports, vec![get_airport(&airports, "KSFO")]);
+
+        let arrivals = random_airport_set(&airports, filters.required_runways.as_ref());
+        let mut arrivals = arrivals.airports;
+        arrivals.sort_by(|x, y| x.icao.cmp(&y.icao));
+        assert_eq!(
+            arrivals,
+            vec![
+                get_airport(&airports, "KSFO"),
+                get_airport(&airports, "RJAA")
+            ]
+        );
+
+        // Narita is out of range
+        let route = Route::new(
+            get_airport(&airports, "KSFO"),
+            get_airport(&airports, "RJAA"),
+            speed,
+            filters.distance,
+        );
+        assert!(!filters.constraints.iter().all(|c| c.fits(&route)));
+
+        // The speed and profile can still be overridden
+        let mut filters: Filters = serde_json::from_str(
+            r#"{
+                "aircraft": "A320",
+                "speed": { "type": "knots", "value": 400 },
+                "profile": { "cruiseAltitudeFT": 30000 }
+            }"#,
+        )
+        .unwrap();
+
+        let speed = filters.prepare(&airports, &catalog, None).unwrap();
+
+        assert_eq!(speed.kind, SpeedKind::Knots(400.0));
+        assert_eq!(filters.profile.unwrap().cruise_altitude_ft, 30_000.0);
+    }
+
+    #[test]
+    fn aircraft_crosswind_and_fuel() {
+        let airports = get_airports();
+        let catalog = AircraftCatalog::builtin().unwrap();
+
+        let filters = |direction| {
+            let json = format!(
+                r#"{{
+                    "aircraft": "C172",
+                    "wind": {{ "type": "uniform", "value": {{ "direction": {}, "speedKT": 20 }} }}
+                }}"#,
+                direction
+            );
+
+            let mut filters: Filters = serde_json::from_str(&json).unwrap();
+            let speed = filters.prepare(&airports, &catalog, None).unwrap();
+            (filters, speed)
+        };
+
+        // Close enough to the open runways at both airports
+        let (aligned, speed) = filters(0);
+        let field = aligned.wind.as_ref().and_then(|wind| wind.field(None));
+        let (route, failed) = aligned.evaluate(
+            get_airport(&airports, "KSFO"),
+            get_airport(&airports, "KSAC"),
+            speed,
+            field,
+        );
+        assert_eq!(failed, None);
+
+        // 24 kg per hour
+        let minutes = route.estimated_time().total_minutes();
+        assert!((route.fuel_kg.unwrap() - minutes * 0.4).abs() < 0.01);
+
+        // Across them, which is more than the 15 knots that the Cessna can handle
+        let (across, speed) = filters(90);
+        let field = across.wind.as_ref().and_then(|wind| wind.field(None));
+        let (_, failed) = across.evaluate(
+            get_airport(&airports, "KSFO"),
+            get_airport(&airports, "KSAC"),
+            speed,
+            field,
+        );
+
+        match failed {
+            Some(Rejection::Constraint(index)) => {
+                assert_eq!(across.constraints[index].name(), "crosswind")
+            }
+            other => panic!("expected a crosswind rejection, got {:?}", other),
+        }
+    }
+
+    fn validation_errors(json: &str) -> Vec<(String, ErrorCode)> {
+        let mut filters: Filters = serde_json::from_str(json).unwrap();
+
+        let catalog = AircraftCatalog::builtin().unwrap();
+
+        match filters.prepare(&get_airports(), &catalog, None) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors
+                .errors()
+                .iter()
+                .map(|err| (err.field.clone(), err.code))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn route_constraints_validate() {
+        let errors = validation_errors(
+            r#"{
+                "speed": { "type": "mach", "value": 0.8 },
+                "constraints": [
+                    { "type": "course", "value": { "min": 350, "max": 10 } },
+                    { "type": "dist", "value": { "min": 500, "max": 100 } }
+                ]
+            }"#,
+        );
+
+        assert_eq!(
+            errors,
+            vec![("constraints[1]".into(), ErrorCode::InvalidRange)]
+        );
+
+        assert_eq!(
+            validation_errors(r#"{ "aircraft": "ZZZZ" }"#),
+            vec![("aircraft".into(), ErrorCode::UnknownAircraft)]
+        );
+
+        assert_eq!(
+            validation_errors(r#"{ "constraints": [] }"#),
+            vec![("speed".into(), ErrorCode::InvalidValue)]
+        );
+
+        // No forecast is loaded
+        assert_eq!(
+            validation_errors(r#"{ "aircraft": "A320", "wind": { "type": "forecast" } }"#),
+            vec![("wind.type".into(), ErrorCode::InvalidValue)]
+        );
+
+        // Winds aloft can't be interpolated without knowing the cruise altitude
+        let aloft = r#""wind": {
+            "type": "aloft",
+            "value": [{ "altitudeFT": 18000, "direction": 270, "speedKT": 40 }]
+        }"#;
+
+        assert_eq!(
+            validation_errors(&format!(
+                r#"{{ "speed": {{ "type": "knots", "value": 250 }}, {} }}"#,
+                aloft
+            )),
+            vec![("speed.altitudeFT".into(), ErrorCode::InvalidValue)]
+        );
+
+        let resolved = [
+            r#""speed": { "type": "knots", "value": 250, "altitudeFT": 10000 }"#,
+            r#""speed": { "type": "mach", "value": 0.78 }"#,
+            r#""speed": { "type": "knots", "value": 250 }, "profile": {}"#,
+        ];
+
+        for speed in &resolved {
+            let json = format!("{{ {}, {} }}", speed, aloft);
+            assert!(validation_errors(&json).is_empty(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn filters_validate() {
+        let errors = validation_errors(
+            r#"{
+                "speed": { "type": "knots", "value": -250 },
+                "profile": { "climbRateFPM": 0 },
+                "wind": { "type": "uniform", "value": { "direction": -90, "speedKT": 20 } },
+                "departure": {
+                    "icao": "KSFO|zzzz",
+                    "runways": { "length": { "min": 9000, "max": 8000 }, "minCount": 0 },
+                    "expr": "type = "
+                },
+                "arrival": {
+                    "radius": { "center": "ABCD", "max": 100 },
+                    "area": { "bbox": [0, 10, 1, 5] }
+                },
+                "timeDist": { "type": "time", "value": { "min": { "hour": 5, "minutes": 0 }, "max": { "hour": 1, "minutes": 0 } } },
+                "geometry": { "spacing": 0 }
+            }"#,
+        );
+
+        assert_eq!(
+            errors,
+            vec![
+                ("speed.value".into(), ErrorCode::InvalidValue),
+                ("profile.climbRateFPM".into(), ErrorCode::InvalidValue),
+                ("wind.value.direction".into(), ErrorCode::InvalidValue),
+                ("departure.icao".into(), ErrorCode::UnknownAirport),
+                ("departure.runways.length".into(), ErrorCode::InvalidRange),
+                ("departure.runways.minCount".into(), ErrorCode::InvalidValue),
+                ("departure.expr".into(), ErrorCode::InvalidExpression),
+                ("arrival.radius.center".into(), ErrorCode::UnknownAirport),
+                ("arrival.area".into(), ErrorCode::InvalidArea),
+                ("timeDist".into(), ErrorCode::InvalidRange),
+                ("geometry.spacing".into(), ErrorCode::InvalidValue),
+            ]
+        );
+
+        let valid = validation_errors(
+            r#"{
+                "speed": { "type": "mach", "value": 0.78 },
+                "departure": { "icao": "ksfo", "radius": { "center": "rjaa", "min": 10 } }
+            }"#,
+        );
+
+        assert!(valid.is_empty());
+    }
+}
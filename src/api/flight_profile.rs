@@ -0,0 +1,260 @@
+use super::error::{ErrorCode, ValidationErrors};
+use super::search_routes::{Route, Time};
+use super::speed::Speed;
+use serde_derive::{Deserialize, Serialize};
+
+/// How an aircraft flies each phase of a flight, for estimating flight times that account for
+/// taxiing, climbing and descending rather than cruising the whole way.
+///
+/// The cruise speed comes from the speed of the search. Any field that isn't specified
+/// falls back to a typical airliner.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FlightProfile {
+    #[serde(rename = "taxiOutMin")]
+    pub taxi_out_min: f32,
+    #[serde(rename = "climbRateFPM")]
+    pub climb_rate_fpm: f32,
+    /// The average true airspeed in knots while climbing.
+    #[serde(rename = "climbSpeedKT")]
+    pub climb_speed_kt: f32,
+    #[serde(rename = "cruiseAltitudeFT")]
+    pub cruise_altitude_ft: f32,
+    #[serde(rename = "descentRateFPM")]
+    pub descent_rate_fpm: f32,
+    /// The average true airspeed in knots while descending.
+    #[serde(rename = "descentSpeedKT")]
+    pub descent_speed_kt: f32,
+    /// Time spent maneuvering for the approach and landing on top of the descent.
+    #[serde(rename = "approachMin")]
+    pub approach_min: f32,
+    #[serde(rename = "taxiInMin")]
+    pub taxi_in_min: f32,
+}
+
+impl FlightProfile {
+    const MAX_ALTITUDE_FT: f32 = 65_000.0;
+    const MAX_PHASE_MIN: f32 = 120.0;
+
+    /// Estimate how long it takes to fly a route, cruising at `speed` or the ground speed
+    /// from the route's wind when it has one.
+    ///
+    /// Routes that are too short to reach the cruise altitude level off at the highest altitude
+    /// that still leaves room to descend.
+    pub fn estimate(&self, route: &Route, speed: Speed) -> FlightTime {
+        let departure_ft = route.from.elevation_ft.unwrap_or(0) as f32;
+        let arrival_ft = route.to.elevation_ft.unwrap_or(0) as f32;
+
+        // Distance covered for every foot of altitude gained or lost
+        let climb_nm_per_ft = self.climb_speed_kt / (self.climb_rate_fpm * 60.0);
+        let descent_nm_per_ft = self.descent_speed_kt / (self.descent_rate_fpm * 60.0);
+
+        let phase_distance = |altitude: f32| {
+            (altitude - departure_ft).max(0.0) * climb_nm_per_ft
+                + (altitude - arrival_ft).max(0.0) * descent_nm_per_ft
+        };
+
+        let altitude = if phase_distance(self.cruise_altitude_ft) <= route.distance {
+            self.cruise_altitude_ft
+        } else {
+            let top_ft =
+                (route.distance + departure_ft * climb_nm_per_ft + arrival_ft * descent_nm_per_ft)
+                    / (climb_nm_per_ft + descent_nm_per_ft);
+
+            top_ft.max(departure_ft).max(arrival_ft)
+        };
+
+        let climb_min = (altitude - departure_ft).max(0.0) / self.climb_rate_fpm;
+        let descent_min = (altitude - arrival_ft).max(0.0) / self.descent_rate_fpm;
+        let cruise_nm = (route.distance - phase_distance(altitude)).max(0.0);
+
+        // The cruise speed is flown at the profile's altitude unless the speed says otherwise
+        let cruise_speed = Speed {
+            altitude_ft: speed.altitude_ft.or(Some(self.cruise_altitude_ft)),
+            ..speed
+        };
+
+        // The wind is only applied to the cruise, since it was found at the cruise altitude
+        let cruise_speed_kt = match &route.wind {
+            Some(wind) => wind.ground_speed_kt,
+            None => cruise_speed.true_airspeed_kt(),
+        };
+
+        let cruise_min = cruise_nm / cruise_speed_kt * 60.0;
+        let air_min = climb_min + cruise_min + descent_min + self.approach_min;
+        let block_min = self.taxi_out_min + air_min + self.taxi_in_min;
+
+        FlightTime {
+            block: Time::from_hours(block_min / 60.0),
+            air: Time::from_hours(air_min / 60.0),
+            cruise_altitude_ft: altitude.round(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let rates = [
+            ("climbRateFPM", self.climb_rate_fpm),
+            ("climbSpeedKT", self.climb_speed_kt),
+            ("descentRateFPM", self.descent_rate_fpm),
+            ("descentSpeedKT", self.descent_speed_kt),
+        ];
+
+        for &(field, value) in &rates {
+            if !value.is_finite() || value <= 0.0 {
+                errors.add(
+                    field,
+                    ErrorCode::InvalidValue,
+                    format!("must be greater than 0, got {}", value),
+                );
+            }
+        }
+
+        if !(0.0..=Self::MAX_ALTITUDE_FT).contains(&self.cruise_altitude_ft) {
+            errors.add(
+                "cruiseAltitudeFT",
+                ErrorCode::InvalidValue,
+                format!(
+                    "cruise altitude must be between 0 and {} ft, got {}",
+                    Self::MAX_ALTITUDE_FT,
+                    self.cruise_altitude_ft
+                ),
+            );
+        }
+
+        let phases = [
+            ("taxiOutMin", self.taxi_out_min),
+            ("approachMin", self.approach_min),
+            ("taxiInMin", self.taxi_in_min),
+        ];
+
+        for &(field, value) in &phases {
+            if !(0.0..=Self::MAX_PHASE_MIN).contains(&value) {
+                errors.add(
+                    field,
+                    ErrorCode::InvalidValue,
+                    format!(
+                        "must be between 0 and {} minutes, got {}",
+                        Self::MAX_PHASE_MIN,
+                        value
+                    ),
+                );
+            }
+        }
+
+        errors.into_result()
+    }
+}
+
+impl Default for FlightProfile {
+    fn default() -> Self {
+        Self {
+            taxi_out_min: 15.0,
+            climb_rate_fpm: 2000.0,
+            climb_speed_kt: 300.0,
+            cruise_altitude_ft: 35_000.0,
+            descent_rate_fpm: 1800.0,
+            descent_speed_kt: 320.0,
+            approach_min: 5.0,
+            taxi_in_min: 7.0,
+        }
+    }
+}
+
+/// Flight times estimated from a `FlightProfile`.
+#[derive(Copy, Clone, Debug, Serialize, PartialEq)]
+pub struct FlightTime {
+    /// The time from leaving the gate to arriving at the gate.
+    pub block: Time,
+    /// The time from takeoff to landing.
+    pub air: Time,
+    /// The highest altitude reached, which is below the profile's cruise altitude on short routes.
+    #[serde(rename = "cruiseAltitudeFT")]
+    pub cruise_altitude_ft: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::{get_airport, get_airports};
+    use crate::api::speed::SpeedKind;
+    use crate::api::wind::RouteWind;
+    use crate::geo::geodesic::DistanceOptions;
+    use crate::wind::Wind;
+
+    fn profile(json: &str) -> FlightProfile {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn estimate_flight_time() {
+        let airports = get_airports();
+        let speed = SpeedKind::Mach(0.78).into();
+
+        let estimate = |from, to, profile: &FlightProfile| {
+            let route = Route::new(
+                get_airport(&airports, from),
+                get_airport(&airports, to),
+                speed,
+                DistanceOptions::default(),
+            );
+            profile.estimate(&route, speed)
+        };
+
+        let default = FlightProfile::default();
+
+        let long = estimate("KSFO", "RJAA", &default);
+        assert_eq!(long.cruise_altitude_ft, 35_000.0);
+        assert_eq!(long.air.to_string(), "10h09m");
+        assert_eq!(long.block.to_string(), "10h31m");
+
+        // Too short to reach FL350, where cruising the whole way would take 9 minutes
+        let short = estimate("KSFO", "KSAC", &default);
+        assert_eq!(short.cruise_altitude_ft, 12_465.0);
+        assert_eq!(short.air.to_string(), "0h18m");
+        assert_eq!(short.block.to_string(), "0h40m");
+
+        let no_taxi = profile(r#"{ "taxiOutMin": 0, "taxiInMin": 0 }"#);
+        assert_eq!(estimate("KSFO", "KSAC", &no_taxi).block, short.air);
+
+        // The cruise is flown at the ground speed from the wind
+        let mut route = Route::new(
+            get_airport(&airports, "RJAA"),
+            get_airport(&airports, "KSFO"),
+            speed,
+            DistanceOptions::default(),
+        );
+        let tailwind = Wind {
+            direction: 270.0,
+            speed_kt: 100.0,
+        };
+
+        route.wind = RouteWind::new(
+            &route,
+            &tailwind,
+            449.6,
+            35_000.0,
+            DistanceOptions::default(),
+        );
+        assert!(default.estimate(&route, speed).block < estimate("RJAA", "KSFO", &default).block);
+    }
+
+    #[test]
+    fn flight_profile_validate() {
+        let fields = |json| match profile(json).validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .errors()
+                .iter()
+                .map(|err| err.field.clone())
+                .collect(),
+        };
+
+        assert!(fields("{}").is_empty());
+        assert_eq!(
+            fields(r#"{ "climbRateFPM": 0, "cruiseAltitudeFT": 90000, "taxiInMin": -5 }"#),
+            vec!["climbRateFPM", "cruiseAltitudeFT", "taxiInMin"]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{anyhow, Result};
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use std::fmt;
+use std::result;
+
+/// A case-insensitive filter over airport ICAO codes.
+///
+/// A pattern is made up of one or more terms separated by `|`, `,` or whitespace.
+/// Each term is either an exact code (`KSFO`), or a glob where `*` matches any number
+/// of characters and `?` matches exactly one (`EG*`, `K??A`).
+#[derive(Debug, PartialEq)]
+pub struct IcaoPattern(Vec<Term>);
+
+impl IcaoPattern {
+    pub fn parse<S>(value: S) -> Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        Self::from_terms(&[value])
+    }
+
+    fn from_terms<S>(values: &[S]) -> Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        let mut terms = Vec::new();
+
+        for value in values {
+            let split = value
+                .as_ref()
+                .split(|c: char| c == '|' || c == ',' || c.is_whitespace())
+                .filter(|term| !term.is_empty());
+
+            for term in split {
+                let term = Term::parse(term)?;
+
+                if terms.contains(&term) {
+                    continue;
+                }
+
+                terms.push(term);
+            }
+        }
+
+        if terms.is_empty() {
+            return Err(anyhow!("ICAO pattern is empty"));
+        }
+
+        Ok(Self(terms))
+    }
+
+    /// Returns the codes this pattern consists of if none of its terms contain a wildcard.
+    pub fn exact_codes(&self) -> Option<Vec<&str>> {
+        self.0
+            .iter()
+            .map(|term| match term {
+                Term::Exact(code) => Some(code.as_str()),
+                Term::Glob(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn matches(&self, icao: &str) -> bool {
+        self.0.iter().any(|term| term.matches(icao))
+    }
+}
+
+impl<'de> Deserialize<'de> for IcaoPattern {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct IcaoPatternVisitor;
+
+        impl<'de> Visitor<'de> for IcaoPatternVisitor {
+            type Value = IcaoPattern;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an ICAO pattern or a list of ICAO patterns")
+            }
+
+            fn visit_str<E>(self, value: &str) -> result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                IcaoPattern::parse(value).map_err(E::custom)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> result::Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(1));
+
+                while let Some(value) = seq.next_element::<String>()? {
+                    values.push(value);
+                }
+
+                IcaoPattern::from_terms(&values).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(IcaoPatternVisitor)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Term {
+    Exact(String),
+    Glob(String),
+}
+
+impl Term {
+    fn parse(value: &str) -> Result<Self> {
+        let is_valid = value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '*' || c == '?');
+
+        if !is_valid {
+            return Err(anyhow!(
+                "ICAO pattern \"{}\" can only contain A-Z, 0-9, * and ?",
+                value
+            ));
+        }
+
+        let value = value.to_ascii_uppercase();
+
+        if value.contains(&['*', '?'][..]) {
+            Ok(Self::Glob(value))
+        } else {
+            Ok(Self::Exact(value))
+        }
+    }
+
+    fn matches(&self, icao: &str) -> bool {
+        match self {
+            Self::Exact(code) => code.eq_ignore_ascii_case(icao),
+            Self::Glob(pattern) => glob_matches(pattern.as_bytes(), icao.as_bytes()),
+        }
+    }
+}
+
+/// Match `value` against a glob `pattern`, ignoring ASCII case.
+pub fn glob_matches(pattern: &[u8], value: &[u8]) -> bool {
+    let mut pat_idx = 0;
+    let mut val_idx = 0;
+    // Position of the last seen `*` in the pattern and the value index it's currently matched up to
+    let mut backtrack = None;
+
+    while val_idx < value.len() {
+        match pattern.get(pat_idx) {
+            Some(b'*') => {
+                backtrack = Some((pat_idx, val_idx));
+                pat_idx += 1;
+            }
+            Some(&c) if c == b'?' || c.eq_ignore_ascii_case(&value[val_idx]) => {
+                pat_idx += 1;
+                val_idx += 1;
+            }
+            _ => match backtrack {
+                Some((star_idx, star_val_idx)) => {
+                    pat_idx = star_idx + 1;
+                    val_idx = star_val_idx + 1;
+                    backtrack = Some((star_idx, val_idx));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[pat_idx..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_matches(b"EG*", b"EGLL"));
+        assert!(glob_matches(b"EG*", b"EG"));
+        assert!(glob_matches(b"K??A", b"KSEA"));
+        assert!(glob_matches(b"*A", b"KSEA"));
+        assert!(glob_matches(b"K*A", b"KAAA"));
+        assert!(glob_matches(b"k*", b"KSFO"));
+
+        assert!(!glob_matches(b"EG*", b"LFPG"));
+        assert!(!glob_matches(b"K??A", b"KSFO"));
+        assert!(!glob_matches(b"K??A", b"KSEAA"));
+        assert!(!glob_matches(b"K*A", b"KSFO"));
+    }
+
+    #[test]
+    fn pattern_matching() {
+        let pattern = IcaoPattern::parse("lf*|LE*").unwrap();
+
+        assert!(pattern.matches("LFPG"));
+        assert!(pattern.matches("LEMD"));
+        assert!(!pattern.matches("EGLL"));
+        assert_eq!(pattern.exact_codes(), None);
+
+        let pattern = IcaoPattern::parse("ksfo, KSEA rjaa").unwrap();
+
+        assert!(pattern.matches("KSFO"));
+        assert!(pattern.matches("rjaa"));
+        assert!(!pattern.matches("KSAC"));
+        assert_eq!(pattern.exact_codes(), Some(vec!["KSFO", "KSEA", "RJAA"]));
+    }
+
+    #[test]
+    fn pattern_parse_errors() {
+        assert!(IcaoPattern::parse("").is_err());
+        assert!(IcaoPattern::parse(" | ").is_err());
+        assert!(IcaoPattern::parse("EG-*").is_err());
+    }
+
+    #[test]
+    fn pattern_deserialize_list() {
+        let pattern: IcaoPattern = serde_json::from_str(r#"["KSFO", "eg*", "KSFO"]"#).unwrap();
+
+        assert_eq!(
+            pattern,
+            IcaoPattern(vec![Term::Exact("KSFO".into()), Term::Glob("EG*".into())])
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use super::error::{self, ErrorCode, FieldError, ValidationErrors};
+use super::search_routes::Time;
+use super::speed::Speed;
+use crate::airport_data::{Airport, Airports};
+use crate::geo::geodesic::{self, DistanceOptions};
+use rocket::http::ContentType;
+use rocket::response::{content, status};
+use rocket::State;
+use rocket_contrib::json::{Json, JsonError, JsonValue};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Get the distance and flight time between every pair of airports in a list.
+#[post("/matrix", format = "json", data = "<query>")]
+pub fn matrix<'a>(
+    query: Result<Json<MatrixQuery>, JsonError<'_>>,
+    airports: State<'a, Airports>,
+) -> Result<content::Custom<String>, status::Custom<JsonValue>> {
+    let query = query.map_err(error::malformed_json)?.into_inner();
+    let format = query.format;
+
+    let matrix = query
+        .into_matrix(&airports)
+        .map_err(ValidationErrors::into_response)?;
+
+    let response = match format {
+        MatrixFormat::Json => content::Custom(ContentType::JSON, json!(matrix).to_string()),
+        MatrixFormat::Csv => content::Custom(ContentType::CSV, matrix.to_csv()),
+    };
+
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatrixQuery {
+    /// The ICAO, IATA or GPS codes of the airports.
+    airports: Vec<String>,
+    speed: Speed,
+    /// How distances are measured.
+    #[serde(default)]
+    distance: DistanceOptions,
+    #[serde(default)]
+    format: MatrixFormat,
+}
+
+impl MatrixQuery {
+    const MIN_AIRPORTS: usize = 2;
+    const MAX_AIRPORTS: usize = 200;
+
+    fn into_matrix(self, airports: &Airports) -> Result<Matrix<'_>, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if let Err(errs) = self.speed.validate() {
+            errors.extend_within("speed", errs);
+        }
+
+        let count = self.airports.len();
+
+        if !(Self::MIN_AIRPORTS..=Self::MAX_AIRPORTS).contains(&count) {
+            errors.add(
+                "airports",
+                ErrorCode::InvalidValue,
+                format!(
+                    "between {} and {} airports are required, got {}",
+                    Self::MIN_AIRPORTS,
+                    Self::MAX_AIRPORTS,
+                    count
+                ),
+            );
+        }
+
+        let mut found = Vec::with_capacity(count);
+
+        for (i, ident) in self.airports.iter().enumerate() {
+            match airports.find_by_ident(ident) {
+                Some(airport) => found.push(airport),
+                None => errors.push(FieldError::unknown_ident(format!("airports[{}]", i), ident)),
+            }
+        }
+
+        errors.into_result()?;
+
+        Ok(Matrix::new(found, self.speed, self.distance))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum MatrixFormat {
+    Json,
+    /// One row for every pair of different airports.
+    Csv,
+}
+
+impl Default for MatrixFormat {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
+/// The distances and times between airports, where row `i` and column `j` is the route
+/// from the `i`th airport to the `j`th airport.
+#[derive(Debug, Serialize)]
+struct Matrix<'a> {
+    /// The ICAO codes of the airports in the order of the rows and columns.
+    #[serde(serialize_with = "serialize_icaos")]
+    airports: Vec<&'a Airport>,
+    /// Distances in nautical miles.
+    distances: Vec<Vec<f32>>,
+    times: Vec<Vec<Time>>,
+}
+
+impl<'a> Matrix<'a> {
+    fn new(airports: Vec<&'a Airport>, speed: Speed, options: DistanceOptions) -> Self {
+        let count = airports.len();
+        let mut distances = vec![vec![0.0; count]; count];
+        let mut times = vec![vec![Time::default(); count]; count];
+
+        // Great circle and rhumb line distances are the same in both directions
+        for i in 0..count {
+            for j in (i + 1)..count {
+                let (from, to) = (airports[i].position, airports[j].position);
+                let distance = geodesic::distance_nm(from, to, options);
+                let time = Time::from_distance(distance, speed);
+
+                distances[i][j] = distance;
+                distances[j][i] = distance;
+                times[i][j] = time;
+                times[j][i] = time;
+            }
+        }
+
+        Self {
+            airports,
+            distances,
+            times,
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("from,to,distance,time\n");
+
+        for (i, from) in self.airports.iter().enumerate() {
+            for (j, to) in self.airports.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+
+                // Writing to a string can't fail
+                let _ = writeln!(
+                    csv,
+                    "{},{},{:.1},{}",
+                    from.icao, to.icao, self.distances[i][j], self.times[i][j]
+                );
+            }
+        }
+
+        csv
+    }
+}
+
+fn serialize_icaos<S>(airports: &[&Airport], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_seq(airports.iter().map(|arpt| &arpt.icao))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::get_airports;
+
+    fn query(json: &str) -> MatrixQuery {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn distance_matrix() {
+        let airports = get_airports();
+
+        let matrix = query(
+            r#"{
+                "airports": ["SFO", "KSAC", "nrt"],
+                "speed": { "type": "knots", "value": 450 }
+            }"#,
+        )
+        .into_matrix(&airports)
+        .unwrap();
+
+        let value = serde_json::to_value(&matrix).unwrap();
+        assert_eq!(
+            value["airports"],
+            serde_json::json!(["KSFO", "KSAC", "RJAA"])
+        );
+
+        let distances = &matrix.distances;
+        assert_eq!(distances[0][0], 0.0);
+        assert_eq!(distances[0][2].round(), 4442.0);
+        assert_eq!(distances[0][1], distances[1][0]);
+
+        assert_eq!(matrix.times[0][1], matrix.times[1][0]);
+        assert_eq!(
+            value["times"][0][2],
+            serde_json::json!({ "hour": 9, "minutes": 52 })
+        );
+    }
+
+    #[test]
+    fn distance_matrix_csv() {
+        let airports = get_airports();
+
+        let matrix = query(
+            r#"{
+                "airports": ["KSFO", "RJAA"],
+                "speed": { "type": "mach", "value": 0.85 },
+                "format": "csv"
+            }"#,
+        )
+        .into_matrix(&airports)
+        .unwrap();
+
+        assert_eq!(
+            matrix.to_csv(),
+            "from,to,distance,time\nKSFO,RJAA,4442.5,9h04m\nRJAA,KSFO,4442.5,9h04m\n"
+        );
+    }
+
+    #[test]
+    fn distance_matrix_validate() {
+        let errors = query(
+            r#"{
+                "airports": ["ZZZZ"],
+                "speed": { "type": "knots", "value": -1 }
+            }"#,
+        )
+        .into_matrix(&get_airports())
+        .unwrap_err();
+
+        let fields = errors
+            .errors()
+            .iter()
+            .map(|err| err.field.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(fields, vec!["speed.value", "airports", "airports[0]"]);
+    }
+}
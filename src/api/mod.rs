@@ -1 +1,14 @@
+pub mod aircraft;
+pub mod airports;
+pub mod error;
+pub mod filter_expr;
+pub mod filters;
+pub mod flight_profile;
+pub mod icao_pattern;
+pub mod matrix;
+pub mod route;
 pub mod search_routes;
+pub mod speed;
+pub mod suggest;
+pub mod units;
+pub mod wind;
@@ -0,0 +1,204 @@
+use super::airports::AirportDetails;
+use super::error::{ErrorCode, FieldError, ValidationErrors};
+use super::search_routes::{Route, Time};
+use super::speed::Speed;
+use crate::airport_data::{Airport, Airports, Position, Runway};
+use crate::geo;
+use crate::geo::geodesic::{DistanceOptions, PathKind};
+use rocket::response::status;
+use rocket::State;
+use rocket_contrib::json::JsonValue;
+use serde_derive::Serialize;
+
+/// Get the details of the route between two airports given by their ICAO, IATA or GPS codes.
+///
+/// `speed` is either in knots, such as `450kts`, or a Mach number, such as `M0.85`.
+#[get("/route?<from>&<to>&<speed>")]
+pub fn route<'a>(
+    from: Option<String>,
+    to: Option<String>,
+    speed: Option<String>,
+    airports: State<'a, Airports>,
+) -> Result<JsonValue, status::Custom<JsonValue>> {
+    let query = RouteQuery { from, to, speed };
+
+    let details = query
+        .into_details(&airports)
+        .map_err(ValidationErrors::into_response)?;
+
+    Ok(json!({ "route": details }))
+}
+
+struct RouteQuery {
+    from: Option<String>,
+    to: Option<String>,
+    speed: Option<String>,
+}
+
+impl RouteQuery {
+    fn into_details(self, airports: &Airports) -> Result<RouteDetails<'_>, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let mut find_airport = |field: &str, ident: Option<String>| {
+            let ident = match ident {
+                Some(ident) => ident,
+                None => {
+                    errors.add(
+                        field,
+                        ErrorCode::InvalidValue,
+                        format!("{} is required", field),
+                    );
+                    return None;
+                }
+            };
+
+            let airport = airports.find_by_ident(&ident);
+
+            if airport.is_none() {
+                errors.push(FieldError::unknown_ident(field, &ident));
+            }
+
+            airport
+        };
+
+        let from = find_airport("from", self.from);
+        let to = find_airport("to", self.to);
+
+        let speed = match self.speed.as_deref().map(str::parse::<Speed>) {
+            Some(Ok(speed)) => match speed.validate() {
+                Ok(()) => Some(speed),
+                Err(errs) => {
+                    errors.extend_within("speed", errs);
+                    None
+                }
+            },
+            Some(Err(msg)) => {
+                errors.add("speed", ErrorCode::InvalidValue, msg);
+                None
+            }
+            None => {
+                errors.add("speed", ErrorCode::InvalidValue, "speed is required".into());
+                None
+            }
+        };
+
+        match (from, to, speed) {
+            (Some(from), Some(to), Some(speed)) => {
+                errors.into_result()?;
+                Ok(RouteDetails::new(from, to, speed, airports))
+            }
+            // Every missing value has already been reported as an error
+            _ => Err(errors),
+        }
+    }
+}
+
+/// A route along with everything that's useful to know when flying it.
+#[derive(Debug, Serialize)]
+struct RouteDetails<'a> {
+    from: AirportDetails<'a>,
+    to: AirportDetails<'a>,
+    distance: f32,
+    time: Time,
+    #[serde(rename = "initialCourse")]
+    initial_course: f32,
+    #[serde(rename = "finalCourse")]
+    final_course: f32,
+    /// The point halfway along the great circle between both airports.
+    midpoint: Position,
+    /// The best runway to depart from.
+    #[serde(rename = "departureRunway")]
+    departure_runway: Option<&'a Runway>,
+    /// The best runway to arrive on.
+    #[serde(rename = "arrivalRunway")]
+    arrival_runway: Option<&'a Runway>,
+}
+
+impl<'a> RouteDetails<'a> {
+    fn new(from: &'a Airport, to: &'a Airport, speed: Speed, airports: &'a Airports) -> Self {
+        let route = Route::new(from, to, speed, DistanceOptions::default());
+
+        Self {
+            from: AirportDetails::new(route.from, airports),
+            to: AirportDetails::new(route.to, airports),
+            distance: route.distance,
+            time: route.time,
+            initial_course: route.initial_course,
+            final_course: route.final_course,
+            midpoint: geo::path::halfway_point(from.position, to.position, PathKind::GreatCircle),
+            departure_runway: from.best_runway(),
+            arrival_runway: to.best_runway(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::get_airports;
+
+    fn query(from: Option<&str>, to: Option<&str>, speed: Option<&str>) -> RouteQuery {
+        RouteQuery {
+            from: from.map(Into::into),
+            to: to.map(Into::into),
+            speed: speed.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn route_details() {
+        let airports = get_airports();
+
+        let details = query(Some("SFO"), Some("rjaa"), Some("M0.85"))
+            .into_details(&airports)
+            .unwrap();
+
+        let details = serde_json::to_value(details).unwrap();
+
+        assert_eq!(details["from"]["icao"], "KSFO");
+        assert_eq!(details["from"]["country"], "United States");
+        assert_eq!(details["to"]["icao"], "RJAA");
+        assert_eq!(details["distance"].as_f64().unwrap().round(), 4442.0);
+        assert_eq!(
+            details["time"],
+            serde_json::json!({ "hour": 9, "minutes": 4 })
+        );
+        assert_eq!(details["initialCourse"].as_f64().unwrap().round(), 303.0);
+        assert_eq!(
+            details["midpoint"]["latitudeDeg"].as_f64().unwrap().round(),
+            48.0
+        );
+        // The longer runway at KSFO is closed
+        assert_eq!(details["departureRunway"]["heMarker"]["name"], "01L");
+        assert_eq!(details["arrivalRunway"]["heMarker"]["name"], "16L");
+    }
+
+    #[test]
+    fn route_details_validate() {
+        let airports = get_airports();
+
+        let fields = |query: RouteQuery| {
+            query
+                .into_details(&airports)
+                .unwrap_err()
+                .errors()
+                .iter()
+                .map(|err| (err.field.clone(), err.code))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            fields(query(Some("ZZZZ"), None, Some("fast"))),
+            vec![
+                ("from".into(), ErrorCode::UnknownAirport),
+                ("to".into(), ErrorCode::InvalidValue),
+                ("speed".into(), ErrorCode::InvalidValue),
+            ]
+        );
+
+        assert_eq!(
+            fields(query(Some("KSFO"), Some("KSAC"), Some("0kts"))),
+            vec![("speed.value".into(), ErrorCode::InvalidValue)]
+        );
+    }
+}
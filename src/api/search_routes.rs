@@ -1,56 +1,81 @@
-use crate::airport_data::{Airport, AirportType, Position, Runway};
+use super::aircraft::AircraftCatalog;
+use super::error::{self, ValidationErrors};
+use super::filters::airport::{self, AirportMatches};
+use super::filters::constraint::RouteConstraint;
+use super::filters::{Filters, Rejection};
+use super::flight_profile::FlightTime;
+use super::speed::Speed;
+use super::units::DistanceUnit;
+use super::wind::RouteWind;
+use crate::airport_data::{Airport, Airports, Position};
+use crate::geo;
+use crate::geo::geodesic::{self, DistanceOptions, PathKind};
+use crate::wind::forecast::WindForecast;
 use rand::seq::SliceRandom;
+use rocket::response::status;
 use rocket::State;
-use rocket_contrib::json::{Json, JsonValue};
+use rocket_contrib::json::{Json, JsonError, JsonValue};
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 use std::ptr;
 
-const MAX_AIRPORTS_TO_GET: usize = 2000;
 const MAX_AIRPORTS_TO_RETURN: usize = 100;
 
 #[post("/search_routes", format = "json", data = "<filters>")]
-pub fn search_routes<'a>(filters: Json<Filters>, airports: State<'a, Vec<Airport>>) -> JsonValue {
+pub fn search_routes<'a>(
+    filters: Result<Json<Filters>, JsonError<'_>>,
+    airports: State<'a, Airports>,
+    aircraft: State<'a, AircraftCatalog>,
+    forecast: State<'a, Option<WindForecast>>,
+) -> Result<JsonValue, status::Custom<JsonValue>> {
+    let mut filters = filters.map_err(error::malformed_json)?.into_inner();
+    let forecast = forecast.as_ref();
+
+    let speed = filters
+        .prepare(&airports, &aircraft, forecast)
+        .map_err(ValidationErrors::into_response)?;
+
+    let wind = filters.wind.as_ref().and_then(|wind| wind.field(forecast));
+    let required_runways = filters.required_runways.as_ref();
+
     let departures = filters
         .departure
         .as_ref()
         .map(|filters| filters.matching_airports(&airports))
-        .unwrap_or_else(|| random_airport_set(&airports));
+        .unwrap_or_else(|| airport::random_airport_set(&airports, required_runways));
 
     let arrivals = filters
         .arrival
         .as_ref()
         .map(|filters| filters.matching_airports(&airports))
-        .unwrap_or_else(|| random_airport_set(&airports));
+        .unwrap_or_else(|| airport::random_airport_set(&airports, required_runways));
 
+    let mut stats = SearchStats::new(&departures, &arrivals, &filters.constraints);
     let mut routes = Vec::with_capacity(MAX_AIRPORTS_TO_RETURN / 2);
 
-    for departure in departures {
-        for arrival in &arrivals {
-            if ptr::eq(departure, *arrival) {
+    for departure in &departures.airports {
+        for arrival in &arrivals.airports {
+            if ptr::eq(*departure, *arrival) {
                 continue;
             }
 
-            let route = Route::new(departure, arrival, filters.speed);
+            stats.pairs_evaluated += 1;
 
-            match &filters.time_or_dist {
-                Some(TimeOrDistance::Time(time_range)) => {
-                    if !time_range.within(&route.time) {
-                        continue;
-                    }
-                }
-                Some(TimeOrDistance::Distance(dist_range)) => {
-                    if !dist_range.within(&route.distance) {
-                        continue;
-                    }
+            let (route, rejection) = filters.evaluate(departure, arrival, speed, wind);
+
+            match rejection {
+                Some(Rejection::Constraint(index)) => {
+                    stats.reject(index, &filters.constraints[index], &route)
                 }
-                None => (),
+                Some(Rejection::WindTooStrong) => stats.wind_too_strong += 1,
+                None => routes.push(route),
             }
-
-            routes.push(route);
         }
     }
 
+    stats.routes_found = routes.len();
+    stats.add_hints();
+
     let mut routes = routes.as_mut_slice();
 
     if routes.len() > 1 {
@@ -58,499 +83,501 @@ pub fn search_routes<'a>(filters: Json<Filters>, airports: State<'a, Vec<Airport
         routes = shuffled;
     }
 
-    json!({ "routes": routes })
-}
+    // Geometry is only worth calculating for the routes that are returned
+    if let Some(options) = &filters.geometry {
+        for route in routes.iter_mut() {
+            route.geometry = Some(RouteGeometry::new(
+                route.from,
+                route.to,
+                options,
+                filters.distance.path,
+            ));
+        }
+    }
 
-#[inline(always)]
-fn random_airport_set(airports: &[Airport]) -> Vec<&Airport> {
-    airports
-        .choose_multiple(&mut rand::thread_rng(), MAX_AIRPORTS_TO_GET)
-        .collect()
+    Ok(json!({ "routes": routes, "stats": stats }))
 }
 
+/// Counts of how many airports and routes made it through each stage of a search,
+/// so that empty results can be explained.
 #[derive(Debug, Serialize)]
-struct Route<'a> {
-    from: &'a Airport,
-    to: &'a Airport,
-    distance: f32,
-    time: Time,
+struct SearchStats {
+    departures: CandidateStats,
+    arrivals: CandidateStats,
+    #[serde(rename = "pairsEvaluated")]
+    pairs_evaluated: usize,
+    /// The routes rejected by each route constraint, in the same order as the constraints.
+    ///
+    /// A route is only counted against the first constraint that it failed, where time and
+    /// crosswind constraints are checked after every other constraint.
+    #[serde(rename = "rejectedByConstraint")]
+    rejected_by_constraint: Vec<ConstraintStats>,
+    /// The routes that couldn't be flown against the wind.
+    #[serde(rename = "windTooStrong")]
+    wind_too_strong: usize,
+    #[serde(rename = "routesFound")]
+    routes_found: usize,
+    hints: Vec<String>,
 }
 
-impl<'a> Route<'a> {
-    #[inline(always)]
-    fn new(from: &'a Airport, to: &'a Airport, speed: Speed) -> Self {
-        let distance = Self::calculate_distance(from.position, to.position);
-        let time = Time::from_distance(distance, speed);
+impl SearchStats {
+    fn new(
+        departures: &AirportMatches,
+        arrivals: &AirportMatches,
+        constraints: &[RouteConstraint],
+    ) -> Self {
+        let rejected_by_constraint = constraints
+            .iter()
+            .map(|constraint| ConstraintStats {
+                constraint: constraint.name(),
+                rejected: 0,
+                closest: None,
+            })
+            .collect();
 
         Self {
-            from,
-            to,
-            distance,
-            time,
+            departures: CandidateStats::from(departures),
+            arrivals: CandidateStats::from(arrivals),
+            pairs_evaluated: 0,
+            rejected_by_constraint,
+            wind_too_strong: 0,
+            routes_found: 0,
+            hints: Vec::new(),
         }
     }
 
-    /// Get the distance in nautical miles between two longitude and latitude points
-    /// using the Haversine formula.
-    fn calculate_distance(from: Position, to: Position) -> f32 {
-        // Radius of Earth in nautical miles
-        const RADIUS_NM: f32 = 3440.0;
-
-        let from_lat = from.latitude_deg.to_radians();
-        let to_lat = to.latitude_deg.to_radians();
-        let lat = to_lat - from_lat;
+    fn reject(&mut self, index: usize, constraint: &RouteConstraint, route: &Route) {
+        let stats = &mut self.rejected_by_constraint[index];
+        stats.rejected += 1;
 
-        let lon = (to.longitude_deg - from.longitude_deg).to_radians();
+        let missed_by = match constraint.missed_by(route) {
+            Some(missed_by) => missed_by,
+            None => return,
+        };
 
-        let a =
-            (lat / 2.0).sin().powi(2) + from_lat.cos() * to_lat.cos() * (lon / 2.0).sin().powi(2);
-        let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+        let is_closer = match &stats.closest {
+            Some(closest) => missed_by < closest.missed_by,
+            None => true,
+        };
 
-        RADIUS_NM * c
+        if is_closer {
+            stats.closest = Some(ClosestMiss {
+                missed_by,
+                time: route.estimated_time(),
+                distance: route.distance,
+            });
+        }
     }
-}
 
-#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, PartialEq, PartialOrd)]
-struct Time {
-    hour: u8,
-    minutes: u8,
-}
+    fn add_hints(&mut self) {
+        for (name, candidates) in &[("departure", &self.departures), ("arrival", &self.arrivals)] {
+            if candidates.matched == 0 {
+                self.hints
+                    .push(format!("no {} airports matched the filters", name));
+            } else if candidates.random {
+                // Random sets are always a sample, so they're only worth mentioning when nothing was found
+                if self.routes_found == 0 {
+                    self.hints.push(format!(
+                        "{} airports were a random sample of {}, add {} filters to search specific airports",
+                        name, candidates.matched, name
+                    ));
+                }
+            } else if candidates.truncated {
+                self.hints.push(format!(
+                    "only {} {} airports were searched, narrow the filters to search all of them",
+                    candidates.matched, name
+                ));
+            }
+        }
 
-impl Time {
-    fn from_distance(distance: f32, speed: Speed) -> Self {
-        let total_hours = distance / speed.as_knots();
+        if self.routes_found > 0 {
+            return;
+        }
 
-        let hour = total_hours.floor().min(99.0);
-        let minutes = ((total_hours - hour) * 60.0).floor().min(59.0);
+        for stats in &self.rejected_by_constraint {
+            let closest = match &stats.closest {
+                Some(closest) => closest,
+                None => continue,
+            };
 
-        Self {
-            hour: hour as u8,
-            minutes: minutes as u8,
+            let hint = match stats.constraint {
+                "time" => format!("closest route was {}", closest.time),
+                "dist" => format!("closest route was {:.0} nm", closest.distance),
+                _ => continue,
+            };
+
+            self.hints.push(hint);
+        }
+
+        if self.wind_too_strong > 0 {
+            self.hints.push(format!(
+                "{} routes couldn't be flown against the wind",
+                self.wind_too_strong
+            ));
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
-#[serde(tag = "type", content = "value")]
-pub enum Speed {
-    Mach(f32),
-    Knots(f32),
+#[derive(Debug, Serialize)]
+struct CandidateStats {
+    matched: usize,
+    truncated: bool,
+    random: bool,
 }
 
-impl Speed {
-    #[inline(always)]
-    fn as_knots(self) -> f32 {
-        const KNOTS_PER_MACH: f32 = 666.739;
-
-        match self {
-            Self::Mach(mach) => mach * KNOTS_PER_MACH,
-            Self::Knots(knots) => knots,
+impl From<&AirportMatches<'_>> for CandidateStats {
+    fn from(matches: &AirportMatches) -> Self {
+        Self {
+            matched: matches.airports.len(),
+            truncated: matches.truncated,
+            random: matches.random,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Filters {
-    speed: Speed,
-    departure: Option<AirportFilters>,
-    arrival: Option<AirportFilters>,
-    #[serde(rename = "timeDist", default)]
-    time_or_dist: Option<TimeOrDistance>,
+#[derive(Debug, Serialize)]
+struct ConstraintStats {
+    constraint: &'static str,
+    rejected: usize,
+    #[serde(skip)]
+    closest: Option<ClosestMiss>,
 }
 
-#[derive(Debug, Deserialize)]
-struct AirportFilters {
-    icao: Option<String>,
-    #[serde(rename = "airportType", default)]
-    airport_type: AirportType,
-    #[serde(rename = "runwayLength")]
-    runway_length: Option<RunwayLength>,
-    #[serde(default)]
-    countries: Vec<String>,
+/// The rejected route that came the closest to fitting a constraint.
+#[derive(Copy, Clone, Debug)]
+struct ClosestMiss {
+    missed_by: f32,
+    time: Time,
+    distance: f32,
 }
 
-impl AirportFilters {
-    fn matching_airports<'a>(&self, airports: &'a [Airport]) -> Vec<&'a Airport> {
-        if let Some(icao) = &self.icao {
-            let index = airports
-                .binary_search_by(|arpt| arpt.icao.as_str().cmp(icao.as_str()))
-                .ok();
-
-            let index = match index {
-                Some(index) => index,
-                None => return Vec::new(),
-            };
-
-            return vec![&airports[index]];
-        }
-
-        let type_filter = match self.airport_type {
-            AirportType::Unknown => OptionalFilter::Passthrough,
-            kind => OptionalFilter::Evaluate(move |arpt| arpt.class == kind),
-        };
-
-        let runway_len_filter = match self.runway_length {
-            Some(len) => OptionalFilter::Evaluate(move |arpt| len.fits_any(&arpt.runways)),
-            None => OptionalFilter::Passthrough,
-        };
-
-        let country_filter = match self.countries.as_slice() {
-            [] => OptionalFilter::Passthrough,
-            countries => OptionalFilter::Evaluate(move |arpt| {
-                Self::list_has_any(&arpt.country_name, countries)
-            }),
-        };
-
-        Self::airport_matches(
-            |arpt| {
-                type_filter.eval(arpt) && runway_len_filter.eval(arpt) && country_filter.eval(arpt)
-            },
-            airports,
-        )
-    }
-
-    fn airport_matches<F>(matcher: F, airports: &[Airport]) -> Vec<&Airport>
-    where
-        F: Fn(&Airport) -> bool,
-    {
-        let mut results = Vec::new();
-
-        for airport in airports {
-            if !matcher(airport) {
-                continue;
-            }
+#[derive(Debug, Serialize)]
+pub struct Route<'a> {
+    pub from: &'a Airport,
+    pub to: &'a Airport,
+    pub distance: f32,
+    pub time: Time,
+    /// The true course in degrees when departing.
+    #[serde(rename = "initialCourse")]
+    pub initial_course: f32,
+    /// The true course in degrees when arriving.
+    #[serde(rename = "finalCourse")]
+    pub final_course: f32,
+    /// The shape of the route for drawing it on a map, if it was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<RouteGeometry>,
+    /// Times estimated from a flight profile, if one was provided.
+    #[serde(rename = "flightTime", skip_serializing_if = "Option::is_none")]
+    pub flight_time: Option<FlightTime>,
+    /// The ground speed and time corrected for the wind, if one was provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind: Option<RouteWind>,
+    /// The fuel burned over the estimated time in kilograms, if an aircraft was selected.
+    #[serde(rename = "fuelKG", skip_serializing_if = "Option::is_none")]
+    pub fuel_kg: Option<f32>,
+}
 
-            results.push(airport);
+impl<'a> Route<'a> {
+    pub fn new(from: &'a Airport, to: &'a Airport, speed: Speed, options: DistanceOptions) -> Self {
+        let leg = geodesic::measure(from.position, to.position, options);
+        let distance = leg.distance_nm as f32;
+        let time = Time::from_distance(distance, speed);
 
-            if results.len() >= MAX_AIRPORTS_TO_GET {
-                break;
-            }
+        Self {
+            from,
+            to,
+            distance,
+            time,
+            initial_course: leg.initial_course_deg as f32,
+            final_course: leg.final_course_deg as f32,
+            geometry: None,
+            flight_time: None,
+            wind: None,
+            fuel_kg: None,
         }
-
-        results
     }
 
+    /// Get the block time from the flight profile when there is one, or the cruise time
+    /// corrected for the wind otherwise.
     #[inline(always)]
-    fn list_has_any(name: &str, list: &[String]) -> bool {
-        list.iter().any(|x| x.eq_ignore_ascii_case(name))
+    pub fn estimated_time(&self) -> Time {
+        match (&self.flight_time, &self.wind) {
+            (Some(flight_time), _) => flight_time.block,
+            (None, Some(wind)) => wind.time,
+            (None, None) => self.time,
+        }
     }
 }
 
-enum OptionalFilter<F>
-where
-    F: Fn(&Airport) -> bool,
-{
-    Evaluate(F),
-    Passthrough,
+#[derive(Debug, Serialize)]
+pub struct RouteGeometry {
+    /// The great circle or rhumb line between both airports as one or more lines of points.
+    ///
+    /// The path is split into separate lines where it crosses the antimeridian, so that none of them
+    /// have to wrap around the map.
+    path: Vec<Vec<Position>>,
+    midpoint: Position,
+    /// The northernmost point along the route.
+    vertex: Position,
 }
 
-impl<F> OptionalFilter<F>
-where
-    F: Fn(&Airport) -> bool,
-{
-    fn eval(&self, airport: &Airport) -> bool {
-        match self {
-            Self::Evaluate(func) => func(airport),
-            Self::Passthrough => true,
+impl RouteGeometry {
+    fn new(from: &Airport, to: &Airport, options: &GeometryOptions, kind: PathKind) -> Self {
+        let (from, to) = (from.position, to.position);
+        let points = geo::path::densify(from, to, options.spacing_nm(), kind);
+
+        Self {
+            path: geo::path::split_at_antimeridian(&points),
+            midpoint: geo::path::halfway_point(from, to, kind),
+            vertex: geo::path::northernmost_point(from, to, kind),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, Deserialize)]
-#[serde(tag = "selector", content = "length")]
-pub enum RunwayLength {
-    #[serde(rename = "eq")]
-    Equal(u32),
-    #[serde(rename = "gt")]
-    GreaterThan(u32),
-    #[serde(rename = "lt")]
-    LessThan(u32),
+/// Options for the geometry that's included with each route.
+#[derive(Debug, Default, Deserialize)]
+pub struct GeometryOptions {
+    /// The largest distance between consecutive points on the path.
+    spacing: Option<f32>,
+    #[serde(default)]
+    unit: DistanceUnit,
 }
 
-impl RunwayLength {
+impl GeometryOptions {
+    const DEFAULT_SPACING_NM: f32 = 100.0;
+
     #[inline(always)]
-    pub fn fits(self, length: u32) -> bool {
-        match self {
-            Self::Equal(len) => len == length,
-            Self::GreaterThan(len) => length > len,
-            Self::LessThan(len) => length < len,
-        }
+    fn spacing_nm(&self) -> f32 {
+        self.spacing
+            .map(|spacing| self.unit.to_nm(spacing))
+            .unwrap_or(Self::DEFAULT_SPACING_NM)
     }
 
-    #[inline(always)]
-    pub fn fits_any(self, runways: &[Runway]) -> bool {
-        runways.iter().any(|runway| match runway.length_ft {
-            Some(len) => self.fits(len),
-            None => false,
-        })
+    pub fn validate(&self) -> Result<(), String> {
+        match self.spacing {
+            Some(spacing) if !spacing.is_finite() || spacing <= 0.0 => Err(format!(
+                "path spacing must be greater than 0, got {}",
+                spacing
+            )),
+            _ => Ok(()),
+        }
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
-pub struct Range<T>
-where
-    T: fmt::Debug + Default + PartialOrd,
-{
-    pub min: Option<T>,
-    pub max: Option<T>,
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, PartialEq, PartialOrd)]
+pub struct Time {
+    hour: u8,
+    minutes: u8,
 }
 
-impl<T> Range<T>
-where
-    T: fmt::Debug + Default + PartialOrd,
-{
-    fn within(&self, value: &T) -> bool {
-        if let Some(true) = self.min.as_ref().map(|min| value < min) {
-            return false;
-        }
+impl Time {
+    pub fn from_distance(distance: f32, speed: Speed) -> Self {
+        Self::from_hours(distance / speed.true_airspeed_kt())
+    }
+
+    pub fn from_hours(total_hours: f32) -> Self {
+        let hour = total_hours.floor().min(99.0);
+        let minutes = ((total_hours - hour) * 60.0).floor().min(59.0);
 
-        if let Some(true) = self.max.as_ref().map(|max| value > max) {
-            return false;
+        Self {
+            hour: hour as u8,
+            minutes: minutes as u8,
         }
+    }
 
-        true
+    #[inline(always)]
+    pub fn total_minutes(self) -> f32 {
+        f32::from(self.hour) * 60.0 + f32::from(self.minutes)
     }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
-#[serde(tag = "type", content = "value")]
-enum TimeOrDistance {
-    #[serde(rename = "time")]
-    Time(Range<Time>),
-    #[serde(rename = "dist")]
-    Distance(Range<f32>),
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}h{:02}m", self.hour, self.minutes)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::airport_data::RunwayMarker;
-    use std::collections::HashMap;
+    use crate::airport_data::test_data::{get_airport, get_airports};
+    use crate::api::error::FieldError;
+    use crate::api::filters::constraint::{CourseFilter, CourseSector};
+    use crate::api::filters::Range;
+    use crate::api::speed::SpeedKind;
 
     #[test]
-    fn range_within() {
-        macro_rules! within {
-            ($min:expr => $max:expr, $value:expr) => {
-                Range::within(
-                    &Range {
-                        min: $min,
-                        max: $max,
-                    },
-                    &$value,
-                )
-            };
-        }
-
-        assert_eq!(within!(Some(1) => Some(3), 2), true);
-        assert_eq!(within!(Some(2) => Some(3), 2), true);
-        assert_eq!(within!(Some(2) => Some(3), 3), true);
-
-        assert_eq!(within!(Some(1) => Some(5), 10), false);
-        assert_eq!(within!(Some(10) => Some(100), 9), false);
-    }
-
-    #[test]
-    fn runway_length_fits() {
-        assert_eq!(RunwayLength::Equal(12345).fits(12345), true);
-        assert_eq!(RunwayLength::Equal(12345).fits(12346), false);
-
-        assert_eq!(RunwayLength::GreaterThan(1000).fits(1001), true);
-        assert_eq!(RunwayLength::GreaterThan(1000).fits(1000), false);
-
-        assert_eq!(RunwayLength::LessThan(1000).fits(999), true);
-        assert_eq!(RunwayLength::LessThan(1000).fits(1000), false);
-    }
-
-    fn get_airports() -> Vec<Airport> {
-        // San Francisco
-        let ksfo = Airport {
-            icao: "KSFO".into(),
-            class: AirportType::Large,
-            position: Position::new(37.618, -122.375),
-            runways: vec![Runway {
-                length_ft: Some(7500),
-                width_ft: Some(200),
-                he_marker: Some(RunwayMarker::new("01L".into(), 0.0, 0.0)),
-                le_marker: Some(RunwayMarker::new("19R".into(), 0.0, 0.0)),
-            }],
-            frequencies: HashMap::new(),
-            country_name: "United States".into(),
-        };
-
-        // Sacramento Executive
-        let ksac = Airport {
-            icao: "KSAC".into(),
-            class: AirportType::Medium,
-            position: Position::new(38.512, -121.492),
-            runways: vec![Runway {
-                length_ft: Some(3836),
-                width_ft: Some(100),
-                he_marker: Some(RunwayMarker::new("12".into(), 0.0, 0.0)),
-                le_marker: Some(RunwayMarker::new("30".into(), 0.0, 0.0)),
-            }],
-            frequencies: HashMap::new(),
-            country_name: "United States".into(),
-        };
+    fn search_stats_hints() {
+        let airports = get_airports();
 
-        // Narita
-        let rjaa = Airport {
-            icao: "RJAA".into(),
-            class: AirportType::Large,
-            position: Position::new(35.764, 140.386),
-            runways: vec![Runway {
-                length_ft: Some(8202),
-                width_ft: Some(196),
-                he_marker: Some(RunwayMarker::new("16L".into(), 0.0, 0.0)),
-                le_marker: Some(RunwayMarker::new("34R".into(), 0.0, 0.0)),
-            }],
-            frequencies: HashMap::new(),
-            country_name: "Japan".into(),
+        let constraints = vec![
+            RouteConstraint::Course(CourseFilter::Sector(CourseSector::NorthWest)),
+            RouteConstraint::Time(Range {
+                min: Some(Time {
+                    hour: 20,
+                    minutes: 0,
+                }),
+                max: None,
+            }),
+        ];
+
+        let departures = AirportMatches {
+            airports: vec![
+                get_airport(&airports, "KSFO"),
+                get_airport(&airports, "RJAA"),
+            ],
+            truncated: false,
+            random: false,
         };
 
-        vec![ksfo, ksac, rjaa]
-    }
-
-    fn display_airports(airports: Vec<&Airport>) -> Vec<&String> {
-        airports.into_iter().map(|arpt| &arpt.icao).collect()
-    }
-
-    macro_rules! assert_expected_icaos {
-        ($result:expr, $expected:expr) => {
-            let result = $result;
-
-            assert!(
-                !result.is_empty(),
-                "expected to get airports {:?}, got nothing",
-                $expected
-            );
+        let mut stats = SearchStats::new(&departures, &AirportMatches::default(), &constraints);
 
-            assert_eq!(
-                $expected.len(),
-                result.len(),
-                "expected to get {} airport(s) ({:?}), got {} ({:?})",
-                $expected.len(),
-                $expected,
-                result.len(),
-                display_airports(result)
+        for (from, to) in &[("KSFO", "RJAA"), ("KSFO", "KSAC"), ("RJAA", "KSFO")] {
+            let route = Route::new(
+                get_airport(&airports, from),
+                get_airport(&airports, to),
+                SpeedKind::Knots(500.0).into(),
+                DistanceOptions::default(),
             );
 
-            let has_expected_arpts = result
-                .iter()
-                .all(|found| $expected.contains(&found.icao.as_str()));
+            if let Some(index) = constraints.iter().position(|c| !c.fits(&route)) {
+                stats.reject(index, &constraints[index], &route);
+            }
+        }
 
-            assert!(
-                has_expected_arpts,
-                "expected to get airports {:?}, got {:?}",
-                $expected,
-                display_airports(result)
-            );
-        };
-    }
+        stats.add_hints();
 
-    #[test]
-    fn filter_icao() {
-        const EXPECTED_ICAO: &'static str = "KSAC";
+        // Sacramento is northeast of San Francisco, and the flight back from Narita is eastbound
+        assert_eq!(stats.rejected_by_constraint[0].rejected, 2);
+        assert_eq!(stats.rejected_by_constraint[1].rejected, 1);
 
-        let airports = get_airports();
+        assert_eq!(
+            stats.hints,
+            vec![
+                "no arrival airports matched the filters".to_string(),
+                "closest route was 8h53m".to_string(),
+            ]
+        );
 
-        let filter = AirportFilters {
-            icao: Some(EXPECTED_ICAO.into()),
-            airport_type: AirportType::Unknown,
-            runway_length: None,
-            countries: vec![],
+        // Random sets are truncated by design, so they don't ask for narrower filters
+        let random = AirportMatches {
+            airports: vec![
+                get_airport(&airports, "KSFO"),
+                get_airport(&airports, "RJAA"),
+            ],
+            truncated: true,
+            random: true,
         };
 
-        assert_expected_icaos!(filter.matching_airports(&airports[..]), [EXPECTED_ICAO]);
-    }
+        let truncated = AirportMatches {
+            airports: random.airports.clone(),
+            truncated: true,
+            random: false,
+        };
 
-    #[test]
-    fn filter_airport_type() {
-        const EXPECTED_TYPE: AirportType = AirportType::Large;
-        const EXPECTED_ICAOS: [&'static str; 2] = ["KSFO", "RJAA"];
+        let mut stats = SearchStats::new(&random, &truncated, &[]);
+        stats.routes_found = 1;
+        stats.add_hints();
 
-        let airports = get_airports();
+        assert_eq!(
+            stats.hints,
+            vec!["only 2 arrival airports were searched, narrow the filters to search all of them"]
+        );
 
-        let filter = AirportFilters {
-            icao: None,
-            airport_type: EXPECTED_TYPE,
-            runway_length: None,
-            countries: vec![],
-        };
+        let mut stats = SearchStats::new(&random, &truncated, &[]);
+        stats.add_hints();
 
-        assert_expected_icaos!(filter.matching_airports(&airports[..]), EXPECTED_ICAOS);
+        assert_eq!(
+            stats.hints[0],
+            "departure airports were a random sample of 2, add departure filters to search specific airports"
+        );
     }
 
     #[test]
-    fn filter_runway_length_gt() {
-        const EXPECTED_ICAOS: [&'static str; 2] = ["KSFO", "RJAA"];
-
-        let airports = get_airports();
-
-        let filter = AirportFilters {
-            icao: None,
-            airport_type: AirportType::Unknown,
-            runway_length: Some(RunwayLength::GreaterThan(7000)),
-            countries: vec![],
-        };
-
-        assert_expected_icaos!(filter.matching_airports(&airports[..]), EXPECTED_ICAOS);
+    fn unknown_airport_message() {
+        let err = FieldError::unknown_airport("icao", "zzzz");
+        assert_eq!(err.message, "no airport with ICAO ZZZZ");
     }
 
     #[test]
-    fn filter_runway_length_eq() {
-        const EXPECTED_ICAOS: [&'static str; 1] = ["KSAC"];
-
+    fn route_courses() {
         let airports = get_airports();
 
-        let filter = AirportFilters {
-            icao: None,
-            airport_type: AirportType::Unknown,
-            runway_length: Some(RunwayLength::Equal(3836)),
-            countries: vec![],
+        let route = |options| {
+            Route::new(
+                get_airport(&airports, "KSFO"),
+                get_airport(&airports, "RJAA"),
+                SpeedKind::Knots(450.0).into(),
+                options,
+            )
         };
 
-        assert_expected_icaos!(filter.matching_airports(&airports[..]), EXPECTED_ICAOS);
-    }
+        let spherical = route(DistanceOptions::default());
 
-    #[test]
-    fn filter_runway_length_lt() {
-        const EXPECTED_ICAOS: [&'static str; 2] = ["KSFO", "KSAC"];
+        assert!((spherical.initial_course - 303.1).abs() < 0.1);
+        assert!((spherical.final_course - 234.8).abs() < 0.1);
+        assert!(CourseFilter::Sector(CourseSector::NorthWest).fits(spherical.initial_course));
 
-        let airports = get_airports();
+        let options: DistanceOptions = serde_json::from_str(r#"{ "model": "wgs84" }"#).unwrap();
+        let wgs84 = route(options);
 
-        let filter = AirportFilters {
-            icao: None,
-            airport_type: AirportType::Unknown,
-            runway_length: Some(RunwayLength::LessThan(7501)),
-            countries: vec![],
-        };
+        assert!((wgs84.distance - 4452.7).abs() < 0.1);
+
+        let options: DistanceOptions =
+            serde_json::from_str(r#"{ "model": "wgs84", "path": "rhumb" }"#).unwrap();
+        let rhumb = route(options);
 
-        assert_expected_icaos!(filter.matching_airports(&airports[..]), EXPECTED_ICAOS);
+        assert!(rhumb.distance > wgs84.distance);
+        assert_eq!(rhumb.initial_course, rhumb.final_course);
+        assert!(CourseFilter::Sector(CourseSector::West).fits(rhumb.initial_course));
     }
 
     #[test]
-    fn filter_countries() {
+    fn route_geometry() {
         let airports = get_airports();
 
-        let mut filter = AirportFilters {
-            icao: None,
-            airport_type: AirportType::Unknown,
-            runway_length: None,
-            countries: vec!["United States".into()],
-        };
+        let options: GeometryOptions =
+            serde_json::from_str(r#"{ "spacing": 500, "unit": "km" }"#).unwrap();
+        let geometry = RouteGeometry::new(
+            get_airport(&airports, "KSFO"),
+            get_airport(&airports, "RJAA"),
+            &options,
+            PathKind::GreatCircle,
+        );
 
-        assert_expected_icaos!(filter.matching_airports(&airports[..]), ["KSFO", "KSAC"]);
+        // The route crosses the antimeridian
+        assert_eq!(geometry.path.len(), 2);
 
-        filter.countries = vec!["Japan".into()];
-        assert_expected_icaos!(filter.matching_airports(&airports[..]), ["RJAA"]);
+        let points = geometry.path.iter().map(Vec::len).sum::<usize>();
+        // 4442 nm is 17 segments of at most 270 nm, plus both sides of the crossing
+        assert_eq!(points, 20);
 
-        filter.countries = vec!["United States".into(), "Japan".into()];
+        assert!(geometry.vertex.latitude_deg >= geometry.midpoint.latitude_deg);
 
-        assert_expected_icaos!(
-            filter.matching_airports(&airports[..]),
-            ["KSFO", "KSAC", "RJAA"]
+        // A rhumb line between airports at similar latitudes never goes further north than them
+        let rhumb = RouteGeometry::new(
+            get_airport(&airports, "KSFO"),
+            get_airport(&airports, "RJAA"),
+            &options,
+            PathKind::Rhumb,
         );
+        let ksfo = get_airport(&airports, "KSFO").position;
+
+        assert_eq!(rhumb.vertex, ksfo);
+        assert!(rhumb.midpoint.latitude_deg < ksfo.latitude_deg);
+        assert!(rhumb
+            .path
+            .iter()
+            .flatten()
+            .all(|pos| pos.latitude_deg <= ksfo.latitude_deg));
+
+        let value = serde_json::to_value(Route::new(
+            get_airport(&airports, "KSFO"),
+            get_airport(&airports, "KSAC"),
+            SpeedKind::Knots(450.0).into(),
+            DistanceOptions::default(),
+        ))
+        .unwrap();
+        assert!(value.get("geometry").is_none());
     }
 }
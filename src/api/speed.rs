@@ -0,0 +1,254 @@
+use super::error::{ErrorCode, ValidationErrors};
+use super::units::DistanceUnit;
+use crate::atmosphere::Atmosphere;
+use serde_derive::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// The cruise speed of an aircraft, along with the conditions it's flown in.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct Speed {
+    #[serde(flatten)]
+    pub kind: SpeedKind,
+    /// The cruise pressure altitude in feet, which indicated airspeeds and Mach numbers are converted at.
+    ///
+    /// Mach numbers default to `DEFAULT_MACH_ALTITUDE_FT` and indicated airspeeds to sea level when this isn't set.
+    #[serde(rename = "altitudeFT", skip_serializing_if = "Option::is_none")]
+    pub altitude_ft: Option<f32>,
+    /// The difference between the actual and standard temperature at the cruise altitude in degrees Celsius.
+    #[serde(rename = "isaDeviationC", default)]
+    pub isa_deviation_c: f32,
+}
+
+impl Speed {
+    /// The altitude that Mach numbers are flown at when no altitude is specified, which is typical for jets.
+    pub const DEFAULT_MACH_ALTITUDE_FT: f32 = 35_000.0;
+
+    const MIN_ALTITUDE_FT: f32 = -1_500.0;
+    const MAX_ALTITUDE_FT: f32 = 65_000.0;
+    const MAX_ISA_DEVIATION_C: f32 = 60.0;
+
+    /// Get the true airspeed in knots.
+    pub fn true_airspeed_kt(self) -> f32 {
+        match self.kind {
+            SpeedKind::Mach(mach) => self.atmosphere().mach_to_tas(mach),
+            SpeedKind::Knots(knots) => knots,
+            SpeedKind::Kias(knots) => self.atmosphere().cas_to_tas(knots),
+            SpeedKind::Kmh(kmh) => DistanceUnit::Kilometers.to_nm(kmh),
+            SpeedKind::Mph(mph) => DistanceUnit::Miles.to_nm(mph),
+        }
+    }
+
+    /// Get the altitude in feet that the speed is flown at, which is only known for
+    /// anything other than a Mach number when it's specified.
+    pub fn cruise_altitude_ft(self) -> Option<f32> {
+        match (self.altitude_ft, self.kind) {
+            (Some(altitude), _) => Some(altitude),
+            (None, SpeedKind::Mach(_)) => Some(Self::DEFAULT_MACH_ALTITUDE_FT),
+            (None, _) => None,
+        }
+    }
+
+    #[inline(always)]
+    fn atmosphere(self) -> Atmosphere {
+        let altitude = self.cruise_altitude_ft().unwrap_or(0.0);
+        Atmosphere::at(altitude, self.isa_deviation_c)
+    }
+
+    pub fn validate(self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let value = self.kind.value();
+
+        if !value.is_finite() || value <= 0.0 {
+            errors.add(
+                "value",
+                ErrorCode::InvalidValue,
+                format!("speed must be greater than 0, got {}", value),
+            );
+        }
+
+        if let Some(altitude) = self.altitude_ft {
+            if !(Self::MIN_ALTITUDE_FT..=Self::MAX_ALTITUDE_FT).contains(&altitude) {
+                errors.add(
+                    "altitudeFT",
+                    ErrorCode::InvalidValue,
+                    format!(
+                        "altitude must be between {} and {} ft, got {}",
+                        Self::MIN_ALTITUDE_FT,
+                        Self::MAX_ALTITUDE_FT,
+                        altitude
+                    ),
+                );
+            }
+        }
+
+        let max_deviation = Self::MAX_ISA_DEVIATION_C;
+
+        if !(-max_deviation..=max_deviation).contains(&self.isa_deviation_c) {
+            errors.add(
+                "isaDeviationC",
+                ErrorCode::InvalidValue,
+                format!(
+                    "ISA deviation must be between -{} and {} degrees, got {}",
+                    max_deviation, max_deviation, self.isa_deviation_c
+                ),
+            );
+        }
+
+        errors.into_result()
+    }
+}
+
+impl From<SpeedKind> for Speed {
+    fn from(kind: SpeedKind) -> Self {
+        Self {
+            kind,
+            altitude_ft: None,
+            isa_deviation_c: 0.0,
+        }
+    }
+}
+
+/// Parses a speed from text, such as `M0.85` for Mach, `450`, `450kts` or `450ktas` for true airspeed,
+/// `250kias` for indicated airspeed, `800kmh` or `500mph`.
+impl FromStr for Speed {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        type Kind = fn(f32) -> SpeedKind;
+
+        const SUFFIXES: [(&str, Kind); 7] = [
+            ("kias", SpeedKind::Kias),
+            ("ktas", SpeedKind::Knots),
+            ("kts", SpeedKind::Knots),
+            ("kt", SpeedKind::Knots),
+            ("km/h", SpeedKind::Kmh),
+            ("kmh", SpeedKind::Kmh),
+            ("mph", SpeedKind::Mph),
+        ];
+
+        let value = value.trim().to_ascii_lowercase();
+
+        let (number, kind): (_, Kind) = match value.strip_prefix('m') {
+            Some(mach) => (mach, SpeedKind::Mach),
+            None => SUFFIXES
+                .iter()
+                .find_map(|&(suffix, kind)| value.strip_suffix(suffix).map(|num| (num, kind)))
+                .unwrap_or((&value, SpeedKind::Knots)),
+        };
+
+        match number.trim().parse() {
+            Ok(number) => Ok(Self::from(kind(number))),
+            Err(_) => Err(format!(
+                "\"{}\" is not a speed in knots, Mach, km/h or mph, such as 450kts or M0.85",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+#[serde(tag = "type", content = "value")]
+pub enum SpeedKind {
+    Mach(f32),
+    /// True airspeed in knots.
+    #[serde(alias = "ktas")]
+    Knots(f32),
+    /// Indicated airspeed in knots.
+    Kias(f32),
+    Kmh(f32),
+    Mph(f32),
+}
+
+impl SpeedKind {
+    #[inline(always)]
+    fn value(self) -> f32 {
+        match self {
+            Self::Mach(value)
+            | Self::Knots(value)
+            | Self::Kias(value)
+            | Self::Kmh(value)
+            | Self::Mph(value) => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Speed {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 0.5,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    #[test]
+    fn true_airspeed() {
+        let tas = |json| parse(json).true_airspeed_kt();
+
+        assert_close(tas(r#"{ "type": "knots", "value": 450 }"#), 450.0);
+        assert_close(tas(r#"{ "type": "ktas", "value": 450 }"#), 450.0);
+        assert_close(tas(r#"{ "type": "kmh", "value": 926 }"#), 500.0);
+        assert_close(tas(r#"{ "type": "mph", "value": 115.078 }"#), 100.0);
+
+        // Mach numbers default to being flown at FL350
+        assert_close(tas(r#"{ "type": "mach", "value": 0.78 }"#), 449.6);
+        assert_close(
+            tas(r#"{ "type": "mach", "value": 0.78, "altitudeFT": 35000 }"#),
+            449.6,
+        );
+        assert_close(
+            tas(r#"{ "type": "mach", "value": 0.78, "altitudeFT": 35000, "isaDeviationC": 10 }"#),
+            459.8,
+        );
+
+        assert_close(tas(r#"{ "type": "kias", "value": 120 }"#), 120.0);
+        assert_close(
+            tas(r#"{ "type": "kias", "value": 250, "altitudeFT": 10000 }"#),
+            288.7,
+        );
+    }
+
+    #[test]
+    fn speed_validate() {
+        let fields = |json| match parse(json).validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .errors()
+                .iter()
+                .map(|err| err.field.clone())
+                .collect(),
+        };
+
+        assert!(fields(r#"{ "type": "mach", "value": 0.85, "altitudeFT": 41000 }"#).is_empty());
+        assert_eq!(
+            fields(r#"{ "type": "kias", "value": 0, "altitudeFT": 90000, "isaDeviationC": -100 }"#),
+            vec!["value", "altitudeFT", "isaDeviationC"]
+        );
+    }
+
+    #[test]
+    fn parse_speed() {
+        let parse = |value: &str| value.parse::<Speed>().ok().map(|speed| speed.kind);
+
+        assert_eq!(parse("450"), Some(SpeedKind::Knots(450.0)));
+        assert_eq!(parse("450kts"), Some(SpeedKind::Knots(450.0)));
+        assert_eq!(parse(" 120 KT "), Some(SpeedKind::Knots(120.0)));
+        assert_eq!(parse("120ktas"), Some(SpeedKind::Knots(120.0)));
+        assert_eq!(parse("250 KIAS"), Some(SpeedKind::Kias(250.0)));
+        assert_eq!(parse("800km/h"), Some(SpeedKind::Kmh(800.0)));
+        assert_eq!(parse("500mph"), Some(SpeedKind::Mph(500.0)));
+        assert_eq!(parse("M0.85"), Some(SpeedKind::Mach(0.85)));
+        assert_eq!(parse("m 1.2"), Some(SpeedKind::Mach(1.2)));
+        assert_eq!(parse("fast"), None);
+        assert_eq!(parse("M"), None);
+    }
+}
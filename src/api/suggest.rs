@@ -0,0 +1,58 @@
+use super::airports::AirportDetails;
+use crate::airport_data::Airports;
+use rocket::State;
+use rocket_contrib::json::JsonValue;
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+/// The fields of each suggested airport that are returned.
+const AIRPORT_FIELDS: [&str; 6] = [
+    "icao",
+    "iataCode",
+    "name",
+    "municipality",
+    "country",
+    "type",
+];
+
+/// Suggest airports whose ICAO, IATA or GPS code, name or municipality match `q`, from best to worst.
+#[get("/suggest/airports?<q>&<limit>")]
+pub fn suggest_airports<'a>(
+    q: String,
+    limit: Option<usize>,
+    airports: State<'a, Airports>,
+) -> JsonValue {
+    let fields = AIRPORT_FIELDS
+        .iter()
+        .map(|field| field.to_string())
+        .collect::<Vec<_>>();
+
+    let results = airports
+        .suggest_airports(&q, clamp_limit(limit))
+        .into_iter()
+        .map(|arpt| AirportDetails::new(arpt, &airports).select(Some(&fields)))
+        .collect::<Vec<_>>();
+
+    json!({ "airports": results })
+}
+
+/// Suggest countries whose name or ISO code match `q`, from best to worst.
+#[get("/suggest/countries?<q>&<limit>")]
+pub fn suggest_countries<'a>(
+    q: String,
+    limit: Option<usize>,
+    airports: State<'a, Airports>,
+) -> JsonValue {
+    let results = airports.suggest_countries(&q, clamp_limit(limit));
+    json!({ "countries": results })
+}
+
+#[inline(always)]
+fn clamp_limit(limit: Option<usize>) -> usize {
+    match limit {
+        Some(0) => 1,
+        Some(limit) => limit.min(MAX_LIMIT),
+        None => DEFAULT_LIMIT,
+    }
+}
@@ -0,0 +1,54 @@
+use serde_derive::Deserialize;
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+pub enum LengthUnit {
+    #[serde(rename = "ft")]
+    Feet,
+    #[serde(rename = "m")]
+    Meters,
+}
+
+impl LengthUnit {
+    const FEET_PER_METER: f32 = 3.2808;
+
+    #[inline(always)]
+    pub fn feet_to_unit(self, feet: f32) -> f32 {
+        match self {
+            Self::Feet => feet,
+            Self::Meters => feet / Self::FEET_PER_METER,
+        }
+    }
+}
+
+impl Default for LengthUnit {
+    fn default() -> Self {
+        Self::Feet
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+pub enum DistanceUnit {
+    #[serde(rename = "nm")]
+    NauticalMiles,
+    #[serde(rename = "mi")]
+    Miles,
+    #[serde(rename = "km")]
+    Kilometers,
+}
+
+impl DistanceUnit {
+    #[inline(always)]
+    pub fn to_nm(self, value: f32) -> f32 {
+        match self {
+            Self::NauticalMiles => value,
+            Self::Miles => value / 1.15078,
+            Self::Kilometers => value / 1.852,
+        }
+    }
+}
+
+impl Default for DistanceUnit {
+    fn default() -> Self {
+        Self::NauticalMiles
+    }
+}
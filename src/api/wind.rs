@@ -0,0 +1,342 @@
+use super::error::{ErrorCode, ValidationErrors};
+use super::search_routes::{Route, Time};
+use crate::airport_data::Airport;
+use crate::geo;
+use crate::geo::geodesic::{self, DistanceOptions, PathKind};
+use crate::wind::forecast::WindForecast;
+use crate::wind::{Wind, WindField, WindsAloft};
+use rocket::State;
+use rocket_contrib::json::JsonValue;
+use serde_derive::{Deserialize, Serialize};
+
+/// Describe the wind forecast that was loaded from the data directory, if there is one.
+#[get("/winds")]
+pub fn wind_forecast<'a>(forecast: State<'a, Option<WindForecast>>) -> JsonValue {
+    json!({ "forecast": forecast.as_ref().map(WindForecast::info) })
+}
+
+/// Where the winds along a route come from.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[serde(tag = "type", content = "value")]
+pub enum WindSource {
+    /// The same wind everywhere and at every altitude.
+    Uniform(Wind),
+    /// Winds at several altitudes that are the same everywhere, which are interpolated
+    /// at the cruise altitude.
+    Aloft(WindsAloft),
+    /// The winds from the forecast file in the data directory.
+    Forecast,
+}
+
+impl WindSource {
+    const MAX_SPEED_KT: f32 = 400.0;
+
+    /// Get the winds to use, which is `None` when the forecast is selected but none was loaded.
+    pub fn field<'a>(&'a self, forecast: Option<&'a WindForecast>) -> Option<&'a dyn WindField> {
+        match self {
+            Self::Uniform(wind) => Some(wind),
+            Self::Aloft(aloft) => Some(aloft),
+            Self::Forecast => forecast.map(|forecast| forecast as &dyn WindField),
+        }
+    }
+
+    /// Check if the winds depend on the cruise altitude.
+    #[inline(always)]
+    pub fn varies_with_altitude(&self) -> bool {
+        !matches!(self, Self::Uniform(_))
+    }
+
+    pub fn validate(&self, forecast: Option<&WindForecast>) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        match self {
+            Self::Forecast if forecast.is_none() => errors.add(
+                "type",
+                ErrorCode::InvalidValue,
+                "no wind forecast has been loaded".into(),
+            ),
+            Self::Forecast => (),
+            Self::Uniform(wind) => Self::validate_wind(wind, "value", &mut errors),
+            Self::Aloft(aloft) if aloft.levels().is_empty() => errors.add(
+                "value",
+                ErrorCode::InvalidValue,
+                "at least one wind level is required".into(),
+            ),
+            Self::Aloft(aloft) => {
+                for (i, level) in aloft.levels().iter().enumerate() {
+                    Self::validate_wind(&level.wind, &format!("value[{}]", i), &mut errors);
+                }
+            }
+        }
+
+        errors.into_result()
+    }
+
+    fn validate_wind(wind: &Wind, field: &str, errors: &mut ValidationErrors) {
+        if !(0.0..=360.0).contains(&wind.direction) {
+            errors.add(
+                format!("{}.direction", field),
+                ErrorCode::InvalidValue,
+                format!(
+                    "direction must be between 0 and 360 degrees, got {}",
+                    wind.direction
+                ),
+            );
+        }
+
+        if !(0.0..=Self::MAX_SPEED_KT).contains(&wind.speed_kt) {
+            errors.add(
+                format!("{}.speedKT", field),
+                ErrorCode::InvalidValue,
+                format!(
+                    "speed must be between 0 and {} knots, got {}",
+                    Self::MAX_SPEED_KT,
+                    wind.speed_kt
+                ),
+            );
+        }
+    }
+}
+
+/// The effect of the wind on a route that's flown at cruise speed and altitude.
+#[derive(Copy, Clone, Debug, Serialize, PartialEq)]
+pub struct RouteWind {
+    /// The average ground speed in knots.
+    #[serde(rename = "groundSpeedKT")]
+    pub ground_speed_kt: f32,
+    /// The average headwind in knots, which is negative for a tailwind.
+    #[serde(rename = "headwindKT")]
+    pub headwind_kt: f32,
+    /// The average crosswind in knots from either side.
+    #[serde(rename = "crosswindKT")]
+    pub crosswind_kt: f32,
+    /// The cruise time corrected for the wind.
+    pub time: Time,
+    /// The wind at the departure airport's elevation.
+    #[serde(rename = "departureWind")]
+    pub departure_wind: Wind,
+    /// The wind at the arrival airport's elevation.
+    #[serde(rename = "arrivalWind")]
+    pub arrival_wind: Wind,
+}
+
+impl RouteWind {
+    /// The longest part of the route that's flown with the same wind and course.
+    const SEGMENT_NM: f32 = 100.0;
+
+    /// Calculate the wind along the path of a route measured with `options`, flown at `tas_kt` knots
+    /// and `altitude_ft` feet.
+    ///
+    /// Returns `None` when the wind is too strong for the aircraft to make progress along part of the route.
+    pub fn new(
+        route: &Route,
+        field: &dyn WindField,
+        tas_kt: f32,
+        altitude_ft: f32,
+        options: DistanceOptions,
+    ) -> Option<Self> {
+        let (from, to) = (route.from.position, route.to.position);
+        let points = geo::path::densify(from, to, Self::SEGMENT_NM, options.path);
+
+        let legs = points
+            .windows(2)
+            .map(|segment| geodesic::measure(segment[0], segment[1], options))
+            .collect::<Vec<_>>();
+
+        let path_nm = legs.iter().map(|leg| leg.distance_nm as f32).sum::<f32>();
+
+        let mut hours = 0.0;
+        let mut headwind_kt = 0.0;
+        let mut crosswind_kt = 0.0;
+
+        for (segment, leg) in points.windows(2).zip(legs) {
+            if path_nm <= 0.0 {
+                break;
+            }
+
+            let (start, end) = (segment[0], segment[1]);
+            let weight = leg.distance_nm as f32 / path_nm;
+            // A rhumb line keeps the course of the whole route
+            let course = match options.path {
+                PathKind::GreatCircle => leg.initial_course_deg as f32,
+                PathKind::Rhumb => route.initial_course,
+            };
+
+            let midpoint = geo::path::halfway_point(start, end, options.path);
+            let wind = field.wind_at(midpoint, altitude_ft);
+            let (headwind, crosswind) = wind.relative_to(course);
+
+            headwind_kt += headwind * weight;
+            crosswind_kt += crosswind.abs() * weight;
+
+            hours += route.distance * weight / wind.ground_speed_kt(tas_kt, course)?;
+        }
+
+        let ground_speed_kt = if hours > 0.0 {
+            route.distance / hours
+        } else {
+            tas_kt
+        };
+
+        Some(Self {
+            ground_speed_kt,
+            headwind_kt,
+            crosswind_kt,
+            time: Time::from_hours(hours),
+            departure_wind: Self::surface_wind(route.from, field),
+            arrival_wind: Self::surface_wind(route.to, field),
+        })
+    }
+
+    #[inline(always)]
+    fn surface_wind(airport: &Airport, field: &dyn WindField) -> Wind {
+        let elevation_ft = airport.elevation_ft.unwrap_or(0) as f32;
+        field.wind_at(airport.position, elevation_ft)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport_data::test_data::{get_airport, get_airports};
+    use crate::api::speed::SpeedKind;
+
+    fn source(json: &str) -> WindSource {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn route_wind() {
+        let airports = get_airports();
+
+        let route_wind_at = |from, to, source: &WindSource, tas| {
+            let route = Route::new(
+                get_airport(&airports, from),
+                get_airport(&airports, to),
+                SpeedKind::Knots(tas).into(),
+                DistanceOptions::default(),
+            );
+
+            RouteWind::new(
+                &route,
+                source.field(None).unwrap(),
+                tas,
+                35_000.0,
+                DistanceOptions::default(),
+            )
+        };
+
+        let route_wind = |from, to, source| route_wind_at(from, to, source, 450.0).unwrap();
+
+        let jet_stream = source(
+            r#"{
+                "type": "aloft",
+                "value": [
+                    { "altitudeFT": 18000, "direction": 270, "speedKT": 40 },
+                    { "altitudeFT": 39000, "direction": 270, "speedKT": 120 }
+                ]
+            }"#,
+        );
+
+        // The great circle from Narita heads northeast before turning southeast
+        let eastbound = route_wind("RJAA", "KSFO", &jet_stream);
+        assert!(eastbound.headwind_kt < -50.0);
+        assert!(eastbound.ground_speed_kt > 500.0);
+        assert_eq!(eastbound.time.to_string(), "8h08m");
+
+        let westbound = route_wind("KSFO", "RJAA", &jet_stream);
+        assert!(westbound.headwind_kt > 50.0);
+        assert!(westbound.ground_speed_kt < 400.0);
+        assert_eq!(westbound.time.to_string(), "12h40m");
+
+        let calm = route_wind(
+            "KSFO",
+            "RJAA",
+            &source(r#"{ "type": "uniform", "value": { "direction": 0, "speedKT": 0 } }"#),
+        );
+        assert_eq!(calm.time.to_string(), "9h52m");
+        assert!((calm.ground_speed_kt - 450.0).abs() < 0.5);
+
+        // Too strong for a slow aircraft to make any progress
+        let gale = source(r#"{ "type": "uniform", "value": { "direction": 40, "speedKT": 120 } }"#);
+        assert_eq!(route_wind_at("KSFO", "KSAC", &gale, 100.0), None);
+        assert!(
+            route_wind_at("KSAC", "KSFO", &gale, 100.0)
+                .unwrap()
+                .ground_speed_kt
+                > 200.0
+        );
+    }
+
+    #[test]
+    fn route_wind_along_rhumb_line() {
+        let airports = get_airports();
+        let westerly = Wind {
+            direction: 270.0,
+            speed_kt: 100.0,
+        };
+
+        let route_wind = |path| {
+            let options = DistanceOptions {
+                path,
+                ..DistanceOptions::default()
+            };
+
+            let route = Route::new(
+                get_airport(&airports, "RJAA"),
+                get_airport(&airports, "KSFO"),
+                SpeedKind::Knots(450.0).into(),
+                options,
+            );
+
+            (
+                route.initial_course,
+                RouteWind::new(&route, &westerly, 450.0, 35_000.0, options).unwrap(),
+            )
+        };
+
+        // The rhumb line keeps heading almost due east, so it gets nearly all of the tailwind
+        let (course, rhumb) = route_wind(PathKind::Rhumb);
+        let (expected_headwind, _) = westerly.relative_to(course);
+        assert!((rhumb.headwind_kt - expected_headwind).abs() < 0.01);
+
+        let (_, great_circle) = route_wind(PathKind::GreatCircle);
+        assert!(rhumb.headwind_kt < great_circle.headwind_kt);
+    }
+
+    #[test]
+    fn wind_source_validate() {
+        let fields = |json| match source(json).validate(None) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .errors()
+                .iter()
+                .map(|err| err.field.clone())
+                .collect(),
+        };
+
+        assert!(
+            fields(r#"{ "type": "uniform", "value": { "direction": 360, "speedKT": 25 } }"#)
+                .is_empty()
+        );
+
+        assert_eq!(
+            fields(r#"{ "type": "uniform", "value": { "direction": 400, "speedKT": -5 } }"#),
+            vec!["value.direction", "value.speedKT"]
+        );
+
+        assert_eq!(fields(r#"{ "type": "aloft", "value": [] }"#), vec!["value"]);
+        assert_eq!(fields(r#"{ "type": "forecast" }"#), vec!["type"]);
+
+        assert_eq!(
+            fields(
+                r#"{ "type": "aloft", "value": [
+                    { "altitudeFT": 3000, "direction": 90, "speedKT": 10 },
+                    { "altitudeFT": 0, "direction": -10, "speedKT": 10 }
+                ] }"#
+            ),
+            vec!["value[1].direction"]
+        );
+    }
+}
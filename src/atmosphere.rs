@@ -0,0 +1,163 @@
+//! The International Standard Atmosphere (ISA), up to 32 km.
+
+/// Standard sea level temperature in kelvin.
+const SEA_LEVEL_TEMP_K: f32 = 288.15;
+/// Standard sea level pressure in pascals.
+const SEA_LEVEL_PRESSURE_PA: f32 = 101_325.0;
+/// Temperature drop per meter of altitude in the troposphere.
+const TROPOSPHERE_LAPSE_RATE: f32 = 0.0065;
+/// Temperature rise per meter of altitude above 20 km.
+const STRATOSPHERE_LAPSE_RATE: f32 = -0.001;
+const TROPOPAUSE_M: f32 = 11_000.0;
+const STRATOSPHERE_M: f32 = 20_000.0;
+const CEILING_M: f32 = 32_000.0;
+
+const GRAVITY: f32 = 9.806_65;
+/// Specific gas constant of dry air in J/(kg K).
+const GAS_CONSTANT: f32 = 287.052_87;
+/// Ratio of specific heats of air.
+const GAMMA: f32 = 1.4;
+
+const METERS_PER_FOOT: f32 = 0.3048;
+const METERS_PER_SEC_PER_KNOT: f32 = 1852.0 / 3600.0;
+
+/// The conditions of the atmosphere at an altitude.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Atmosphere {
+    pub temperature_k: f32,
+    pub pressure_pa: f32,
+}
+
+impl Atmosphere {
+    /// Get the conditions at a pressure altitude in feet, with the temperature offset from
+    /// the standard temperature by `isa_deviation_c` degrees.
+    ///
+    /// Altitudes above 32 km are treated as 32 km.
+    pub fn at(altitude_ft: f32, isa_deviation_c: f32) -> Self {
+        let altitude_m = (altitude_ft * METERS_PER_FOOT).min(CEILING_M);
+        let tropopause_temp = Self::troposphere_temp(TROPOPAUSE_M);
+
+        let troposphere_pressure = |temp: f32| {
+            SEA_LEVEL_PRESSURE_PA
+                * (temp / SEA_LEVEL_TEMP_K).powf(GRAVITY / (GAS_CONSTANT * TROPOSPHERE_LAPSE_RATE))
+        };
+
+        let (std_temp, pressure) = if altitude_m <= TROPOPAUSE_M {
+            let temp = Self::troposphere_temp(altitude_m);
+            (temp, troposphere_pressure(temp))
+        } else {
+            let tropopause_pressure = troposphere_pressure(tropopause_temp);
+            let isothermal_m = altitude_m.min(STRATOSPHERE_M) - TROPOPAUSE_M;
+
+            let mut pressure = tropopause_pressure
+                * (-GRAVITY * isothermal_m / (GAS_CONSTANT * tropopause_temp)).exp();
+
+            let mut temp = tropopause_temp;
+
+            if altitude_m > STRATOSPHERE_M {
+                temp -= STRATOSPHERE_LAPSE_RATE * (altitude_m - STRATOSPHERE_M);
+                pressure *= (temp / tropopause_temp)
+                    .powf(GRAVITY / (GAS_CONSTANT * STRATOSPHERE_LAPSE_RATE));
+            }
+
+            (temp, pressure)
+        };
+
+        Self {
+            temperature_k: std_temp + isa_deviation_c,
+            pressure_pa: pressure,
+        }
+    }
+
+    #[inline(always)]
+    fn troposphere_temp(altitude_m: f32) -> f32 {
+        SEA_LEVEL_TEMP_K - TROPOSPHERE_LAPSE_RATE * altitude_m.min(TROPOPAUSE_M)
+    }
+
+    /// Get the speed of sound in knots.
+    #[inline(always)]
+    pub fn speed_of_sound_kt(&self) -> f32 {
+        (GAMMA * GAS_CONSTANT * self.temperature_k).sqrt() / METERS_PER_SEC_PER_KNOT
+    }
+
+    /// Convert a Mach number into true airspeed in knots.
+    #[inline(always)]
+    pub fn mach_to_tas(&self, mach: f32) -> f32 {
+        mach * self.speed_of_sound_kt()
+    }
+
+    /// Convert calibrated airspeed in knots into true airspeed in knots, accounting for compressibility.
+    pub fn cas_to_tas(&self, cas_kt: f32) -> f32 {
+        let sea_level = Self::at(0.0, 0.0);
+        let sea_level_sound_kt = sea_level.speed_of_sound_kt();
+
+        // Impact pressure, which is what the airspeed indicator actually measures
+        let impact_pressure = SEA_LEVEL_PRESSURE_PA
+            * ((1.0 + 0.2 * (cas_kt / sea_level_sound_kt).powi(2)).powf(3.5) - 1.0);
+
+        let mach =
+            (5.0 * ((impact_pressure / self.pressure_pa + 1.0).powf(2.0 / 7.0) - 1.0)).sqrt();
+
+        self.mach_to_tas(mach)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    #[test]
+    fn standard_atmosphere() {
+        let sea_level = Atmosphere::at(0.0, 0.0);
+        assert_close(sea_level.temperature_k, 288.15, 0.01);
+        assert_close(sea_level.pressure_pa, 101_325.0, 1.0);
+        assert_close(sea_level.speed_of_sound_kt(), 661.5, 0.1);
+
+        let fl350 = Atmosphere::at(35_000.0, 0.0);
+        assert_close(fl350.temperature_k, 218.8, 0.1);
+        assert_close(fl350.pressure_pa, 23_842.0, 10.0);
+
+        let fl450 = Atmosphere::at(45_000.0, 0.0);
+        assert_close(fl450.temperature_k, 216.65, 0.01);
+        assert_close(fl450.pressure_pa, 14_748.0, 10.0);
+
+        let high = Atmosphere::at(80_000.0, 0.0);
+        assert_close(high.temperature_k, 221.03, 0.01);
+        assert_close(high.pressure_pa, 2_761.5, 5.0);
+
+        let hot = Atmosphere::at(35_000.0, 15.0);
+        assert_close(hot.temperature_k, 233.8, 0.1);
+        assert_eq!(hot.pressure_pa, fl350.pressure_pa);
+    }
+
+    #[test]
+    fn airspeed_conversions() {
+        let sea_level = Atmosphere::at(0.0, 0.0);
+        assert_close(sea_level.cas_to_tas(250.0), 250.0, 0.1);
+
+        // 250 KIAS at 10,000 ft is about 290 KTAS
+        assert_close(Atmosphere::at(10_000.0, 0.0).cas_to_tas(250.0), 288.7, 0.5);
+
+        let fl350 = Atmosphere::at(35_000.0, 0.0);
+        assert_close(fl350.mach_to_tas(0.78), 449.6, 0.5);
+        // 280 KIAS at FL350 is about Mach 0.82
+        assert_close(fl350.cas_to_tas(280.0), 473.4, 0.5);
+        assert_close(
+            fl350.cas_to_tas(280.0) / fl350.speed_of_sound_kt(),
+            0.821,
+            0.001,
+        );
+
+        // Warmer air means a higher true airspeed for the same Mach number
+        assert!(Atmosphere::at(35_000.0, 10.0).mach_to_tas(0.78) > fl350.mach_to_tas(0.78));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::airport_data::Position;
+use crate::path::FilePath;
+use anyhow::{anyhow, Context, Result};
+use serde_derive::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+
+/// An area of the Earth's surface that positions can be tested against.
+#[derive(Debug)]
+pub enum Area {
+    BoundingBox(BoundingBox),
+    Polygons(Vec<Polygon>),
+}
+
+impl Area {
+    pub fn from_geojson(geojson: &GeoJson) -> Result<Self> {
+        let mut polygons = Vec::new();
+        geojson.collect_polygons(&mut polygons)?;
+
+        if polygons.is_empty() {
+            return Err(anyhow!("GeoJSON does not contain any polygons"));
+        }
+
+        Ok(Self::Polygons(polygons))
+    }
+
+    /// Load a GeoJSON region with the specified `name` from the regions folder in the data directory.
+    pub fn load_named(name: &str) -> Result<Self> {
+        let is_valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if !is_valid_name {
+            return Err(anyhow!(
+                "region name \"{}\" can only contain A-Z, 0-9, - and _",
+                name
+            ));
+        }
+
+        let mut path = FilePath::LocalData.validated_subdir("regions")?;
+        path.push(format!("{}.geojson", name));
+
+        let file = File::open(&path).with_context(|| format!("no region named \"{}\"", name))?;
+
+        let geojson: GeoJson = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse region \"{}\"", name))?;
+
+        Self::from_geojson(&geojson).with_context(|| format!("invalid region \"{}\"", name))
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        match self {
+            Self::BoundingBox(bbox) => bbox.contains(pos),
+            Self::Polygons(polygons) => polygons.iter().any(|poly| poly.contains(pos)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    west: f32,
+    south: f32,
+    east: f32,
+    north: f32,
+}
+
+impl BoundingBox {
+    /// Create a new bounding box from its edges in degrees.
+    ///
+    /// If `west` is greater than `east`, the box is assumed to cross the antimeridian.
+    pub fn new(west: f32, south: f32, east: f32, north: f32) -> Result<Self> {
+        let lat_range = -90.0..=90.0;
+        let lon_range = -180.0..=180.0;
+
+        if !lat_range.contains(&south) || !lat_range.contains(&north) {
+            return Err(anyhow!("bounding box latitudes must be between -90 and 90"));
+        }
+
+        if !lon_range.contains(&west) || !lon_range.contains(&east) {
+            return Err(anyhow!(
+                "bounding box longitudes must be between -180 and 180"
+            ));
+        }
+
+        if south > north {
+            return Err(anyhow!("bounding box south edge is above its north edge"));
+        }
+
+        Ok(Self {
+            west,
+            south,
+            east,
+            north,
+        })
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        let lat = pos.latitude_deg;
+        let lon = pos.longitude_deg;
+
+        if lat < self.south || lat > self.north {
+            return false;
+        }
+
+        if self.west <= self.east {
+            lon >= self.west && lon <= self.east
+        } else {
+            lon >= self.west || lon <= self.east
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Polygon {
+    exterior: Ring,
+    holes: Vec<Ring>,
+}
+
+impl Polygon {
+    fn from_rings(rings: &[Vec<Vec<f32>>]) -> Result<Self> {
+        let (exterior, holes) = match rings.split_first() {
+            Some(rings) => rings,
+            None => return Err(anyhow!("polygon has no rings")),
+        };
+
+        let exterior = Ring::new(exterior, None)?;
+        let reference_lon = exterior.points[0].0;
+
+        let holes = holes
+            .iter()
+            .map(|hole| Ring::new(hole, Some(reference_lon)))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { exterior, holes })
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        let lat = pos.latitude_deg;
+
+        // Rings can extend past the antimeridian after being unwrapped, so the position
+        // has to be checked at its equivalent longitudes as well
+        [0.0, 360.0, -360.0].iter().any(|offset| {
+            let lon = pos.longitude_deg + offset;
+
+            self.exterior.contains(lon, lat) && !self.holes.iter().any(|h| h.contains(lon, lat))
+        })
+    }
+}
+
+/// A closed ring of (longitude, latitude) points.
+///
+/// Longitudes are unwrapped so that consecutive points never differ by more than 180 degrees,
+/// which allows rings that cross the antimeridian to be tested like any other ring.
+#[derive(Debug)]
+struct Ring {
+    points: Vec<(f32, f32)>,
+    min: (f32, f32),
+    max: (f32, f32),
+}
+
+impl Ring {
+    fn new(coords: &[Vec<f32>], reference_lon: Option<f32>) -> Result<Self> {
+        let mut points: Vec<(f32, f32)> = Vec::with_capacity(coords.len() + 1);
+
+        for coord in coords {
+            let (lon, lat) = match coord.as_slice() {
+                [lon, lat, ..] => (*lon, *lat),
+                _ => return Err(anyhow!("positions must have a longitude and latitude")),
+            };
+
+            if !(-90.0..=90.0).contains(&lat) {
+                return Err(anyhow!("latitude {} is out of range", lat));
+            }
+
+            let prev_lon = points.last().map(|&(lon, _)| lon).or(reference_lon);
+
+            let lon = match prev_lon {
+                Some(prev) => lon + 360.0 * ((prev - lon) / 360.0).round(),
+                None => lon,
+            };
+
+            points.push((lon, lat));
+        }
+
+        if points.first() != points.last() {
+            if let Some(&first) = points.first() {
+                points.push(first);
+            }
+        }
+
+        // A closed triangle needs 4 points
+        if points.len() < 4 {
+            return Err(anyhow!(
+                "polygon rings must have at least 3 distinct positions"
+            ));
+        }
+
+        let mut min = points[0];
+        let mut max = points[0];
+
+        for &(lon, lat) in &points {
+            min = (min.0.min(lon), min.1.min(lat));
+            max = (max.0.max(lon), max.1.max(lat));
+        }
+
+        Ok(Self { points, min, max })
+    }
+
+    /// Test if a point is within the ring by casting a ray towards positive longitude
+    /// and counting the edges it crosses.
+    fn contains(&self, lon: f32, lat: f32) -> bool {
+        if lon < self.min.0 || lon > self.max.0 || lat < self.min.1 || lat > self.max.1 {
+            return false;
+        }
+
+        let mut inside = false;
+
+        for edge in self.points.windows(2) {
+            let (x1, y1) = edge[0];
+            let (x2, y2) = edge[1];
+
+            if (y1 > lat) != (y2 > lat) {
+                let cross_lon = x1 + (lat - y1) * (x2 - x1) / (y2 - y1);
+
+                if lon < cross_lon {
+                    inside = !inside;
+                }
+            }
+        }
+
+        inside
+    }
+}
+
+/// The subset of GeoJSON that can describe an area.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum GeoJson {
+    Polygon {
+        coordinates: Vec<Vec<Vec<f32>>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<Vec<f32>>>>,
+    },
+    GeometryCollection {
+        geometries: Vec<GeoJson>,
+    },
+    Feature {
+        geometry: Option<Box<GeoJson>>,
+    },
+    FeatureCollection {
+        features: Vec<GeoJson>,
+    },
+}
+
+impl GeoJson {
+    fn collect_polygons(&self, polygons: &mut Vec<Polygon>) -> Result<()> {
+        match self {
+            Self::Polygon { coordinates } => polygons.push(Polygon::from_rings(coordinates)?),
+            Self::MultiPolygon { coordinates } => {
+                for rings in coordinates {
+                    polygons.push(Polygon::from_rings(rings)?);
+                }
+            }
+            Self::GeometryCollection { geometries: items }
+            | Self::FeatureCollection { features: items } => {
+                for item in items {
+                    item.collect_polygons(polygons)?;
+                }
+            }
+            Self::Feature { geometry } => {
+                if let Some(geometry) = geometry {
+                    geometry.collect_polygons(polygons)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Area {
+        let geojson: GeoJson = serde_json::from_str(json).unwrap();
+        Area::from_geojson(&geojson).unwrap()
+    }
+
+    #[test]
+    fn bounding_box() {
+        let bbox = BoundingBox::new(-125.0, 32.0, -114.0, 42.0).unwrap();
+
+        assert!(bbox.contains(Position::new(37.618, -122.375)));
+        assert!(!bbox.contains(Position::new(35.764, 140.386)));
+
+        let bbox = BoundingBox::new(170.0, -25.0, -170.0, -10.0).unwrap();
+
+        assert!(bbox.contains(Position::new(-17.755, 177.443)));
+        assert!(bbox.contains(Position::new(-13.830, -172.008)));
+        assert!(!bbox.contains(Position::new(-37.008, 174.792)));
+        assert!(!bbox.contains(Position::new(-17.0, 0.0)));
+
+        assert!(BoundingBox::new(0.0, 10.0, 1.0, 5.0).is_err());
+        assert!(BoundingBox::new(0.0, -91.0, 1.0, 5.0).is_err());
+    }
+
+    #[test]
+    fn polygon_with_hole() {
+        let area = parse(
+            r#"{
+                "type": "Polygon",
+                "coordinates": [
+                    [[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]],
+                    [[4, 4], [6, 4], [6, 6], [4, 6], [4, 4]]
+                ]
+            }"#,
+        );
+
+        assert!(area.contains(Position::new(2.0, 2.0)));
+        assert!(area.contains(Position::new(9.0, 5.0)));
+        assert!(!area.contains(Position::new(5.0, 5.0)));
+        assert!(!area.contains(Position::new(11.0, 5.0)));
+    }
+
+    #[test]
+    fn polygon_across_antimeridian() {
+        // Unclosed ring around Fiji and Samoa
+        let area = parse(
+            r#"{
+                "type": "Feature",
+                "properties": { "name": "test" },
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[[170, -25], [-170, -25], [-170, -10], [170, -10]]]
+                }
+            }"#,
+        );
+
+        assert!(area.contains(Position::new(-17.755, 177.443)));
+        assert!(area.contains(Position::new(-13.830, -172.008)));
+        assert!(area.contains(Position::new(-15.0, 180.0)));
+        assert!(area.contains(Position::new(-15.0, -180.0)));
+        assert!(!area.contains(Position::new(-37.008, 174.792)));
+        assert!(!area.contains(Position::new(-17.0, 0.0)));
+    }
+
+    #[test]
+    fn multi_polygon() {
+        let area = parse(
+            r#"{
+                "type": "FeatureCollection",
+                "features": [{
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "MultiPolygon",
+                        "coordinates": [
+                            [[[0, 0], [1, 0], [1, 1], [0, 0]]],
+                            [[[20, 20, 100], [21, 20, 100], [21, 21, 100], [20, 20, 100]]]
+                        ]
+                    }
+                }]
+            }"#,
+        );
+
+        assert!(area.contains(Position::new(0.25, 0.75)));
+        assert!(area.contains(Position::new(20.25, 20.75)));
+        assert!(!area.contains(Position::new(10.0, 10.0)));
+    }
+
+    #[test]
+    fn invalid_geojson() {
+        let parse = |json| Area::from_geojson(&serde_json::from_str(json).unwrap());
+
+        assert!(parse(r#"{ "type": "Polygon", "coordinates": [[[0, 0], [1, 1]]] }"#).is_err());
+        assert!(
+            parse(r#"{ "type": "Polygon", "coordinates": [[[0, 95], [1, 1], [0, 1]]] }"#).is_err()
+        );
+        assert!(parse(r#"{ "type": "FeatureCollection", "features": [] }"#).is_err());
+        assert!(Area::load_named("../secrets").is_err());
+    }
+}
@@ -0,0 +1,1037 @@
+use crate::airport_data::Position;
+use serde_derive::Deserialize;
+use std::f64::consts::PI;
+
+/// Semi-major axis of the WGS-84 ellipsoid in meters.
+const WGS84_A: f64 = 6_378_137.0;
+/// Flattening of the WGS-84 ellipsoid.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// Semi-minor axis of the WGS-84 ellipsoid in meters.
+const WGS84_B: f64 = WGS84_A * (1.0 - WGS84_F);
+/// Square of the first eccentricity of the WGS-84 ellipsoid.
+const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+
+const METERS_PER_NM: f64 = 1852.0;
+
+/// The shape of the Earth that distances and courses are measured on.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EarthModel {
+    /// A sphere with a radius of `EARTH_RADIUS_NM`.
+    Spherical,
+    /// The WGS-84 ellipsoid, which is accurate to within millimeters.
+    Wgs84,
+}
+
+impl Default for EarthModel {
+    fn default() -> Self {
+        Self::Spherical
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum PathKind {
+    /// The shortest path between two points.
+    GreatCircle,
+    /// The path that keeps a constant true course.
+    Rhumb,
+}
+
+impl Default for PathKind {
+    fn default() -> Self {
+        Self::GreatCircle
+    }
+}
+
+/// How the distance between two points should be measured.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct DistanceOptions {
+    #[serde(default)]
+    pub model: EarthModel,
+    #[serde(default)]
+    pub path: PathKind,
+}
+
+/// The distance and courses between two points.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Leg {
+    pub distance_nm: f64,
+    /// The true course in degrees when leaving the first point.
+    pub initial_course_deg: f64,
+    /// The true course in degrees when arriving at the second point.
+    pub final_course_deg: f64,
+}
+
+/// Measure the distance and courses from one point to another.
+pub fn measure(from: Position, to: Position, options: DistanceOptions) -> Leg {
+    let from = Coord::from(from);
+    let to = Coord::from(to);
+
+    match (options.model, options.path) {
+        (EarthModel::Spherical, PathKind::GreatCircle) => spherical_great_circle(from, to),
+        (EarthModel::Spherical, PathKind::Rhumb) => spherical_rhumb(from, to),
+        (EarthModel::Wgs84, PathKind::GreatCircle) => Karney::new().inverse(from, to),
+        (EarthModel::Wgs84, PathKind::Rhumb) => ellipsoidal_rhumb(from, to),
+    }
+}
+
+/// Get the distance in nautical miles from one point to another.
+#[inline(always)]
+pub fn distance_nm(from: Position, to: Position, options: DistanceOptions) -> f32 {
+    measure(from, to, options).distance_nm as f32
+}
+
+/// A position in radians.
+#[derive(Copy, Clone, Debug)]
+struct Coord {
+    lat: f64,
+    lon: f64,
+}
+
+impl From<Position> for Coord {
+    fn from(pos: Position) -> Self {
+        Self {
+            lat: f64::from(pos.latitude_deg).to_radians(),
+            lon: f64::from(pos.longitude_deg).to_radians(),
+        }
+    }
+}
+
+#[inline(always)]
+fn earth_radius_nm() -> f64 {
+    f64::from(super::EARTH_RADIUS_NM)
+}
+
+/// Wrap an angle in radians to be within [-PI, PI].
+#[inline(always)]
+fn wrap_pi(angle: f64) -> f64 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Convert an angle in radians into a course in degrees within [0, 360).
+#[inline(always)]
+fn to_course(angle: f64) -> f64 {
+    let course = angle.to_degrees().rem_euclid(360.0);
+
+    // rem_euclid can round up to 360 for tiny negative values
+    if course >= 360.0 {
+        0.0
+    } else {
+        course
+    }
+}
+
+/// Get the course in radians of the great circle on a sphere when leaving `from`.
+fn spherical_course(from: Coord, to: Coord) -> f64 {
+    let lon = to.lon - from.lon;
+
+    let y = lon.sin() * to.lat.cos();
+    let x = from.lat.cos() * to.lat.sin() - from.lat.sin() * to.lat.cos() * lon.cos();
+
+    y.atan2(x)
+}
+
+fn spherical_great_circle(from: Coord, to: Coord) -> Leg {
+    let lat = to.lat - from.lat;
+    let lon = to.lon - from.lon;
+
+    let a = (lat / 2.0).sin().powi(2) + from.lat.cos() * to.lat.cos() * (lon / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+
+    Leg {
+        distance_nm: earth_radius_nm() * c,
+        initial_course_deg: to_course(spherical_course(from, to)),
+        final_course_deg: to_course(spherical_course(to, from) + PI),
+    }
+}
+
+fn spherical_rhumb(from: Coord, to: Coord) -> Leg {
+    let isometric_lat = |lat: f64| (PI / 4.0 + lat / 2.0).tan().ln();
+
+    let lat = to.lat - from.lat;
+    let lon = wrap_pi(to.lon - from.lon);
+    let stretched_lat = isometric_lat(to.lat) - isometric_lat(from.lat);
+
+    // The ratio between the latitude difference and its stretched Mercator equivalent,
+    // which becomes undefined on east / west courses
+    let q = if stretched_lat.abs() > 1e-12 {
+        lat / stretched_lat
+    } else {
+        from.lat.cos()
+    };
+
+    let course = to_course(lon.atan2(stretched_lat));
+
+    Leg {
+        distance_nm: (lat * lat + q * q * lon * lon).sqrt() * earth_radius_nm(),
+        initial_course_deg: course,
+        final_course_deg: course,
+    }
+}
+
+/// The order of the series in the third flattening that Karney's method is expanded to.
+const KARNEY_ORDER: usize = 6;
+
+/// Solves the inverse geodesic problem on the WGS-84 ellipsoid with the method from Karney's
+/// "Algorithms for geodesics" (2013), following GeographicLib.
+///
+/// Unlike Vincenty's formulae, it converges for every pair of points, including nearly antipodal
+/// ones, where Newton's method is started from the solution of an astroid and falls back to bisection.
+struct Karney {
+    /// Coefficients of the A3 series in the third flattening.
+    a3x: [f64; KARNEY_ORDER],
+    /// Coefficients of the C3 series in the third flattening.
+    c3x: [f64; 15],
+    /// The largest angle for which the short line approximation is accurate enough.
+    etol2: f64,
+}
+
+/// The reduced latitudes of both points, with the first point the furthest from the equator
+/// and in the southern hemisphere.
+#[derive(Copy, Clone, Debug)]
+struct ReducedLats {
+    sbet1: f64,
+    cbet1: f64,
+    dn1: f64,
+    sbet2: f64,
+    cbet2: f64,
+    dn2: f64,
+}
+
+/// The first guess for the azimuth at the first point.
+#[derive(Copy, Clone, Debug)]
+struct InverseStart {
+    salp1: f64,
+    calp1: f64,
+    /// The arc length on the auxiliary sphere when the points are close enough for the guess
+    /// to be the solution, along with the azimuth at the second point.
+    short_line: Option<(f64, f64, f64)>,
+    dnm: f64,
+}
+
+/// A geodesic that leaves the first point at a given azimuth and reaches the latitude of the second.
+#[derive(Copy, Clone, Debug)]
+struct TrialGeodesic {
+    /// How far in longitude the geodesic misses the second point by.
+    lam12: f64,
+    /// The derivative of `lam12` with respect to the azimuth at the first point.
+    dlam12: f64,
+    salp2: f64,
+    calp2: f64,
+    sig12: f64,
+    ssig1: f64,
+    csig1: f64,
+    ssig2: f64,
+    csig2: f64,
+    eps: f64,
+}
+
+impl Karney {
+    const F1: f64 = 1.0 - WGS84_F;
+    /// Square of the second eccentricity.
+    const EP2: f64 = WGS84_E2 / (Self::F1 * Self::F1);
+    /// The third flattening.
+    const N: f64 = WGS84_F / (2.0 - WGS84_F);
+
+    /// The square root of the smallest normal f64.
+    const TINY: f64 = 1.491_668_146_240_041_3e-154;
+    const TOL0: f64 = f64::EPSILON;
+    const TOL1: f64 = 200.0 * Self::TOL0;
+    /// The square root of the machine epsilon.
+    const TOL2: f64 = 1.490_116_119_384_765_6e-8;
+    const TOLB: f64 = Self::TOL0 * Self::TOL2;
+    const XTHRESH: f64 = 1000.0 * Self::TOL2;
+
+    /// Iterations of Newton's method before falling back to bisection.
+    const MAX_NEWTON_ITERATIONS: usize = 20;
+    /// Enough further iterations for bisection to reach the precision of an f64.
+    const MAX_ITERATIONS: usize = Self::MAX_NEWTON_ITERATIONS + 53 + 10;
+
+    fn new() -> Self {
+        const A3: [f64; 18] = [
+            -3.0, 128.0, -2.0, -3.0, 64.0, -1.0, -3.0, -1.0, 16.0, 3.0, -1.0, -2.0, 8.0, 1.0, -1.0,
+            2.0, 1.0, 1.0,
+        ];
+
+        const C3: [f64; 45] = [
+            3.0, 128.0, 2.0, 5.0, 128.0, -1.0, 3.0, 3.0, 64.0, -1.0, 0.0, 1.0, 8.0, -1.0, 1.0, 4.0,
+            5.0, 256.0, 1.0, 3.0, 128.0, -3.0, -2.0, 3.0, 64.0, 1.0, -3.0, 2.0, 32.0, 7.0, 512.0,
+            -10.0, 9.0, 384.0, 5.0, -9.0, 5.0, 192.0, 7.0, 512.0, -14.0, 7.0, 512.0, 21.0, 2560.0,
+        ];
+
+        let mut a3x = [0.0; KARNEY_ORDER];
+        let mut offset = 0;
+
+        // From the coefficient of the highest power of eps down
+        for (k, j) in (0..KARNEY_ORDER).rev().enumerate() {
+            let m = (KARNEY_ORDER - j - 1).min(j);
+            a3x[k] = polyval(m, &A3[offset..], Self::N) / A3[offset + m + 1];
+            offset += m + 2;
+        }
+
+        let mut c3x = [0.0; 15];
+        let mut offset = 0;
+        let mut k = 0;
+
+        for l in 1..KARNEY_ORDER {
+            for j in (l..KARNEY_ORDER).rev() {
+                let m = (KARNEY_ORDER - j - 1).min(j);
+                c3x[k] = polyval(m, &C3[offset..], Self::N) / C3[offset + m + 1];
+                offset += m + 2;
+                k += 1;
+            }
+        }
+
+        let etol2 = 0.1 * Self::TOL2
+            / (WGS84_F.abs().max(0.001) * (1.0 - WGS84_F / 2.0).min(1.0) / 2.0).sqrt();
+
+        Self { a3x, c3x, etol2 }
+    }
+
+    fn inverse(&self, from: Coord, to: Coord) -> Leg {
+        let (lat1, lon1) = (from.lat.to_degrees(), from.lon.to_degrees());
+        let (lat2, lon2) = (to.lat.to_degrees(), to.lon.to_degrees());
+
+        // Make the longitude difference positive, which is undone for the azimuths at the end
+        let lon12 = (lon2 - lon1 + 180.0).rem_euclid(360.0) - 180.0;
+        let mut lonsign = if lon12 >= 0.0 { 1.0 } else { -1.0 };
+        let lon12 = lon12 * lonsign;
+        let lon12s = 180.0 - lon12;
+        let lam12 = lon12.to_radians();
+
+        let (slam12, clam12) = if lon12 > 90.0 {
+            let (slam12, clam12) = lon12s.to_radians().sin_cos();
+            (slam12, -clam12)
+        } else {
+            lon12.to_radians().sin_cos()
+        };
+
+        // Make the first point the one furthest from the equator, and put it in the southern hemisphere
+        let swapp = if lat1.abs() < lat2.abs() { -1.0 } else { 1.0 };
+
+        let (lat1, lat2) = if swapp < 0.0 {
+            lonsign *= -1.0;
+            (lat2, lat1)
+        } else {
+            (lat1, lat2)
+        };
+
+        let latsign = if lat1 < 0.0 { 1.0 } else { -1.0 };
+        let (lat1, lat2) = (lat1 * latsign, lat2 * latsign);
+
+        let reduced = |lat: f64| {
+            let (sbet, cbet) = lat.to_radians().sin_cos();
+            let (sbet, cbet) = norm(sbet * Self::F1, cbet);
+            (sbet, cbet.max(Self::TINY))
+        };
+
+        let (sbet1, cbet1) = reduced(lat1);
+        let (mut sbet2, mut cbet2) = reduced(lat2);
+
+        // Keep points that are the same distance from the equator exactly symmetric
+        if cbet1 < -sbet1 {
+            if cbet2 == cbet1 {
+                sbet2 = sbet1.copysign(sbet2);
+            }
+        } else if sbet2.abs() == -sbet1 {
+            cbet2 = cbet1;
+        }
+
+        let lats = ReducedLats {
+            sbet1,
+            cbet1,
+            dn1: (1.0 + Self::EP2 * sbet1 * sbet1).sqrt(),
+            sbet2,
+            cbet2,
+            dn2: (1.0 + Self::EP2 * sbet2 * sbet2).sqrt(),
+        };
+
+        let (s12, mut salp1, mut calp1, mut salp2, mut calp2) = self
+            .meridian(&lats, lat1, slam12, clam12)
+            .or_else(|| Self::equator(&lats, lam12, lon12s))
+            .unwrap_or_else(|| self.general(&lats, lam12, slam12, clam12));
+
+        if swapp < 0.0 {
+            std::mem::swap(&mut salp1, &mut salp2);
+            std::mem::swap(&mut calp1, &mut calp2);
+        }
+
+        salp1 *= swapp * lonsign;
+        calp1 *= swapp * latsign;
+        salp2 *= swapp * lonsign;
+        calp2 *= swapp * latsign;
+
+        Leg {
+            distance_nm: s12 / METERS_PER_NM,
+            initial_course_deg: to_course(salp1.atan2(calp1)),
+            final_course_deg: to_course(salp2.atan2(calp2)),
+        }
+    }
+
+    /// Solve geodesics that follow a meridian, which is the shortest path unless it's
+    /// longer than going around the ellipsoid the other way.
+    ///
+    /// Returns the distance in meters and the sines and cosines of both azimuths.
+    fn meridian(
+        &self,
+        lats: &ReducedLats,
+        lat1: f64,
+        slam12: f64,
+        clam12: f64,
+    ) -> Option<(f64, f64, f64, f64, f64)> {
+        if lat1 != -90.0 && slam12 != 0.0 {
+            return None;
+        }
+
+        let (salp1, calp1) = (slam12, clam12);
+        let (salp2, calp2) = (0.0, 1.0);
+
+        let sig1 = (lats.sbet1, calp1 * lats.cbet1);
+        let sig2 = (lats.sbet2, calp2 * lats.cbet2);
+        let sig12 = (sig1.1 * sig2.0 - sig1.0 * sig2.1)
+            .max(0.0)
+            .atan2(sig1.1 * sig2.1 + sig1.0 * sig2.0);
+
+        let (s12, m12) = lengths(Self::N, sig12, sig1, sig2, lats);
+
+        if sig12 >= 1.0 && m12 < 0.0 {
+            return None;
+        }
+
+        let coincident =
+            sig12 < 3.0 * Self::TINY || (sig12 < Self::TOL0 && (s12 < 0.0 || m12 < 0.0));
+        let s12 = if coincident { 0.0 } else { s12 * WGS84_B };
+
+        Some((s12, salp1, calp1, salp2, calp2))
+    }
+
+    /// Solve geodesics that follow the equator, which is the shortest path unless the points
+    /// are close enough to being antipodal for a path over the poles to be shorter.
+    fn equator(lats: &ReducedLats, lam12: f64, lon12s: f64) -> Option<(f64, f64, f64, f64, f64)> {
+        if lats.sbet1 != 0.0 || lon12s < WGS84_F * 180.0 {
+            return None;
+        }
+
+        Some((WGS84_A * lam12, 1.0, 0.0, 1.0, 0.0))
+    }
+
+    /// Solve every other geodesic by finding the azimuth at the first point with Newton's method.
+    fn general(
+        &self,
+        lats: &ReducedLats,
+        lam12: f64,
+        slam12: f64,
+        clam12: f64,
+    ) -> (f64, f64, f64, f64, f64) {
+        let start = self.inverse_start(lats, lam12, slam12, clam12);
+
+        if let Some((sig12, salp2, calp2)) = start.short_line {
+            let s12 = sig12 * WGS84_B * start.dnm;
+            return (s12, start.salp1, start.calp1, salp2, calp2);
+        }
+
+        let (mut salp1, mut calp1) = (start.salp1, start.calp1);
+
+        // The azimuths that are known to be too far east and west
+        let (mut salp1a, mut calp1a) = (Self::TINY, 1.0);
+        let (mut salp1b, mut calp1b) = (Self::TINY, -1.0);
+
+        let mut tripn = false;
+        let mut tripb = false;
+        let mut iterations = 0;
+
+        let trial = loop {
+            let trial = self.lambda12(lats, salp1, calp1, slam12, clam12);
+            let v = trial.lam12;
+            let tolerance = if tripn { 8.0 } else { 1.0 } * Self::TOL0;
+
+            if tripb || v.is_nan() || v.abs() < tolerance || iterations == Self::MAX_ITERATIONS {
+                break trial;
+            }
+
+            // Narrow down the bracket of azimuths that contains the solution
+            if v > 0.0
+                && (iterations > Self::MAX_NEWTON_ITERATIONS || calp1 / salp1 > calp1b / salp1b)
+            {
+                salp1b = salp1;
+                calp1b = calp1;
+            } else if v < 0.0
+                && (iterations > Self::MAX_NEWTON_ITERATIONS || calp1 / salp1 < calp1a / salp1a)
+            {
+                salp1a = salp1;
+                calp1a = calp1;
+            }
+
+            iterations += 1;
+
+            if iterations < Self::MAX_NEWTON_ITERATIONS && trial.dlam12 > 0.0 {
+                let dalp1 = -v / trial.dlam12;
+                let (sdalp1, cdalp1) = dalp1.sin_cos();
+                let nsalp1 = salp1 * cdalp1 + calp1 * sdalp1;
+
+                if nsalp1 > 0.0 && dalp1.abs() < PI {
+                    let (s, c) = norm(nsalp1, calp1 * cdalp1 - salp1 * sdalp1);
+                    salp1 = s;
+                    calp1 = c;
+                    tripn = v.abs() <= 16.0 * Self::TOL0;
+                    continue;
+                }
+            }
+
+            // Bisect when Newton's method overshoots
+            let (s, c) = norm((salp1a + salp1b) / 2.0, (calp1a + calp1b) / 2.0);
+            salp1 = s;
+            calp1 = c;
+            tripn = false;
+            tripb = (salp1a - salp1).abs() + (calp1a - calp1) < Self::TOLB
+                || (salp1 - salp1b).abs() + (calp1 - calp1b) < Self::TOLB;
+        };
+
+        let (s12, _) = lengths(
+            trial.eps,
+            trial.sig12,
+            (trial.ssig1, trial.csig1),
+            (trial.ssig2, trial.csig2),
+            lats,
+        );
+
+        (s12 * WGS84_B, salp1, calp1, trial.salp2, trial.calp2)
+    }
+
+    /// Guess the azimuth at the first point, which solves the problem outright for short lines.
+    ///
+    /// Nearly antipodal points are guessed from the solution of an astroid, since the
+    /// spherical guess is too far off for Newton's method to converge there.
+    fn inverse_start(
+        &self,
+        lats: &ReducedLats,
+        lam12: f64,
+        slam12: f64,
+        clam12: f64,
+    ) -> InverseStart {
+        let ReducedLats {
+            sbet1,
+            cbet1,
+            sbet2,
+            cbet2,
+            ..
+        } = *lats;
+
+        let sbet12 = sbet2 * cbet1 - cbet2 * sbet1;
+        let cbet12 = cbet2 * cbet1 + sbet2 * sbet1;
+        let sbet12a = sbet2 * cbet1 + cbet2 * sbet1;
+        let is_short = cbet12 >= 0.0 && sbet12 < 0.5 && cbet2 * lam12 < 0.5;
+
+        let (somg12, comg12, dnm) = if is_short {
+            let sbetm2 = (sbet1 + sbet2).powi(2);
+            let sbetm2 = sbetm2 / (sbetm2 + (cbet1 + cbet2).powi(2));
+            let dnm = (1.0 + Self::EP2 * sbetm2).sqrt();
+            let (somg12, comg12) = (lam12 / (Self::F1 * dnm)).sin_cos();
+
+            (somg12, comg12, dnm)
+        } else {
+            (slam12, clam12, 1.0)
+        };
+
+        let mut salp1 = cbet2 * somg12;
+        let mut calp1 = if comg12 >= 0.0 {
+            sbet12 + cbet2 * sbet1 * somg12 * somg12 / (1.0 + comg12)
+        } else {
+            sbet12a - cbet2 * sbet1 * somg12 * somg12 / (1.0 - comg12)
+        };
+
+        let ssig12 = salp1.hypot(calp1);
+        let csig12 = sbet1 * sbet2 + cbet1 * cbet2 * comg12;
+        let mut short_line = None;
+
+        if is_short && ssig12 < self.etol2 {
+            let calp2 = sbet12
+                - cbet1
+                    * sbet2
+                    * if comg12 >= 0.0 {
+                        somg12 * somg12 / (1.0 + comg12)
+                    } else {
+                        1.0 - comg12
+                    };
+
+            let (salp2, calp2) = norm(cbet1 * somg12, calp2);
+            short_line = Some((ssig12.atan2(csig12), salp2, calp2));
+        } else if Self::N.abs() < 0.1
+            && csig12 < 0.0
+            && ssig12 < 6.0 * Self::N.abs() * PI * cbet1 * cbet1
+        {
+            // Scale the longitude difference and latitude of the second point so that the
+            // point antipodal to the first is at the origin
+            let lam12x = (-slam12).atan2(-clam12);
+            let k2 = sbet1 * sbet1 * Self::EP2;
+            let eps = k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2);
+            let lamscale = WGS84_F * cbet1 * self.a3f(eps) * PI;
+            let betscale = lamscale * cbet1;
+
+            let x = lam12x / lamscale;
+            let y = sbet12a / betscale;
+
+            if y > -Self::TOL1 && x > -1.0 - Self::XTHRESH {
+                salp1 = (-x).min(1.0);
+                calp1 = -(1.0 - salp1 * salp1).sqrt();
+            } else {
+                let k = astroid(x, y);
+                let omg12a = lamscale * (-x * k / (1.0 + k));
+                let (somg12, comg12) = omg12a.sin_cos();
+                let comg12 = -comg12;
+
+                salp1 = cbet2 * somg12;
+                calp1 = sbet12a - cbet2 * sbet1 * somg12 * somg12 / (1.0 - comg12);
+            }
+        }
+
+        let (salp1, calp1) = if salp1 > 0.0 {
+            norm(salp1, calp1)
+        } else {
+            (1.0, 0.0)
+        };
+
+        InverseStart {
+            salp1,
+            calp1,
+            short_line,
+            dnm,
+        }
+    }
+
+    /// Follow the geodesic that leaves the first point at an azimuth to the latitude of the second.
+    fn lambda12(
+        &self,
+        lats: &ReducedLats,
+        salp1: f64,
+        calp1: f64,
+        slam120: f64,
+        clam120: f64,
+    ) -> TrialGeodesic {
+        let ReducedLats {
+            sbet1,
+            cbet1,
+            dn1,
+            sbet2,
+            cbet2,
+            ..
+        } = *lats;
+
+        let calp1 = if sbet1 == 0.0 && calp1 == 0.0 {
+            -Self::TINY
+        } else {
+            calp1
+        };
+
+        // The azimuth where the geodesic crosses the equator
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+
+        let somg1 = salp0 * sbet1;
+        let comg1 = calp1 * cbet1;
+        let (ssig1, csig1) = norm(sbet1, comg1);
+
+        let salp2 = if cbet2 != cbet1 { salp0 / cbet2 } else { salp1 };
+
+        let calp2 = if cbet2 != cbet1 || sbet2.abs() != -sbet1 {
+            let diff = if cbet1 < -sbet1 {
+                (cbet2 - cbet1) * (cbet1 + cbet2)
+            } else {
+                (sbet1 - sbet2) * (sbet1 + sbet2)
+            };
+
+            ((calp1 * cbet1).powi(2) + diff).sqrt() / cbet2
+        } else {
+            calp1.abs()
+        };
+
+        let somg2 = salp0 * sbet2;
+        let comg2 = calp2 * cbet2;
+        let (ssig2, csig2) = norm(sbet2, comg2);
+
+        let sig12 = (csig1 * ssig2 - ssig1 * csig2)
+            .max(0.0)
+            .atan2(csig1 * csig2 + ssig1 * ssig2);
+
+        let somg12 = (comg1 * somg2 - somg1 * comg2).max(0.0);
+        let comg12 = comg1 * comg2 + somg1 * somg2;
+        let eta = (somg12 * clam120 - comg12 * slam120).atan2(comg12 * clam120 + somg12 * slam120);
+
+        let k2 = calp0 * calp0 * Self::EP2;
+        let eps = k2 / (2.0 * (1.0 + (1.0 + k2).sqrt()) + k2);
+        let c3a = self.c3f(eps);
+        let b312 = sin_series(ssig2, csig2, &c3a) - sin_series(ssig1, csig1, &c3a);
+        let domg12 = -WGS84_F * self.a3f(eps) * salp0 * (sig12 + b312);
+
+        let dlam12 = if calp2 == 0.0 {
+            -2.0 * Self::F1 * dn1 / sbet1
+        } else {
+            let (_, m12) = lengths(eps, sig12, (ssig1, csig1), (ssig2, csig2), lats);
+            m12 * Self::F1 / (calp2 * cbet2)
+        };
+
+        TrialGeodesic {
+            lam12: eta + domg12,
+            dlam12,
+            salp2,
+            calp2,
+            sig12,
+            ssig1,
+            csig1,
+            ssig2,
+            csig2,
+            eps,
+        }
+    }
+
+    #[inline(always)]
+    fn a3f(&self, eps: f64) -> f64 {
+        polyval(KARNEY_ORDER - 1, &self.a3x, eps)
+    }
+
+    fn c3f(&self, eps: f64) -> [f64; KARNEY_ORDER] {
+        let mut c = [0.0; KARNEY_ORDER];
+        let mut mult = 1.0;
+        let mut offset = 0;
+
+        for (l, c) in c.iter_mut().enumerate().skip(1) {
+            let m = KARNEY_ORDER - l - 1;
+            mult *= eps;
+            *c = mult * polyval(m, &self.c3x[offset..], eps);
+            offset += m + 1;
+        }
+
+        c
+    }
+}
+
+/// Get the distance and reduced length on the auxiliary sphere between two points of a geodesic,
+/// given the sine and cosine of their arc lengths from where the geodesic crosses the equator.
+fn lengths(
+    eps: f64,
+    sig12: f64,
+    (ssig1, csig1): (f64, f64),
+    (ssig2, csig2): (f64, f64),
+    lats: &ReducedLats,
+) -> (f64, f64) {
+    const A1: [f64; 5] = [1.0, 4.0, 64.0, 0.0, 256.0];
+    const A2: [f64; 5] = [-11.0, -28.0, -192.0, 0.0, 256.0];
+
+    const C1: [f64; 18] = [
+        -1.0, 6.0, -16.0, 32.0, -9.0, 64.0, -128.0, 2048.0, 9.0, -16.0, 768.0, 3.0, -5.0, 512.0,
+        -7.0, 1280.0, -7.0, 2048.0,
+    ];
+
+    const C2: [f64; 18] = [
+        1.0, 2.0, 16.0, 32.0, 35.0, 64.0, 384.0, 2048.0, 15.0, 80.0, 768.0, 7.0, 35.0, 512.0, 63.0,
+        1280.0, 77.0, 2048.0,
+    ];
+
+    let eps2 = eps * eps;
+    let m = KARNEY_ORDER / 2;
+    let a1 = (polyval(m, &A1, eps2) / A1[m + 1] + eps) / (1.0 - eps);
+    let a2 = (polyval(m, &A2, eps2) / A2[m + 1] - eps) / (1.0 + eps);
+    let c1a = fourier_coefficients(&C1, eps);
+    let c2a = fourier_coefficients(&C2, eps);
+
+    let m0x = a1 - a2;
+    let (a1, a2) = (1.0 + a1, 1.0 + a2);
+
+    let b1 = sin_series(ssig2, csig2, &c1a) - sin_series(ssig1, csig1, &c1a);
+    let b2 = sin_series(ssig2, csig2, &c2a) - sin_series(ssig1, csig1, &c2a);
+    let j12 = m0x * sig12 + (a1 * b1 - a2 * b2);
+
+    let s12 = a1 * (sig12 + b1);
+    let m12 = lats.dn2 * (csig1 * ssig2) - lats.dn1 * (ssig1 * csig2) - csig1 * csig2 * j12;
+
+    (s12, m12)
+}
+
+/// Evaluate the coefficients of a Fourier series that are polynomials in `eps`, given as
+/// their coefficients in `eps` squared from the highest power down, each followed by a divisor.
+fn fourier_coefficients(coeff: &[f64], eps: f64) -> [f64; KARNEY_ORDER + 1] {
+    let mut c = [0.0; KARNEY_ORDER + 1];
+    let eps2 = eps * eps;
+    let mut d = eps;
+    let mut offset = 0;
+
+    for (l, c) in c.iter_mut().enumerate().skip(1) {
+        let m = (KARNEY_ORDER - l) / 2;
+        *c = d * polyval(m, &coeff[offset..], eps2) / coeff[offset + m + 1];
+        offset += m + 2;
+        d *= eps;
+    }
+
+    c
+}
+
+/// Evaluate the sum of `c[l] * sin(2 * l * x)` for every `l` from 1 with Clenshaw summation.
+fn sin_series(sinx: f64, cosx: f64, c: &[f64]) -> f64 {
+    let mut k = c.len();
+    let mut n = k - 1;
+    let ar = 2.0 * (cosx - sinx) * (cosx + sinx);
+
+    let mut y0 = if n % 2 == 1 {
+        k -= 1;
+        c[k]
+    } else {
+        0.0
+    };
+
+    let mut y1 = 0.0;
+    n /= 2;
+
+    while n > 0 {
+        n -= 1;
+        k -= 1;
+        y1 = ar * y0 - y1 + c[k];
+        k -= 1;
+        y0 = ar * y1 - y0 + c[k];
+    }
+
+    2.0 * sinx * cosx * y0
+}
+
+/// Solve the astroid equation for the positive root `k`, which estimates the azimuth
+/// of geodesics between nearly antipodal points.
+fn astroid(x: f64, y: f64) -> f64 {
+    let p = x * x;
+    let q = y * y;
+    let r = (p + q - 1.0) / 6.0;
+
+    if q == 0.0 && r <= 0.0 {
+        return 0.0;
+    }
+
+    let s = p * q / 4.0;
+    let r2 = r * r;
+    let r3 = r * r2;
+    let disc = s * (s + 2.0 * r3);
+    let mut u = r;
+
+    if disc >= 0.0 {
+        let t3 = s + r3;
+        let t3 = t3 + disc.sqrt().copysign(t3);
+        let t = t3.cbrt();
+        u += t + if t != 0.0 { r2 / t } else { 0.0 };
+    } else {
+        let angle = (-disc).sqrt().atan2(-(s + r3));
+        u += 2.0 * r * (angle / 3.0).cos();
+    }
+
+    let v = (u * u + q).sqrt();
+    let uv = if u < 0.0 { q / (v - u) } else { u + v };
+    let w = (uv - q) / (2.0 * v);
+
+    uv / ((uv + w * w).sqrt() + w)
+}
+
+/// Evaluate the polynomial of degree `degree` with coefficients `p` from the highest power down.
+#[inline(always)]
+fn polyval(degree: usize, p: &[f64], x: f64) -> f64 {
+    p[..=degree].iter().fold(0.0, |y, &c| y * x + c)
+}
+
+/// Scale a sine and cosine so that they're on the unit circle.
+#[inline(always)]
+fn norm(sin: f64, cos: f64) -> (f64, f64) {
+    let r = sin.hypot(cos);
+    (sin / r, cos / r)
+}
+
+fn ellipsoidal_rhumb(from: Coord, to: Coord) -> Leg {
+    let e = WGS84_E2.sqrt();
+
+    let isometric_lat = |lat: f64| {
+        let sin_lat = lat.sin();
+        sin_lat.atanh() - e * (e * sin_lat).atanh()
+    };
+
+    let lon = wrap_pi(to.lon - from.lon);
+    let stretched_lat = isometric_lat(to.lat) - isometric_lat(from.lat);
+    let course = lon.atan2(stretched_lat);
+
+    let distance = if (to.lat - from.lat).abs() > 1e-12 {
+        (meridian_arc(to.lat) - meridian_arc(from.lat)) / course.cos()
+    } else {
+        // The radius of the parallel that the path follows
+        let sin_lat = from.lat.sin();
+        let radius = WGS84_A * from.lat.cos() / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+
+        radius * lon.abs()
+    };
+
+    let course = to_course(course);
+
+    Leg {
+        distance_nm: distance / METERS_PER_NM,
+        initial_course_deg: course,
+        final_course_deg: course,
+    }
+}
+
+/// Get the distance in meters along a meridian from the equator to `lat` on the WGS-84 ellipsoid.
+fn meridian_arc(lat: f64) -> f64 {
+    let e2 = WGS84_E2;
+    let e4 = e2 * e2;
+    let e6 = e4 * e2;
+
+    WGS84_A
+        * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
+            - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * lat).sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure_with(model: EarthModel, path: PathKind, from: (f64, f64), to: (f64, f64)) -> Leg {
+        let pos = |(lat, lon): (f64, f64)| Position::new(lat as f32, lon as f32);
+        measure(pos(from), pos(to), DistanceOptions { model, path })
+    }
+
+    fn dms(deg: f64, min: f64, sec: f64) -> f64 {
+        deg.signum() * (deg.abs() + min / 60.0 + sec / 3600.0)
+    }
+
+    fn assert_close(value: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    #[test]
+    fn wgs84_great_circle() {
+        // Flinders Peak to Buninyong, from Vincenty's 1975 paper
+        let leg = measure_with(
+            EarthModel::Wgs84,
+            PathKind::GreatCircle,
+            (dms(-37.0, 57.0, 3.7203), dms(144.0, 25.0, 29.5244)),
+            (dms(-37.0, 39.0, 10.1561), dms(143.0, 55.0, 35.3839)),
+        );
+
+        // Positions are stored as f32, which limits their precision to a few meters
+        assert_close(leg.distance_nm * METERS_PER_NM, 54_972.271, 5.0);
+        assert_close(leg.initial_course_deg, dms(306.0, 52.0, 5.37), 0.01);
+        assert_close(leg.final_course_deg, dms(307.0, 10.0, 25.07), 0.01);
+
+        // JFK to LHR, from the GeographicLib documentation
+        let leg = measure_with(
+            EarthModel::Wgs84,
+            PathKind::GreatCircle,
+            (40.6, -73.8),
+            (51.6, -0.5),
+        );
+
+        assert_close(leg.distance_nm * METERS_PER_NM, 5_551_759.400, 2.0);
+        assert_close(leg.initial_course_deg, 51.198_883, 0.001);
+        assert_close(leg.final_course_deg, 107.821_777, 0.001);
+    }
+
+    #[test]
+    fn wgs84_edge_cases() {
+        let same = measure_with(
+            EarthModel::Wgs84,
+            PathKind::GreatCircle,
+            (10.0, 20.0),
+            (10.0, 20.0),
+        );
+        assert_eq!(same.distance_nm, 0.0);
+
+        // Nearly antipodal points, from Karney's "Algorithms for geodesics"
+        let antipodal = measure_with(
+            EarthModel::Wgs84,
+            PathKind::GreatCircle,
+            (-30.0, 0.0),
+            (29.9, 179.8),
+        );
+        assert_close(antipodal.distance_nm * METERS_PER_NM, 19_989_832.828, 2.0);
+        assert_close(antipodal.initial_course_deg, 161.890_524_736, 0.001);
+        assert_close(antipodal.final_course_deg, 18.090_737_246, 0.001);
+
+        // Exactly antipodal points on the equator are joined over the poles
+        let antipodal = measure_with(
+            EarthModel::Wgs84,
+            PathKind::GreatCircle,
+            (0.0, 0.0),
+            (0.0, 180.0),
+        );
+        assert_close(antipodal.distance_nm * METERS_PER_NM, 20_003_931.459, 1.0);
+
+        let equator = measure_with(
+            EarthModel::Wgs84,
+            PathKind::GreatCircle,
+            (0.0, 0.0),
+            (0.0, 1.0),
+        );
+        assert_close(equator.distance_nm * METERS_PER_NM, 111_319.491, 1.0);
+    }
+
+    #[test]
+    fn spherical_great_circles() {
+        let ksfo = Position::new(37.618, -122.375);
+        let rjaa = Position::new(35.764, 140.386);
+        let leg = measure(ksfo, rjaa, DistanceOptions::default());
+
+        assert_close(leg.distance_nm, 4442.0, 1.0);
+        assert_close(leg.initial_course_deg, 303.1, 0.1);
+        assert_close(leg.final_course_deg, 234.8, 0.1);
+
+        let north = Position::new(10.0, 20.0);
+        let south = Position::new(-10.0, 20.0);
+
+        let leg = measure(south, north, DistanceOptions::default());
+        assert_close(leg.initial_course_deg, 0.0, 0.1);
+
+        let leg = measure(north, south, DistanceOptions::default());
+        assert_close(leg.initial_course_deg, 180.0, 0.1);
+        assert_close(leg.final_course_deg, 180.0, 0.1);
+    }
+
+    #[test]
+    fn rhumb_lines() {
+        // Plymouth to Boston, from Chris Veness' spherical geodesy reference (5198 km, 260°07′38″)
+        let leg = measure_with(
+            EarthModel::Spherical,
+            PathKind::Rhumb,
+            (dms(50.0, 21.0, 59.0), -dms(4.0, 8.0, 2.0)),
+            (dms(42.0, 21.0, 4.0), -dms(71.0, 2.0, 27.0)),
+        );
+
+        assert_close(leg.distance_nm * METERS_PER_NM / 1000.0, 5198.0, 1.0);
+        assert_close(leg.initial_course_deg, dms(260.0, 7.0, 38.0), 0.01);
+        assert_eq!(leg.initial_course_deg, leg.final_course_deg);
+
+        // JFK to LHR, from the GeographicLib RhumbSolve documentation
+        let leg = measure_with(
+            EarthModel::Wgs84,
+            PathKind::Rhumb,
+            (40.6, -73.8),
+            (51.6, -0.5),
+        );
+
+        assert_close(leg.distance_nm * METERS_PER_NM, 5_771_083.383, 5.0);
+        assert_close(leg.initial_course_deg, 77.768_390, 0.001);
+
+        // Due east along a parallel
+        let leg = measure_with(EarthModel::Wgs84, PathKind::Rhumb, (0.0, 0.0), (0.0, 1.0));
+        assert_close(leg.distance_nm * METERS_PER_NM, 111_319.491, 1.0);
+        assert_close(leg.initial_course_deg, 90.0, 1e-9);
+
+        // Rhumb lines take the short way across the antimeridian
+        let leg = measure_with(
+            EarthModel::Spherical,
+            PathKind::Rhumb,
+            (0.0, 179.0),
+            (0.0, -179.0),
+        );
+        assert_close(leg.distance_nm, 120.0, 0.5);
+        assert_close(leg.initial_course_deg, 90.0, 1e-9);
+    }
+}
@@ -0,0 +1,46 @@
+pub mod area;
+pub mod geodesic;
+pub mod path;
+
+/// Radius of Earth in nautical miles
+pub const EARTH_RADIUS_NM: f32 = 3440.0;
+
+/// Wrap a longitude in degrees to be within [-180, 180).
+#[inline(always)]
+pub fn normalize_longitude(lon: f32) -> f32 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Wrap a course in degrees to be within [0, 360).
+#[inline(always)]
+pub fn normalize_course(course: f32) -> f32 {
+    let course = course.rem_euclid(360.0);
+
+    // rem_euclid can round up to 360 for tiny negative values
+    if course >= 360.0 {
+        0.0
+    } else {
+        course
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 0.1,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    #[test]
+    fn normalize_courses() {
+        assert_close(normalize_course(-90.0), 270.0);
+        assert_close(normalize_course(360.0), 0.0);
+        assert_close(normalize_course(725.0), 5.0);
+    }
+}
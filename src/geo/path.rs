@@ -0,0 +1,360 @@
+use super::geodesic::{self, DistanceOptions, EarthModel, PathKind};
+use super::normalize_longitude;
+use crate::airport_data::Position;
+use std::f32::consts::FRAC_PI_4;
+
+/// A point on the unit sphere.
+type Vector = [f32; 3];
+
+/// The most points that a densified path will contain.
+const MAX_PATH_POINTS: usize = 1000;
+
+/// Get the points along a path of `kind` from one point to another, spaced at most
+/// `spacing_nm` nautical miles apart. Both end points are included.
+pub fn densify(from: Position, to: Position, spacing_nm: f32, kind: PathKind) -> Vec<Position> {
+    let options = DistanceOptions {
+        model: EarthModel::Spherical,
+        path: kind,
+    };
+
+    let distance = geodesic::distance_nm(from, to, options);
+
+    let segments = if spacing_nm > 0.0 {
+        ((distance / spacing_nm).ceil() as usize).max(1)
+    } else {
+        1
+    };
+
+    let segments = segments.min(MAX_PATH_POINTS - 1);
+
+    (0..=segments)
+        .map(|i| point_along(from, to, i as f32 / segments as f32, kind))
+        .collect()
+}
+
+/// Get the point that is `fraction` of the way along a path of `kind` from one point to another.
+#[inline(always)]
+pub fn point_along(from: Position, to: Position, fraction: f32, kind: PathKind) -> Position {
+    match kind {
+        PathKind::GreatCircle => intermediate_point(from, to, fraction),
+        PathKind::Rhumb => intermediate_rhumb_point(from, to, fraction),
+    }
+}
+
+/// Get the point halfway along a path of `kind` from one point to another.
+#[inline(always)]
+pub fn halfway_point(from: Position, to: Position, kind: PathKind) -> Position {
+    match kind {
+        PathKind::GreatCircle => intermediate_point(from, to, 0.5),
+        PathKind::Rhumb => intermediate_rhumb_point(from, to, 0.5),
+    }
+}
+
+/// Get the point that is `fraction` of the way along the great circle from one point to another.
+pub fn intermediate_point(from: Position, to: Position, fraction: f32) -> Position {
+    if fraction <= 0.0 {
+        return from;
+    } else if fraction >= 1.0 {
+        return to;
+    }
+
+    let from_vec = to_vector(from);
+    let to_vec = to_vector(to);
+    let normal = cross(from_vec, to_vec);
+    let angle = dot(normal, normal).sqrt().atan2(dot(from_vec, to_vec));
+
+    // The path between identical or opposite points is either empty or undefined
+    if angle.sin().abs() < 1e-6 {
+        return from;
+    }
+
+    let a = ((1.0 - fraction) * angle).sin() / angle.sin();
+    let b = (fraction * angle).sin() / angle.sin();
+
+    to_position([
+        a * from_vec[0] + b * to_vec[0],
+        a * from_vec[1] + b * to_vec[1],
+        a * from_vec[2] + b * to_vec[2],
+    ])
+}
+
+/// Get the point that is `fraction` of the way along the rhumb line from one point to another.
+///
+/// The latitude changes evenly along a rhumb line, while the longitude changes evenly with
+/// the Mercator projection of the latitude. The rhumb line takes the short way around the world.
+pub fn intermediate_rhumb_point(from: Position, to: Position, fraction: f32) -> Position {
+    if fraction <= 0.0 {
+        return from;
+    } else if fraction >= 1.0 {
+        return to;
+    }
+
+    let isometric_lat = |lat_deg: f32| (FRAC_PI_4 + lat_deg.to_radians() / 2.0).tan().ln();
+
+    let lat = from.latitude_deg + fraction * (to.latitude_deg - from.latitude_deg);
+    let lon_diff = normalize_longitude(to.longitude_deg - from.longitude_deg);
+    let stretched_lat = isometric_lat(to.latitude_deg) - isometric_lat(from.latitude_deg);
+
+    // Paths along a parallel don't stretch at all
+    let lon_fraction = if stretched_lat.abs() > 1e-6 {
+        (isometric_lat(lat) - isometric_lat(from.latitude_deg)) / stretched_lat
+    } else {
+        fraction
+    };
+
+    Position::new(
+        lat,
+        normalize_longitude(from.longitude_deg + lon_fraction * lon_diff),
+    )
+}
+
+/// Split a path into separate lines wherever it crosses the antimeridian, so that each line
+/// can be drawn on a map without wrapping around the world.
+///
+/// Each crossing ends one line at 180 or -180 degrees longitude and starts the next line on the other side.
+pub fn split_at_antimeridian(points: &[Position]) -> Vec<Vec<Position>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::with_capacity(points.len());
+
+    for (i, &point) in points.iter().enumerate() {
+        if i > 0 {
+            let prev = points[i - 1];
+            let lon_diff = point.longitude_deg - prev.longitude_deg;
+
+            if lon_diff.abs() > 180.0 {
+                // The side of the antimeridian that the previous point is on
+                let edge = if lon_diff > 0.0 { -180.0 } else { 180.0 };
+
+                let unwrapped_lon = point.longitude_deg + 2.0 * edge;
+                let fraction = (edge - prev.longitude_deg) / (unwrapped_lon - prev.longitude_deg);
+                let lat = prev.latitude_deg + fraction * (point.latitude_deg - prev.latitude_deg);
+
+                line.push(Position::new(lat, edge));
+                lines.push(line);
+
+                line = Vec::with_capacity(points.len() - i + 1);
+                line.push(Position::new(lat, -edge));
+            }
+        }
+
+        line.push(point);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Get the northernmost point along a path of `kind` from one point to another.
+///
+/// For a great circle, this is its vertex when it lies between both points. Otherwise, and
+/// always for a rhumb line, it's the northernmost of the two points.
+pub fn northernmost_point(from: Position, to: Position, kind: PathKind) -> Position {
+    let endpoint = if from.latitude_deg >= to.latitude_deg {
+        from
+    } else {
+        to
+    };
+
+    if kind == PathKind::Rhumb {
+        return endpoint;
+    }
+
+    let from_vec = to_vector(from);
+    let to_vec = to_vector(to);
+    let normal = cross(from_vec, to_vec);
+
+    if dot(normal, normal) < 1e-12 {
+        return endpoint;
+    }
+
+    // Project the north pole onto the plane of the great circle
+    let pole_dist = normal[2] / dot(normal, normal);
+    let vertex = [
+        -pole_dist * normal[0],
+        -pole_dist * normal[1],
+        1.0 - pole_dist * normal[2],
+    ];
+
+    if dot(vertex, vertex) < 1e-12 {
+        return endpoint;
+    }
+
+    let is_between =
+        dot(cross(from_vec, vertex), normal) >= 0.0 && dot(cross(vertex, to_vec), normal) >= 0.0;
+
+    if is_between {
+        to_position(vertex)
+    } else {
+        endpoint
+    }
+}
+
+fn to_vector(pos: Position) -> Vector {
+    let lat = pos.latitude_deg.to_radians();
+    let lon = pos.longitude_deg.to_radians();
+
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+/// Convert a vector of any length back into a position.
+fn to_position(vec: Vector) -> Position {
+    let lat = vec[2].atan2((vec[0] * vec[0] + vec[1] * vec[1]).sqrt());
+    let lon = vec[1].atan2(vec[0]);
+
+    Position::new(lat.to_degrees(), normalize_longitude(lon.to_degrees()))
+}
+
+#[inline(always)]
+fn dot(a: Vector, b: Vector) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline(always)]
+fn cross(a: Vector, b: Vector) -> Vector {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 0.1,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    const KSFO: Position = Position {
+        latitude_deg: 37.618,
+        longitude_deg: -122.375,
+    };
+
+    const RJAA: Position = Position {
+        latitude_deg: 35.764,
+        longitude_deg: 140.386,
+    };
+
+    #[test]
+    fn densify_path() {
+        let path = densify(KSFO, RJAA, 100.0, PathKind::GreatCircle);
+
+        // 4442 nm
+        assert_eq!(path.len(), 46);
+        assert_eq!(path[0], KSFO);
+        assert_eq!(path[45], RJAA);
+
+        for points in path.windows(2) {
+            let distance = geodesic::distance_nm(points[0], points[1], DistanceOptions::default());
+            assert!(distance <= 100.0);
+        }
+
+        let mid = halfway_point(KSFO, RJAA, PathKind::GreatCircle);
+        assert_close(mid.latitude_deg, 48.4);
+        assert_close(mid.longitude_deg, -171.8);
+
+        let mid = halfway_point(
+            Position::new(0.0, 170.0),
+            Position::new(0.0, -170.0),
+            PathKind::GreatCircle,
+        );
+        assert_close(mid.latitude_deg, 0.0);
+        assert_close(mid.longitude_deg.abs(), 180.0);
+
+        assert_eq!(densify(KSFO, KSFO, 100.0, PathKind::GreatCircle).len(), 2);
+        assert_eq!(
+            densify(KSFO, RJAA, 0.01, PathKind::GreatCircle).len(),
+            MAX_PATH_POINTS
+        );
+    }
+
+    #[test]
+    fn split_path_at_antimeridian() {
+        let lines = split_at_antimeridian(&densify(KSFO, RJAA, 100.0, PathKind::GreatCircle));
+
+        assert_eq!(lines.len(), 2);
+
+        let east = &lines[0];
+        let west = &lines[1];
+        let crossing = east.last().unwrap();
+
+        assert_eq!(crossing.longitude_deg, -180.0);
+        assert_eq!(west[0].longitude_deg, 180.0);
+        assert_eq!(west[0].latitude_deg, crossing.latitude_deg);
+        assert!(east.iter().all(|pos| pos.longitude_deg <= 0.0));
+        assert!(west.iter().all(|pos| pos.longitude_deg >= 0.0));
+
+        // Paths that don't cross are left alone
+        let path = densify(
+            KSFO,
+            Position::new(38.512, -121.492),
+            10.0,
+            PathKind::GreatCircle,
+        );
+        assert_eq!(split_at_antimeridian(&path), vec![path]);
+    }
+
+    #[test]
+    fn northernmost_points() {
+        let vertex = northernmost_point(KSFO, RJAA, PathKind::GreatCircle);
+        assert_close(vertex.latitude_deg, 48.5);
+        assert!(vertex.latitude_deg > KSFO.latitude_deg);
+
+        // The vertex of the great circle is beyond the end of the path
+        let ksac = Position::new(38.512, -121.492);
+        assert_eq!(northernmost_point(KSFO, ksac, PathKind::GreatCircle), ksac);
+
+        let south = Position::new(-10.0, 20.0);
+        let north = Position::new(10.0, 20.0);
+        assert_eq!(
+            northernmost_point(north, south, PathKind::GreatCircle),
+            north
+        );
+    }
+
+    #[test]
+    fn rhumb_paths() {
+        let rhumb = DistanceOptions {
+            model: EarthModel::Spherical,
+            path: PathKind::Rhumb,
+        };
+
+        let course = geodesic::measure(KSFO, RJAA, rhumb).initial_course_deg;
+        let path = densify(KSFO, RJAA, 100.0, PathKind::Rhumb);
+
+        assert_eq!(path[0], KSFO);
+        assert_eq!(path[path.len() - 1], RJAA);
+
+        // The course stays the same along the whole path, which doesn't go as far north as the great circle
+        for points in path.windows(2) {
+            let leg = geodesic::measure(points[0], points[1], rhumb);
+            assert!(leg.distance_nm <= 100.0);
+            assert!((leg.initial_course_deg - course).abs() < 0.1);
+        }
+
+        assert!(path.iter().all(|pos| pos.latitude_deg <= KSFO.latitude_deg));
+        assert_eq!(northernmost_point(KSFO, RJAA, PathKind::Rhumb), KSFO);
+
+        let mid = halfway_point(KSFO, RJAA, PathKind::Rhumb);
+        assert_close(
+            mid.latitude_deg,
+            (KSFO.latitude_deg + RJAA.latitude_deg) / 2.0,
+        );
+
+        // Paths along a parallel change longitude evenly, the short way around
+        let east = Position::new(10.0, 170.0);
+        let west = Position::new(10.0, -170.0);
+        let mid = intermediate_rhumb_point(east, west, 0.25);
+        assert_close(mid.latitude_deg, 10.0);
+        assert_close(mid.longitude_deg, 175.0);
+    }
+}
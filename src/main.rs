@@ -6,13 +6,18 @@ extern crate rocket_contrib;
 
 mod airport_data;
 mod api;
+mod atmosphere;
+mod geo;
 mod path;
+mod wind;
 
 use airport_data::our_airports::OurAirports;
-use airport_data::AirportData;
+use airport_data::{AirportData, Airports};
 use anyhow::{Context, Result};
+use api::aircraft::AircraftCatalog;
 use rocket::config::{Config, Environment};
 use rocket_contrib::serve::StaticFiles;
+use wind::forecast::WindForecast;
 
 #[rocket::main]
 async fn main() -> Result<()> {
@@ -36,14 +41,47 @@ async fn main() -> Result<()> {
 
     let airports = airports_source
         .load()
+        .and_then(|airports| Ok(Airports::new(airports, airports_source.load_navaids()?)))
         .context("failed to load OurAirports data")?;
 
     println!("finished loading OurAirports data");
 
+    let aircraft = AircraftCatalog::load().context("failed to load aircraft catalog")?;
+    // Winds are optional, so a forecast that can't be loaded doesn't stop the server
+    let forecast = WindForecast::load().unwrap_or_else(|err| {
+        eprintln!("warning: failed to load wind forecast: {:#}", err);
+        None
+    });
+
+    if forecast.is_some() {
+        println!("loaded wind forecast");
+    }
+
     rocket::custom(config)
         .manage(airports)
+        .manage(aircraft)
+        .manage(forecast)
         .mount("/", StaticFiles::from("frontend/public/"))
-        .mount("/api", routes![api::search_routes::search_routes])
+        .mount(
+            "/api",
+            routes![
+                api::search_routes::search_routes,
+                api::aircraft::list_aircraft,
+                api::airports::airport,
+                api::airports::list_airports,
+                api::airports::nearest_airports,
+                api::airports::search_airports,
+                api::matrix::matrix,
+                api::route::route,
+                api::suggest::suggest_airports,
+                api::suggest::suggest_countries,
+                api::wind::wind_forecast
+            ],
+        )
+        .register(catchers![
+            api::error::bad_request,
+            api::error::unprocessable_entity
+        ])
         .launch()
         .await
         .context("failed to initialize Rocket")
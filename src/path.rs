@@ -31,6 +31,16 @@ impl FilePath {
         validate_dir(&dir)?;
         Ok(dir)
     }
+
+    /// Get the path of a file directly in the directory, which may not exist yet.
+    pub fn validated_file<P>(self, filename: P) -> Result<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = self.path();
+        validate_dir(&dir)?;
+        Ok(dir.join(filename))
+    }
 }
 
 pub fn validate_dir<P>(dir: P) -> Result<()>
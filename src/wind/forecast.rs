@@ -0,0 +1,351 @@
+use super::grib::{self, LatLonGrid, Product};
+use super::{Wind, WindField};
+use crate::airport_data::Position;
+use crate::atmosphere::Atmosphere;
+use crate::path::FilePath;
+use anyhow::{anyhow, bail, Result};
+use chrono::NaiveDateTime;
+use serde_derive::Serialize;
+use std::cmp::{Ordering, Reverse};
+use std::fs;
+use std::path::Path;
+
+/// Winds on a grid at several pressure levels, read from a GRIB2 forecast such as the GFS.
+#[derive(Debug)]
+pub struct WindForecast {
+    reference_time: Option<NaiveDateTime>,
+    forecast_hours: Option<u32>,
+    grid: LatLonGrid,
+    /// Sorted from the highest pressure to the lowest, which is from the lowest altitude to the highest.
+    levels: Vec<PressureLevel>,
+}
+
+/// The eastward and northward wind components in meters per second at every point of the grid.
+#[derive(Debug)]
+struct PressureLevel {
+    pressure_pa: f32,
+    u: Vec<f32>,
+    v: Vec<f32>,
+}
+
+impl WindForecast {
+    const DIR: &'static str = "winds";
+
+    const METEOROLOGY: u8 = 0;
+    const MOMENTUM: u8 = 2;
+    const U_WIND: u8 = 2;
+    const V_WIND: u8 = 3;
+
+    const KT_PER_MPS: f32 = 3600.0 / 1852.0;
+
+    /// Load the most recently modified file in the winds directory of the data directory
+    /// that can be read, or `None` if there aren't any.
+    ///
+    /// The file isn't downloaded automatically, so it can be any GRIB2 file with u and v
+    /// winds at isobaric levels. GFS files at 0.5 or 1 degree resolution keep memory use down.
+    /// Files that fail to load are reported and skipped, since the forecast is optional.
+    pub fn load() -> Result<Option<Self>> {
+        let dir = FilePath::LocalData.validated_subdir(Self::DIR)?;
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let path = entry.path();
+
+            if !metadata.is_file() || !Self::is_forecast_file(&path) {
+                continue;
+            }
+
+            files.push((metadata.modified()?, path));
+        }
+
+        files.sort_unstable_by_key(|(modified, _)| Reverse(*modified));
+
+        for (_, path) in files {
+            match Self::from_file(&path) {
+                Ok(forecast) => return Ok(Some(forecast)),
+                Err(err) => eprintln!(
+                    "warning: skipping wind forecast {}: {:#}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Whether a file might be a forecast, which leaves out hidden files and the index
+    /// files that are published alongside GFS forecasts.
+    fn is_forecast_file(path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .map(|name| name.to_string_lossy().starts_with('.'))
+            .unwrap_or(true);
+
+        let index = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("idx"))
+            .unwrap_or(false);
+
+        !hidden && !index
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let data = fs::read(path)?;
+        Self::from_grib(&data)
+    }
+
+    pub fn from_grib(data: &[u8]) -> Result<Self> {
+        let fields = grib::read_fields(data, |product| {
+            product.discipline == Self::METEOROLOGY
+                && product.category == Self::MOMENTUM
+                && (product.number == Self::U_WIND || product.number == Self::V_WIND)
+                && product.surface_type == Product::ISOBARIC_SURFACE
+        })?;
+
+        let first = fields
+            .first()
+            .ok_or_else(|| anyhow!("no u and v winds at isobaric levels were found"))?;
+
+        let reference_time = first.reference_time;
+        let forecast_hours = first.product.forecast_hours;
+        let grid = first.grid;
+
+        let mut u_fields = Vec::new();
+        let mut v_fields = Vec::new();
+
+        for field in fields {
+            if field.grid != grid {
+                bail!("winds must all be on the same grid");
+            }
+
+            if field.product.forecast_hours != forecast_hours {
+                bail!("winds must all be from the same forecast hour");
+            }
+
+            if field.product.number == Self::U_WIND {
+                u_fields.push(field);
+            } else {
+                v_fields.push(field);
+            }
+        }
+
+        let mut levels = Vec::new();
+
+        // Levels with only one of the components are left out
+        for u in u_fields {
+            let pressure_pa = u.product.surface_value;
+
+            let v = v_fields
+                .iter()
+                .position(|v| v.product.surface_value == pressure_pa)
+                .map(|index| v_fields.swap_remove(index));
+
+            if let Some(v) = v {
+                levels.push(PressureLevel {
+                    pressure_pa,
+                    u: u.values,
+                    v: v.values,
+                });
+            }
+        }
+
+        if levels.is_empty() {
+            bail!("no levels with both u and v winds were found");
+        }
+
+        levels.sort_unstable_by(|a, b| {
+            b.pressure_pa
+                .partial_cmp(&a.pressure_pa)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        Ok(Self {
+            reference_time,
+            forecast_hours,
+            grid,
+            levels,
+        })
+    }
+
+    pub fn info(&self) -> ForecastInfo {
+        ForecastInfo {
+            reference_time: self
+                .reference_time
+                .map(|time| time.format("%FT%TZ").to_string()),
+            forecast_hours: self.forecast_hours,
+            levels_hpa: self
+                .levels
+                .iter()
+                .map(|level| level.pressure_pa / 100.0)
+                .collect(),
+        }
+    }
+
+    /// Get the wind components of a level in knots, or `None` when the position isn't covered.
+    fn components_at(&self, level: &PressureLevel, position: Position) -> Option<(f32, f32)> {
+        let u = self.grid.interpolate(&level.u, position)?;
+        let v = self.grid.interpolate(&level.v, position)?;
+        Some((u * Self::KT_PER_MPS, v * Self::KT_PER_MPS))
+    }
+}
+
+impl WindField for WindForecast {
+    /// Get the wind by interpolating between grid points, and then between the closest
+    /// pressure levels by the logarithm of the pressure at the altitude in a standard atmosphere.
+    ///
+    /// Altitudes outside of the levels get the wind of the closest level, and positions
+    /// outside of the grid are calm.
+    fn wind_at(&self, position: Position, altitude_ft: f32) -> Wind {
+        let pressure_pa = Atmosphere::at(altitude_ft, 0.0).pressure_pa;
+
+        let upper = self
+            .levels
+            .iter()
+            .position(|level| level.pressure_pa <= pressure_pa);
+
+        let (lower, upper) = match upper {
+            Some(0) => (&self.levels[0], &self.levels[0]),
+            Some(index) => (&self.levels[index - 1], &self.levels[index]),
+            None => {
+                let last = &self.levels[self.levels.len() - 1];
+                (last, last)
+            }
+        };
+
+        let lower_wind = self.components_at(lower, position);
+        let upper_wind = self.components_at(upper, position);
+
+        let ((lower_u, lower_v), (upper_u, upper_v)) = match (lower_wind, upper_wind) {
+            (Some(lower_wind), Some(upper_wind)) => (lower_wind, upper_wind),
+            _ => return Wind::CALM,
+        };
+
+        let fraction = if upper.pressure_pa < lower.pressure_pa {
+            (pressure_pa.ln() - lower.pressure_pa.ln())
+                / (upper.pressure_pa.ln() - lower.pressure_pa.ln())
+        } else {
+            0.0
+        };
+
+        Wind::from_components(
+            lower_u + fraction * (upper_u - lower_u),
+            lower_v + fraction * (upper_v - lower_v),
+        )
+    }
+}
+
+/// What a loaded forecast covers.
+#[derive(Debug, Serialize)]
+pub struct ForecastInfo {
+    #[serde(rename = "referenceTime")]
+    pub reference_time: Option<String>,
+    #[serde(rename = "forecastHours")]
+    pub forecast_hours: Option<u32>,
+    #[serde(rename = "levelsHPA")]
+    pub levels_hpa: Vec<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wind::test_data::*;
+
+    /// A global grid with 90 degree spacing where the u and v winds are the same everywhere.
+    fn level(pressure_pa: u32, u: u8, v: u8) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        for &(number, value) in &[(WindForecast::U_WIND, u), (WindForecast::V_WIND, v)] {
+            data.extend(message(&[
+                (1, identification()),
+                (3, global_grid(4, 3, 90)),
+                (4, isobaric_product(2, number, pressure_pa)),
+                (5, simple_packing(12, 0.0)),
+                (6, vec![255]),
+                (7, vec![value; 12]),
+            ]));
+        }
+
+        data
+    }
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 0.1,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    #[test]
+    fn forecast_winds() {
+        let mut data = level(25_000, 40, 0);
+        data.extend(level(50_000, 10, 10));
+
+        // Temperatures are skipped
+        data.extend(message(&[
+            (3, global_grid(4, 3, 90)),
+            (4, isobaric_product(0, 0, 50_000)),
+            (5, simple_packing(12, 200.0)),
+            (6, vec![255]),
+            (7, vec![50; 12]),
+        ]));
+
+        let forecast = WindForecast::from_grib(&data).unwrap();
+        let info = forecast.info();
+        assert_eq!(info.reference_time.as_deref(), Some("2020-09-14T06:00:00Z"));
+        assert_eq!(info.forecast_hours, Some(6));
+        assert_eq!(info.levels_hpa, vec![500.0, 250.0]);
+
+        let position = Position::new(37.6, -122.4);
+
+        // Below 500 hPa, which is around 18,300 feet
+        let low = forecast.wind_at(position, 5000.0);
+        assert_close(low.direction, 225.0);
+        assert_close(low.speed_kt, 200f32.sqrt() * WindForecast::KT_PER_MPS);
+
+        // Above 250 hPa, which is around 34,000 feet
+        let high = forecast.wind_at(position, 40_000.0);
+        assert_close(high.direction, 270.0);
+        assert_close(high.speed_kt, 40.0 * WindForecast::KT_PER_MPS);
+
+        // Between the levels by the logarithm of the pressure
+        let pressure_pa = Atmosphere::at(25_000.0, 0.0).pressure_pa;
+        let fraction = (pressure_pa / 50_000.0).ln() / 0.5f32.ln();
+        let (u, v) = forecast.wind_at(position, 25_000.0).components();
+        assert!(fraction > 0.4 && fraction < 0.6);
+        assert_close(u, (10.0 + 30.0 * fraction) * WindForecast::KT_PER_MPS);
+        assert_close(v, (10.0 - 10.0 * fraction) * WindForecast::KT_PER_MPS);
+    }
+
+    #[test]
+    fn forecast_without_winds() {
+        let data = message(&[
+            (3, global_grid(4, 3, 90)),
+            (4, isobaric_product(2, WindForecast::U_WIND, 25_000)),
+            (5, simple_packing(12, 0.0)),
+            (6, vec![255]),
+            (7, vec![0; 12]),
+        ]);
+
+        assert_eq!(
+            WindForecast::from_grib(&data).unwrap_err().to_string(),
+            "no levels with both u and v winds were found"
+        );
+
+        assert!(WindForecast::from_grib(b"not a forecast").is_err());
+    }
+
+    #[test]
+    fn forecast_files() {
+        let is_forecast = |name| WindForecast::is_forecast_file(Path::new(name));
+
+        assert!(is_forecast("gfs.t00z.pgrb2.0p50.f006"));
+        assert!(is_forecast("winds.grib2"));
+        assert!(!is_forecast("gfs.t00z.pgrb2.0p50.f006.idx"));
+        assert!(!is_forecast(".DS_Store"));
+    }
+}
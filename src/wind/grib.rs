@@ -0,0 +1,900 @@
+//! A reader for the parts of GRIB2 files that are needed for winds aloft.
+//!
+//! Only regular latitude/longitude grids with simple or complex packing are supported,
+//! which covers the GFS forecasts that NOAA publishes.
+
+use crate::airport_data::Position;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// The values of one parameter on a grid, such as the eastward wind at 250 hPa.
+#[derive(Debug)]
+pub struct Field {
+    pub product: Product,
+    pub reference_time: Option<NaiveDateTime>,
+    pub grid: LatLonGrid,
+    /// One value for every point of the grid, where missing values are NaN.
+    pub values: Vec<f32>,
+}
+
+/// What a field contains.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Product {
+    pub discipline: u8,
+    pub category: u8,
+    pub number: u8,
+    /// The type of the surface the field is on, such as 100 for an isobaric surface.
+    pub surface_type: u8,
+    /// The value of the surface, such as the pressure in pascals for an isobaric surface.
+    pub surface_value: f32,
+    /// How many hours after the reference time the field is valid for, if it's given in hours or minutes.
+    pub forecast_hours: Option<u32>,
+}
+
+impl Product {
+    pub const ISOBARIC_SURFACE: u8 = 100;
+}
+
+/// A grid with points evenly spaced by latitude and longitude.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LatLonGrid {
+    /// The number of points along a parallel.
+    pub ni: usize,
+    /// The number of points along a meridian.
+    pub nj: usize,
+    pub first: Position,
+    /// The longitude increment in degrees.
+    pub di: f32,
+    /// The latitude increment in degrees.
+    pub dj: f32,
+    scan_mode: u8,
+}
+
+impl LatLonGrid {
+    /// Set when points go from east to west.
+    const SCAN_NEGATIVE_I: u8 = 0x80;
+    /// Set when points go from south to north.
+    const SCAN_POSITIVE_J: u8 = 0x40;
+    /// Set when consecutive points go along a meridian rather than a parallel.
+    const SCAN_J_CONSECUTIVE: u8 = 0x20;
+    /// Set when every other row goes in the opposite direction.
+    const SCAN_BOUSTROPHEDON: u8 = 0x10;
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ni * self.nj
+    }
+
+    /// Whether the grid goes all the way around the world, so that the last column is next to the first.
+    #[inline(always)]
+    fn is_global(&self) -> bool {
+        (self.ni as f32 * self.di - 360.0).abs() < self.di / 2.0
+    }
+
+    #[inline(always)]
+    fn index(&self, i: usize, j: usize) -> usize {
+        if self.scan_mode & Self::SCAN_J_CONSECUTIVE == 0 {
+            j * self.ni + i
+        } else {
+            i * self.nj + j
+        }
+    }
+
+    /// Interpolate the value at a position from the four closest points.
+    ///
+    /// Returns `None` when the position is outside of the grid or next to a missing value.
+    pub fn interpolate(&self, values: &[f32], pos: Position) -> Option<f32> {
+        let lon_offset = if self.scan_mode & Self::SCAN_NEGATIVE_I == 0 {
+            pos.longitude_deg - self.first.longitude_deg
+        } else {
+            self.first.longitude_deg - pos.longitude_deg
+        };
+
+        let lat_offset = if self.scan_mode & Self::SCAN_POSITIVE_J == 0 {
+            self.first.latitude_deg - pos.latitude_deg
+        } else {
+            pos.latitude_deg - self.first.latitude_deg
+        };
+
+        let x = lon_offset.rem_euclid(360.0) / self.di;
+        let y = lat_offset / self.dj;
+        let (last_i, last_j) = ((self.ni - 1) as f32, (self.nj - 1) as f32);
+
+        if y < 0.0 || y > last_j || (x > last_i && !self.is_global()) {
+            return None;
+        }
+
+        let (i, j0) = (x.floor() as usize, y.floor() as usize);
+        let (fx, fy) = (x - i as f32, y - j0 as f32);
+
+        // Offsets that round up to 360 degrees end up past the last column of a global grid
+        let i0 = i % self.ni;
+        let i1 = (i0 + 1) % self.ni;
+        let j1 = (j0 + 1).min(self.nj - 1);
+
+        let value = |i, j| values[self.index(i, j)];
+        let top = value(i0, j0) + fx * (value(i1, j0) - value(i0, j0));
+        let bottom = value(i0, j1) + fx * (value(i1, j1) - value(i0, j1));
+        let result = top + fy * (bottom - top);
+
+        if result.is_nan() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+}
+
+/// Read every field from GRIB2 data that `wanted` returns true for.
+///
+/// Fields that aren't wanted are skipped without being decoded, so they can use
+/// templates that aren't supported.
+pub fn read_fields<F>(data: &[u8], mut wanted: F) -> Result<Vec<Field>>
+where
+    F: FnMut(&Product) -> bool,
+{
+    let mut fields = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = find_message(&data[pos..]) {
+        let start = pos + offset;
+
+        let truncated = || anyhow!("truncated message at byte {}", start);
+
+        let end = data
+            .get(start + 8..start + 16)
+            .and_then(|bytes| start.checked_add(read_u64(bytes) as usize))
+            .ok_or_else(truncated)?;
+
+        let message = data.get(start..end).ok_or_else(truncated)?;
+
+        read_message(message, &mut wanted, &mut fields)
+            .with_context(|| format!("failed to read message at byte {}", start))?;
+
+        pos = end;
+    }
+
+    Ok(fields)
+}
+
+#[inline(always)]
+fn find_message(data: &[u8]) -> Option<usize> {
+    data.windows(4).position(|bytes| bytes == b"GRIB")
+}
+
+/// How the values of a field are packed.
+enum Packing {
+    Simple(SimplePacking),
+    Complex(SimplePacking, ComplexPacking),
+    Unsupported(u16),
+}
+
+#[derive(Copy, Clone)]
+struct SimplePacking {
+    reference: f32,
+    binary_scale: i32,
+    decimal_scale: i32,
+    bits: u32,
+}
+
+impl SimplePacking {
+    #[inline(always)]
+    fn unpack(self, value: i64) -> f32 {
+        let value = (f64::from(self.reference) + value as f64 * 2f64.powi(self.binary_scale))
+            / 10f64.powi(self.decimal_scale);
+
+        value as f32
+    }
+}
+
+#[derive(Copy, Clone)]
+struct ComplexPacking {
+    missing_values: u8,
+    groups: usize,
+    width_reference: u32,
+    width_bits: u32,
+    length_reference: u32,
+    length_increment: u32,
+    last_length: u32,
+    length_bits: u32,
+    /// The order of spatial differencing, which is 0 when it isn't used.
+    differencing_order: usize,
+    descriptor_octets: usize,
+}
+
+fn read_message<F>(message: &[u8], wanted: &mut F, fields: &mut Vec<Field>) -> Result<()>
+where
+    F: FnMut(&Product) -> bool,
+{
+    if message.len() < 16 || message[7] != 2 {
+        bail!("only GRIB edition 2 is supported");
+    }
+
+    let discipline = message[6];
+    let mut reference_time = None;
+    let mut grid = None;
+    let mut product = None;
+    let mut packing = None;
+    let mut bitmap: Option<&[u8]> = None;
+    let mut pos = 16;
+
+    // Sections 2 through 7 can repeat for each field in the message
+    while pos + 4 <= message.len() && &message[pos..pos + 4] != b"7777" {
+        let section = message
+            .get(pos..)
+            .filter(|rest| rest.len() >= 5)
+            .and_then(|rest| pos.checked_add(read_u32(rest) as usize))
+            .and_then(|end| message.get(pos..end))
+            .filter(|section| section.len() >= 5)
+            .ok_or_else(|| anyhow!("truncated section at byte {}", pos))?;
+
+        let number = section[4];
+
+        match number {
+            1 => reference_time = read_reference_time(section),
+            3 => grid = Some(read_grid(section)?),
+            4 => product = read_product(discipline, section)?,
+            5 => packing = Some(read_packing(section)?),
+            6 => match section.get(5) {
+                Some(0) => bitmap = Some(&section[6..]),
+                Some(254) => (),
+                _ => bitmap = None,
+            },
+            7 => {
+                let product = match &product {
+                    Some(product) if wanted(product) => *product,
+                    _ => {
+                        pos += section.len();
+                        continue;
+                    }
+                };
+
+                let grid = match grid {
+                    Some(Ok(grid)) => grid,
+                    Some(Err(template)) => bail!("grid template {} is not supported", template),
+                    None => bail!("data is missing its grid definition"),
+                };
+
+                let (count, packing) = packing
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("data is missing its data representation"))?;
+
+                if *count > grid.len() {
+                    bail!(
+                        "{} values don't fit on a grid of {} points",
+                        count,
+                        grid.len()
+                    );
+                }
+
+                let values = decode_values(packing, *count, &section[5..])?;
+                let values = apply_bitmap(values, bitmap, grid.len())?;
+
+                fields.push(Field {
+                    product,
+                    reference_time,
+                    grid,
+                    values,
+                });
+            }
+            _ => (),
+        }
+
+        pos += section.len();
+    }
+
+    Ok(())
+}
+
+fn read_reference_time(section: &[u8]) -> Option<NaiveDateTime> {
+    let bytes = section.get(12..19)?;
+    let year = read_u16(&bytes[0..2]);
+
+    NaiveDate::from_ymd_opt(i32::from(year), u32::from(bytes[2]), u32::from(bytes[3]))?.and_hms_opt(
+        u32::from(bytes[4]),
+        u32::from(bytes[5]),
+        u32::from(bytes[6]),
+    )
+}
+
+/// Read a grid definition, or the number of the grid template if it isn't supported.
+fn read_grid(section: &[u8]) -> Result<Result<LatLonGrid, u16>> {
+    let template = read_u16(
+        section
+            .get(12..14)
+            .ok_or_else(|| anyhow!("truncated grid"))?,
+    );
+
+    if template != 0 {
+        return Ok(Err(template));
+    }
+
+    let bytes = section
+        .get(14..72)
+        .ok_or_else(|| anyhow!("truncated latitude/longitude grid"))?;
+
+    // Octets are numbered from the start of the section
+    let octet = |num: usize| &bytes[num - 15..];
+
+    let ni = read_u32(octet(31)) as usize;
+    let nj = read_u32(octet(35)) as usize;
+    let basic_angle = read_u32(octet(39));
+    let subdivisions = read_u32(octet(43));
+
+    let unit = match (basic_angle, subdivisions) {
+        (0, _) | (u32::MAX, _) | (_, 0) | (_, u32::MAX) => 1e-6,
+        (basic_angle, subdivisions) => f64::from(basic_angle) / f64::from(subdivisions),
+    };
+
+    let angle = |value: i64| (value as f64 * unit) as f32;
+    let scan_mode = octet(72)[0];
+
+    if ni == 0 || nj == 0 {
+        bail!("grid has no points");
+    }
+
+    if scan_mode & LatLonGrid::SCAN_BOUSTROPHEDON != 0 {
+        bail!("grids with alternating row directions are not supported");
+    }
+
+    Ok(Ok(LatLonGrid {
+        ni,
+        nj,
+        first: Position::new(
+            angle(read_signed(&octet(47)[..4])),
+            angle(read_signed(&octet(51)[..4])),
+        ),
+        di: angle(i64::from(read_u32(octet(64)))),
+        dj: angle(i64::from(read_u32(octet(68)))),
+        scan_mode,
+    }))
+}
+
+/// Read a product definition, which is `None` when its template isn't supported.
+fn read_product(discipline: u8, section: &[u8]) -> Result<Option<Product>> {
+    let template = read_u16(
+        section
+            .get(7..9)
+            .ok_or_else(|| anyhow!("truncated product"))?,
+    );
+
+    // Templates 4.1 and 4.2 for ensemble forecasts start out the same as 4.0
+    if template > 2 {
+        return Ok(None);
+    }
+
+    let bytes = section
+        .get(9..34)
+        .ok_or_else(|| anyhow!("truncated product definition"))?;
+
+    let octet = |num: usize| &bytes[num - 10..];
+    let forecast_time = read_u32(octet(19));
+
+    let forecast_hours = match octet(18)[0] {
+        0 => Some(forecast_time / 60),
+        1 => Some(forecast_time),
+        _ => None,
+    };
+
+    let scale_factor = read_signed(&octet(24)[..1]) as i32;
+    let scaled_value = read_signed(&octet(25)[..4]);
+
+    Ok(Some(Product {
+        discipline,
+        category: octet(10)[0],
+        number: octet(11)[0],
+        surface_type: octet(23)[0],
+        surface_value: (scaled_value as f64 / 10f64.powi(scale_factor)) as f32,
+        forecast_hours,
+    }))
+}
+
+/// Read the number of packed values and how they're packed.
+fn read_packing(section: &[u8]) -> Result<(usize, Packing)> {
+    let header = section
+        .get(5..11)
+        .ok_or_else(|| anyhow!("truncated data representation"))?;
+
+    let count = read_u32(header) as usize;
+    let template = read_u16(&header[4..]);
+
+    // Get `len` octets starting from octet `num`, which are numbered from 1
+    let octet = |num: usize, len: usize| {
+        section
+            .get(num - 1..num - 1 + len)
+            .ok_or_else(|| anyhow!("truncated data representation template {}", template))
+    };
+
+    let simple = match template {
+        0 | 2 | 3 => SimplePacking {
+            reference: f32::from_bits(read_u32(octet(12, 4)?)),
+            binary_scale: read_signed(octet(16, 2)?) as i32,
+            decimal_scale: read_signed(octet(18, 2)?) as i32,
+            bits: u32::from(octet(20, 1)?[0]),
+        },
+        _ => return Ok((count, Packing::Unsupported(template))),
+    };
+
+    if template == 0 {
+        return Ok((count, Packing::Simple(simple)));
+    }
+
+    if octet(22, 1)?[0] != 1 {
+        bail!("only general group splitting is supported");
+    }
+
+    let (differencing_order, descriptor_octets) = match template {
+        3 => (usize::from(octet(48, 1)?[0]), usize::from(octet(49, 1)?[0])),
+        _ => (0, 0),
+    };
+
+    let complex = ComplexPacking {
+        missing_values: octet(23, 1)?[0],
+        groups: read_u32(octet(32, 4)?) as usize,
+        width_reference: u32::from(octet(36, 1)?[0]),
+        width_bits: u32::from(octet(37, 1)?[0]),
+        length_reference: read_u32(octet(38, 4)?),
+        length_increment: u32::from(octet(42, 1)?[0]),
+        last_length: read_u32(octet(43, 4)?),
+        length_bits: u32::from(octet(47, 1)?[0]),
+        differencing_order,
+        descriptor_octets,
+    };
+
+    if complex.missing_values > 2 {
+        bail!(
+            "missing value management {} is not supported",
+            complex.missing_values
+        );
+    }
+
+    if differencing_order > 2 || (differencing_order > 0 && !(1..=4).contains(&descriptor_octets)) {
+        bail!(
+            "spatial differencing of order {} is not supported",
+            differencing_order
+        );
+    }
+
+    Ok((count, Packing::Complex(simple, complex)))
+}
+
+fn decode_values(packing: &Packing, count: usize, data: &[u8]) -> Result<Vec<f32>> {
+    match packing {
+        Packing::Simple(simple) => decode_simple(*simple, count, data),
+        Packing::Complex(simple, complex) => decode_complex(*simple, complex, count, data),
+        Packing::Unsupported(template) => {
+            bail!("data representation template {} is not supported", template)
+        }
+    }
+}
+
+fn decode_simple(packing: SimplePacking, count: usize, data: &[u8]) -> Result<Vec<f32>> {
+    let mut reader = BitReader::new(data);
+    let mut values = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let value = reader.read(packing.bits)?;
+        values.push(packing.unpack(i64::from(value)));
+    }
+
+    Ok(values)
+}
+
+fn decode_complex(
+    simple: SimplePacking,
+    complex: &ComplexPacking,
+    count: usize,
+    data: &[u8],
+) -> Result<Vec<f32>> {
+    let order = complex.differencing_order;
+    let octets = complex.descriptor_octets;
+
+    // The first values and the minimum of the spatial differences come before the groups
+    let descriptors = if order > 0 {
+        let bytes = data
+            .get(..(order + 1) * octets)
+            .ok_or_else(|| anyhow!("truncated spatial differencing descriptors"))?;
+
+        bytes.chunks(octets).map(read_signed).collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+
+    let mut reader = BitReader::new(&data[descriptors.len() * octets..]);
+    let groups = complex.groups;
+
+    // Every group has at least one value
+    if groups > count {
+        bail!("{} groups is more than the {} values", groups, count);
+    }
+
+    let mut read_group_values = |bits, reference, increment| -> Result<Vec<u32>> {
+        let values = (0..groups)
+            .map(|_| Ok(reference + reader.read(bits)? * increment))
+            .collect::<Result<Vec<_>>>()?;
+
+        reader.align();
+        Ok(values)
+    };
+
+    let references = read_group_values(simple.bits, 0, 1)?;
+    let widths = read_group_values(complex.width_bits, complex.width_reference, 1)?;
+    let mut lengths = read_group_values(
+        complex.length_bits,
+        complex.length_reference,
+        complex.length_increment,
+    )?;
+
+    if let Some(last) = lengths.last_mut() {
+        *last = complex.last_length;
+    }
+
+    let minimum = descriptors.last().copied().unwrap_or(0);
+    let mut values: Vec<Option<i64>> = Vec::with_capacity(count);
+
+    for ((&reference, &width), &length) in references.iter().zip(&widths).zip(&lengths) {
+        let missing = |value: u32, bits: u32| {
+            let all_ones = ((1u64 << bits) - 1) as u32;
+
+            match complex.missing_values {
+                1 => value == all_ones,
+                2 => value == all_ones || value == all_ones.wrapping_sub(1),
+                _ => false,
+            }
+        };
+
+        for _ in 0..length {
+            if values.len() == count {
+                bail!("groups have more than the {} values", count);
+            }
+
+            let value = if width == 0 {
+                if missing(reference, simple.bits) {
+                    None
+                } else {
+                    Some(i64::from(reference))
+                }
+            } else {
+                let packed = reader.read(width)?;
+
+                if missing(packed, width) {
+                    None
+                } else {
+                    Some(i64::from(reference) + i64::from(packed))
+                }
+            };
+
+            values.push(value.map(|value| value + minimum));
+        }
+    }
+
+    if values.len() != count {
+        bail!("expected {} values, found {}", count, values.len());
+    }
+
+    if order > 0 {
+        undo_spatial_differencing(&mut values, &descriptors[..order]);
+    }
+
+    let values = values
+        .into_iter()
+        .map(|value| match value {
+            Some(value) => simple.unpack(value),
+            None => f32::NAN,
+        })
+        .collect();
+
+    Ok(values)
+}
+
+/// Turn spatial differences back into values, skipping over missing values.
+fn undo_spatial_differencing(values: &mut [Option<i64>], first_values: &[i64]) {
+    let mut present = values.iter_mut().filter_map(Option::as_mut);
+    let mut prev = [0i64; 2];
+
+    for (i, value) in present.by_ref().enumerate() {
+        match first_values.get(i) {
+            Some(&first) => *value = first,
+            None if first_values.len() == 1 => *value += prev[1],
+            None => *value += 2 * prev[1] - prev[0],
+        }
+
+        prev = [prev[1], *value];
+    }
+}
+
+/// Spread packed values out over every point of the grid, where points that aren't
+/// in the bitmap are missing.
+fn apply_bitmap(values: Vec<f32>, bitmap: Option<&[u8]>, points: usize) -> Result<Vec<f32>> {
+    let bitmap = match bitmap {
+        Some(bitmap) => bitmap,
+        None if values.len() == points => return Ok(values),
+        None => bail!("expected {} values, found {}", points, values.len()),
+    };
+
+    if bitmap.len() * 8 < points {
+        bail!("bitmap is too short for {} points", points);
+    }
+
+    let mut values = values.into_iter();
+    let mut result = Vec::with_capacity(points);
+
+    for i in 0..points {
+        let is_present = bitmap[i / 8] & (0x80 >> (i % 8)) != 0;
+
+        let value = if is_present {
+            values
+                .next()
+                .ok_or_else(|| anyhow!("bitmap has more points than there are values"))?
+        } else {
+            f32::NAN
+        };
+
+        result.push(value);
+    }
+
+    Ok(result)
+}
+
+/// Reads unsigned integers of any width up to 32 bits from a stream of bits.
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// The position in bits.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    #[inline(always)]
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn read(&mut self, bits: u32) -> Result<u32> {
+        if bits == 0 {
+            return Ok(0);
+        }
+
+        if bits > 32 {
+            bail!("values with {} bits are not supported", bits);
+        }
+
+        let end = self.pos + bits as usize;
+
+        if end > self.data.len() * 8 {
+            bail!("unexpected end of data");
+        }
+
+        let (first, last) = (self.pos / 8, (end - 1) / 8);
+
+        let value = self.data[first..=last]
+            .iter()
+            .fold(0u64, |value, &byte| (value << 8) | u64::from(byte));
+
+        let value = (value >> ((last + 1) * 8 - end)) & ((1u64 << bits) - 1);
+        self.pos = end;
+
+        Ok(value as u32)
+    }
+
+    /// Skip to the start of the next byte.
+    #[inline(always)]
+    fn align(&mut self) {
+        self.pos += (8 - self.pos % 8) % 8;
+    }
+}
+
+#[inline(always)]
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+#[inline(always)]
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[inline(always)]
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(buf)
+}
+
+/// Read a signed integer, which GRIB stores with a sign bit rather than in two's complement.
+fn read_signed(bytes: &[u8]) -> i64 {
+    let value = bytes
+        .iter()
+        .fold(0i64, |value, &byte| (value << 8) | i64::from(byte));
+
+    let sign_bit = 1i64 << (bytes.len() * 8 - 1);
+
+    if value & sign_bit != 0 {
+        -(value & !sign_bit)
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wind::test_data::*;
+
+    #[test]
+    fn read_signed_integers() {
+        assert_eq!(read_signed(&[0x00, 0x05]), 5);
+        assert_eq!(read_signed(&[0x80, 0x05]), -5);
+        assert_eq!(read_signed(&[0x85]), -5);
+        assert_eq!(read_signed(&[0x80, 0x00, 0x00, 0x00]), 0);
+    }
+
+    #[test]
+    fn bit_reader() {
+        let mut reader = BitReader::new(&[0b1011_0011, 0b1100_0000, 0xFF]);
+
+        assert_eq!(reader.read(3).unwrap(), 0b101);
+        assert_eq!(reader.read(0).unwrap(), 0);
+        assert_eq!(reader.read(7).unwrap(), 0b100_1111);
+        reader.align();
+        assert_eq!(reader.read(8).unwrap(), 0xFF);
+        assert!(reader.read(1).is_err());
+    }
+
+    #[test]
+    fn simple_packed_field() {
+        // A bitmap leaves out the second point
+        let mut data = vec![0];
+        data.extend_from_slice(&[10, 20, 30, 40, 50]);
+
+        let data = message(&[
+            (1, identification()),
+            (3, global_grid(3, 2, 120)),
+            (4, isobaric_product(2, 2, 25_000)),
+            (5, simple_packing(5, -10.0)),
+            (6, vec![0, 0b1011_1110]),
+            (7, data[1..].to_vec()),
+        ]);
+
+        let fields = read_fields(&data, |_| true).unwrap();
+        assert_eq!(fields.len(), 1);
+
+        let field = &fields[0];
+        assert_eq!(field.product.category, 2);
+        assert_eq!(field.product.surface_value, 25_000.0);
+        assert_eq!(field.product.forecast_hours, Some(6));
+        assert_eq!(
+            field.reference_time,
+            NaiveDate::from_ymd_opt(2020, 9, 14).and_then(|date| date.and_hms_opt(6, 0, 0))
+        );
+
+        assert_eq!(field.grid.ni, 3);
+        assert_eq!(field.grid.first, Position::new(90.0, 0.0));
+        assert_eq!(field.grid.di, 120.0);
+
+        assert_eq!(field.values[0], 0.0);
+        assert!(field.values[1].is_nan());
+        assert_eq!(&field.values[2..], &[10.0, 20.0, 30.0, 40.0]);
+
+        // Fields that aren't wanted aren't decoded
+        assert!(read_fields(&data, |_| false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn complex_packed_field() {
+        let mut packing = 6u32.to_be_bytes().to_vec();
+        packing.extend_from_slice(&3u16.to_be_bytes());
+        packing.extend_from_slice(&0f32.to_bits().to_be_bytes());
+        // Values are in tenths, and each group reference takes 1 bit
+        packing.extend_from_slice(&[0, 0, 0, 1, 1, 0]);
+        packing.extend_from_slice(&[1, 0]);
+        packing.extend_from_slice(&[0; 8]);
+        // 2 groups with widths of 3 + 0 and 3 + 1 bits, and lengths of 3
+        packing.extend_from_slice(&2u32.to_be_bytes());
+        packing.extend_from_slice(&[3, 1]);
+        packing.extend_from_slice(&3u32.to_be_bytes());
+        packing.push(1);
+        packing.extend_from_slice(&3u32.to_be_bytes());
+        packing.push(1);
+        // Second order spatial differencing with 2 octets per descriptor
+        packing.extend_from_slice(&[2, 2]);
+
+        // The first two values and the minimum difference of -3
+        let mut data = vec![0x00, 0x0A, 0x00, 0x0C, 0x80, 0x03];
+        // Group references, widths and lengths
+        data.extend_from_slice(&[0x00, 0x40, 0x00]);
+        // Differences of 0, 0, 4 and 0, 2, 10 from the minimum
+        data.extend_from_slice(&[0x02, 0x01, 0x50]);
+
+        let read = |packing: &[u8]| {
+            let message = message(&[
+                (1, identification()),
+                (3, global_grid(3, 2, 120)),
+                (4, isobaric_product(2, 3, 25_000)),
+                (5, packing.to_vec()),
+                (6, vec![255]),
+                (7, data.clone()),
+            ]);
+
+            read_fields(&message, |_| true)
+        };
+
+        let fields = read(&packing).unwrap();
+        assert_eq!(fields[0].values, vec![1.0, 1.2, 1.5, 1.5, 1.4, 2.0]);
+
+        // A last group that's far longer than the field is rejected before it's read
+        packing[37..41].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(read(&packing).is_err());
+
+        assert!(read(&packing[..40]).is_err());
+    }
+
+    #[test]
+    fn malformed_messages() {
+        let read = |packing: Vec<u8>| {
+            let message = message(&[
+                (3, global_grid(3, 2, 120)),
+                (4, isobaric_product(2, 2, 25_000)),
+                (5, packing),
+                (6, vec![255]),
+                (7, vec![0; 6]),
+            ]);
+
+            read_fields(&message, |_| true)
+        };
+
+        assert!(read(simple_packing(6, 0.0)).is_ok());
+
+        // More values than the grid has points
+        assert!(read(simple_packing(u32::MAX, 0.0)).is_err());
+
+        // Complex packing without its template
+        let mut packing = simple_packing(6, 0.0);
+        packing[5] = 3;
+        assert!(read(packing).is_err());
+
+        let mut data = message(&[(3, global_grid(3, 2, 120))]);
+        data[8..16].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert!(read_fields(&data, |_| true).is_err());
+
+        let mut data = message(&[(3, global_grid(3, 2, 120))]);
+        data[16..20].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(read_fields(&data, |_| true).is_err());
+    }
+
+    #[test]
+    fn spatial_differencing() {
+        let mut values = vec![Some(0), Some(2), None, Some(3), Some(-1)];
+        undo_spatial_differencing(&mut values, &[5]);
+        assert_eq!(values, vec![Some(5), Some(7), None, Some(10), Some(9)]);
+    }
+
+    #[test]
+    fn grid_interpolation() {
+        let data = message(&[
+            (3, global_grid(4, 3, 90)),
+            (4, isobaric_product(2, 2, 25_000)),
+            (5, simple_packing(12, 0.0)),
+            (6, vec![255]),
+            (7, vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110]),
+        ]);
+
+        let field = read_fields(&data, |_| true).unwrap().remove(0);
+        let value = |lat, lon| {
+            field
+                .grid
+                .interpolate(&field.values, Position::new(lat, lon))
+        };
+
+        assert_eq!(value(90.0, 0.0), Some(0.0));
+        assert_eq!(value(0.0, 90.0), Some(50.0));
+        assert_eq!(value(0.0, -90.0), Some(70.0));
+        assert_eq!(value(45.0, 45.0), Some(25.0));
+
+        // Between the last column and the first
+        assert_eq!(value(0.0, 315.0), Some(55.0));
+        assert_eq!(value(-90.0, 180.0), Some(100.0));
+
+        // Just west of the first column, which rounds to a whole turn
+        assert_eq!(value(0.0, -1e-6), Some(40.0));
+    }
+}
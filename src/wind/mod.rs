@@ -0,0 +1,249 @@
+pub mod forecast;
+pub mod grib;
+
+#[cfg(test)]
+pub mod test_data;
+
+use crate::airport_data::Position;
+use crate::geo;
+use serde_derive::{Deserialize, Serialize};
+
+/// Anything that can give the wind at a position and altitude.
+pub trait WindField {
+    fn wind_at(&self, position: Position, altitude_ft: f32) -> Wind;
+}
+
+/// A wind at a single point.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Wind {
+    /// The true direction in degrees that the wind is blowing from.
+    pub direction: f32,
+    #[serde(rename = "speedKT")]
+    pub speed_kt: f32,
+}
+
+impl Wind {
+    pub const CALM: Self = Self {
+        direction: 0.0,
+        speed_kt: 0.0,
+    };
+
+    /// Create a wind from its eastward (`u`) and northward (`v`) components in knots.
+    pub fn from_components(u: f32, v: f32) -> Self {
+        let speed_kt = (u * u + v * v).sqrt();
+
+        if speed_kt < 1e-6 {
+            return Self::CALM;
+        }
+
+        // The components point to where the wind is blowing, rather than where it's from
+        let direction = geo::normalize_course((-u).atan2(-v).to_degrees());
+
+        Self {
+            direction,
+            speed_kt,
+        }
+    }
+
+    /// Get the eastward and northward components of the wind in knots.
+    pub fn components(self) -> (f32, f32) {
+        let direction = self.direction.to_radians();
+
+        (
+            -self.speed_kt * direction.sin(),
+            -self.speed_kt * direction.cos(),
+        )
+    }
+
+    /// Get the headwind and crosswind components in knots for an aircraft on `course_deg`.
+    ///
+    /// A negative headwind is a tailwind, and a positive crosswind blows from the right.
+    pub fn relative_to(self, course_deg: f32) -> (f32, f32) {
+        let angle = (self.direction - course_deg).to_radians();
+        (self.speed_kt * angle.cos(), self.speed_kt * angle.sin())
+    }
+
+    /// Get the ground speed in knots for an aircraft flying at `tas_kt` while correcting
+    /// for the wind to stay on `course_deg`.
+    ///
+    /// Returns `None` when the wind is too strong for the aircraft to make any progress.
+    pub fn ground_speed_kt(self, tas_kt: f32, course_deg: f32) -> Option<f32> {
+        let (headwind, crosswind) = self.relative_to(course_deg);
+
+        if crosswind.abs() >= tas_kt {
+            return None;
+        }
+
+        let correction_angle = (crosswind / tas_kt).asin();
+        let ground_speed = tas_kt * correction_angle.cos() - headwind;
+
+        if ground_speed > 0.0 {
+            Some(ground_speed)
+        } else {
+            None
+        }
+    }
+}
+
+/// A wind at an altitude, such as from a winds aloft forecast.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq)]
+pub struct WindLevel {
+    #[serde(rename = "altitudeFT")]
+    pub altitude_ft: f32,
+    #[serde(flatten)]
+    pub wind: Wind,
+}
+
+/// Winds at several altitudes that are the same everywhere along a route.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(from = "Vec<WindLevel>")]
+pub struct WindsAloft {
+    /// The levels in the order they were given, which don't need to be sorted.
+    levels: Vec<WindLevel>,
+}
+
+impl WindsAloft {
+    #[inline(always)]
+    pub fn new(levels: Vec<WindLevel>) -> Self {
+        Self { levels }
+    }
+
+    /// Get the wind at an altitude by interpolating between the closest levels above and below it.
+    ///
+    /// Altitudes outside of the levels get the wind of the closest level.
+    pub fn wind_at(&self, altitude_ft: f32) -> Wind {
+        let mut lower: Option<&WindLevel> = None;
+        let mut upper: Option<&WindLevel> = None;
+
+        for level in &self.levels {
+            if level.altitude_ft <= altitude_ft {
+                match lower {
+                    Some(closest) if closest.altitude_ft >= level.altitude_ft => (),
+                    _ => lower = Some(level),
+                }
+            }
+
+            if level.altitude_ft >= altitude_ft {
+                match upper {
+                    Some(closest) if closest.altitude_ft <= level.altitude_ft => (),
+                    _ => upper = Some(level),
+                }
+            }
+        }
+
+        let (lower, upper) = match (lower, upper) {
+            (Some(lower), Some(upper)) if upper.altitude_ft > lower.altitude_ft => (lower, upper),
+            (Some(level), _) | (None, Some(level)) => return level.wind,
+            (None, None) => return Wind::CALM,
+        };
+
+        let fraction = (altitude_ft - lower.altitude_ft) / (upper.altitude_ft - lower.altitude_ft);
+        let (lower_u, lower_v) = lower.wind.components();
+        let (upper_u, upper_v) = upper.wind.components();
+
+        Wind::from_components(
+            lower_u + fraction * (upper_u - lower_u),
+            lower_v + fraction * (upper_v - lower_v),
+        )
+    }
+
+    #[inline(always)]
+    pub fn levels(&self) -> &[WindLevel] {
+        &self.levels
+    }
+}
+
+impl WindField for Wind {
+    #[inline(always)]
+    fn wind_at(&self, _position: Position, _altitude_ft: f32) -> Wind {
+        *self
+    }
+}
+
+impl WindField for WindsAloft {
+    #[inline(always)]
+    fn wind_at(&self, _position: Position, altitude_ft: f32) -> Wind {
+        WindsAloft::wind_at(self, altitude_ft)
+    }
+}
+
+impl From<Vec<WindLevel>> for WindsAloft {
+    fn from(levels: Vec<WindLevel>) -> Self {
+        Self::new(levels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 0.01,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    fn wind(direction: f32, speed_kt: f32) -> Wind {
+        Wind {
+            direction,
+            speed_kt,
+        }
+    }
+
+    #[test]
+    fn wind_components() {
+        // A westerly wind blows to the east
+        let (u, v) = wind(270.0, 20.0).components();
+        assert_close(u, 20.0);
+        assert_close(v, 0.0);
+
+        let from = Wind::from_components(-10.0, -10.0);
+        assert_close(from.direction, 45.0);
+        assert_close(from.speed_kt, 200f32.sqrt());
+
+        assert_eq!(Wind::from_components(0.0, 0.0), Wind::CALM);
+
+        let (headwind, crosswind) = wind(300.0, 20.0).relative_to(270.0);
+        assert_close(headwind, 17.32);
+        assert_close(crosswind, 10.0);
+    }
+
+    #[test]
+    fn wind_ground_speed() {
+        let ground_speed = |wind: Wind, course| wind.ground_speed_kt(100.0, course);
+
+        assert_close(ground_speed(wind(270.0, 20.0), 270.0).unwrap(), 80.0);
+        assert_close(ground_speed(wind(270.0, 20.0), 90.0).unwrap(), 120.0);
+
+        // Correcting for a direct crosswind costs some speed
+        assert_close(ground_speed(wind(360.0, 20.0), 90.0).unwrap(), 97.98);
+
+        assert_eq!(ground_speed(wind(270.0, 100.0), 270.0), None);
+        assert_eq!(ground_speed(wind(360.0, 150.0), 90.0), None);
+    }
+
+    #[test]
+    fn winds_aloft_interpolation() {
+        let aloft = WindsAloft::new(vec![
+            WindLevel {
+                altitude_ft: 30_000.0,
+                wind: wind(270.0, 80.0),
+            },
+            WindLevel {
+                altitude_ft: 6_000.0,
+                wind: wind(270.0, 20.0),
+            },
+        ]);
+
+        assert_eq!(aloft.levels()[0].altitude_ft, 30_000.0);
+        assert_eq!(aloft.wind_at(0.0), wind(270.0, 20.0));
+        assert_eq!(aloft.wind_at(40_000.0), wind(270.0, 80.0));
+
+        let between = aloft.wind_at(18_000.0);
+        assert_close(between.direction, 270.0);
+        assert_close(between.speed_kt, 50.0);
+    }
+}
@@ -0,0 +1,66 @@
+use super::grib::Product;
+
+/// Build a GRIB2 message out of the contents of its sections, without their length and number.
+pub fn message(sections: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    for (number, contents) in sections {
+        body.extend_from_slice(&(contents.len() as u32 + 5).to_be_bytes());
+        body.push(*number);
+        body.extend_from_slice(contents);
+    }
+
+    let length = 16 + body.len() + 4;
+    let mut message = b"GRIB\0\0\0\x02".to_vec();
+    message.extend_from_slice(&(length as u64).to_be_bytes());
+    message.extend(body);
+    message.extend_from_slice(b"7777");
+    message
+}
+
+pub fn identification() -> Vec<u8> {
+    let mut section = vec![0, 7, 0, 0, 2, 1, 1];
+    section.extend_from_slice(&2020u16.to_be_bytes());
+    section.extend_from_slice(&[9, 14, 6, 0, 0, 0, 1]);
+    section
+}
+
+/// A global grid starting at 90 N and 0 E, going east then south.
+pub fn global_grid(ni: u32, nj: u32, step_deg: u32) -> Vec<u8> {
+    let mut section = vec![0];
+    section.extend_from_slice(&(ni * nj).to_be_bytes());
+    section.extend_from_slice(&[0, 0, 0, 0, 6]);
+    section.extend_from_slice(&[0; 15]);
+    section.extend_from_slice(&ni.to_be_bytes());
+    section.extend_from_slice(&nj.to_be_bytes());
+    section.extend_from_slice(&[0; 8]);
+    section.extend_from_slice(&90_000_000u32.to_be_bytes());
+    section.extend_from_slice(&0u32.to_be_bytes());
+    section.push(48);
+    // The last point, which is 90 S written with a sign bit
+    section.extend_from_slice(&(0x8000_0000u32 | 90_000_000).to_be_bytes());
+    section.extend_from_slice(&(360_000_000 - step_deg * 1_000_000).to_be_bytes());
+    section.extend_from_slice(&(step_deg * 1_000_000).to_be_bytes());
+    section.extend_from_slice(&(step_deg * 1_000_000).to_be_bytes());
+    section.push(0);
+    section
+}
+
+/// A product on an isobaric surface, forecast 6 hours ahead.
+pub fn isobaric_product(category: u8, number: u8, pressure_pa: u32) -> Vec<u8> {
+    let mut section = vec![0, 0, 0, 0, category, number, 2, 0, 96, 0, 0, 0, 1];
+    section.extend_from_slice(&6u32.to_be_bytes());
+    section.extend_from_slice(&[Product::ISOBARIC_SURFACE, 0]);
+    section.extend_from_slice(&pressure_pa.to_be_bytes());
+    section.extend_from_slice(&[255, 0, 0, 0, 0, 0, 0]);
+    section
+}
+
+/// Simple packing of whole numbers starting from `reference` with 8 bits per value.
+pub fn simple_packing(count: u32, reference: f32) -> Vec<u8> {
+    let mut section = count.to_be_bytes().to_vec();
+    section.extend_from_slice(&0u16.to_be_bytes());
+    section.extend_from_slice(&reference.to_bits().to_be_bytes());
+    section.extend_from_slice(&[0, 0, 0, 0, 8, 0]);
+    section
+}